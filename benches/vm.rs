@@ -0,0 +1,99 @@
+// times the bytecode vm against the ast interpreter on the same programs:
+//
+//     cargo bench --bench vm
+//
+// neither prints anything, and every program stays well under the
+// interpreter's call depth
+use pipes::bytecode::{self, Vm};
+use pipes::interpreter::Interpreter;
+use pipes::parser::parse;
+use pipes::tokenizer::tokenizer::tokenize;
+use pipes::typeck::check;
+use std::io;
+use std::time::{Duration, Instant};
+
+const RUNS: u32 = 10;
+
+const PROGRAMS: [(&str, &str); 4] = [
+    (
+        "fib",
+        "func fib(n: int) -> int {
+            if (n < 2) { return n; }
+            return fib(n - 1) + fib(n - 2);
+        }
+        fib(25)",
+    ),
+    (
+        "while loop",
+        "let i: int = 0;
+        let total: int = 0;
+        while (i < 1000000) {
+            if (i % 3 == 0 || i % 5 == 0) { total = total + i; }
+            i = i + 1;
+        }
+        total",
+    ),
+    (
+        "for loop",
+        "let xs: [float] = [1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5, 8.5, 9.5, 10.5];
+        let total: float = 0.0;
+        let i: int = 0;
+        while (i < 50000) {
+            for x in xs { total = total + x * 2.0; }
+            i = i + 1;
+        }
+        total",
+    ),
+    (
+        "closures",
+        "func make_adder(n: int) -> (int -> int) {
+            return (a: int) { return a + n; };
+        }
+        let add: (int -> int) = make_adder(1);
+        let total: int = 0;
+        let i: int = 0;
+        while (i < 200000) {
+            total = make_adder(i)(total) |> add;
+            i = i + 1;
+        }
+        total",
+    ),
+];
+
+// the fastest of a few runs, which is the one the least noise got into
+fn time<T>(mut run: impl FnMut() -> T) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    println!(
+        "{:<12} {:>14} {:>14} {:>8}",
+        "program", "interpreter", "vm", "speedup"
+    );
+    for (name, source) in PROGRAMS.iter() {
+        let mut program = parse(tokenize(source).unwrap()).unwrap();
+        let structs = check(&mut program).unwrap();
+        let compiled = bytecode::compile(&program, &structs);
+
+        let expected = Interpreter::with_output(io::sink()).run(&program).unwrap();
+        let found = Vm::with_output(io::sink()).run(&compiled).unwrap();
+        assert_eq!(found.to_string(), expected.to_string(), "{}", name);
+
+        let interpreter = time(|| Interpreter::with_output(io::sink()).run(&program).unwrap());
+        let vm = time(|| Vm::with_output(io::sink()).run(&compiled).unwrap());
+        println!(
+            "{:<12} {:>14?} {:>14?} {:>7.1}x",
+            name,
+            interpreter,
+            vm,
+            interpreter.as_secs_f64() / vm.as_secs_f64()
+        );
+    }
+}
@@ -1,11 +1,7 @@
 mod exp;
+mod program;
 mod statement;
 
 pub use exp::*;
+pub use program::*;
 pub use statement::*;
-
-pub enum AST {
-	StmtNode(Statement, Box<AST>),
-	ExpNode(Exp, Box<AST>),
-	Leaf,
-}
@@ -1,27 +1,128 @@
+use crate::ast::Statement;
+use crate::span::Span;
 use crate::types::Type;
+use num_bigint::BigInt;
+use std::fmt;
 
+#[derive(Debug, PartialEq, Clone)]
 pub enum Exp {
     Var(Box<Var>),
-    Int(i32),
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
     Str(String),
     Bool(bool),
+    Void, // ()
 
-	Call(String, Vec<Var>, Box<Var>),
-    Negate(Box<Exp>),
+    Call(Box<Exp>, Vec<Exp>), // callee(args)
+    Unary(UnaryOp, Box<Exp>),
     Op(Operation, Box<Exp>, Box<Exp>),
+    // left |> f(args) calls f(left, args), and left |> f calls f(left)
+    Pipe(Box<Exp>, Box<Exp>),
 
     // the hard stuff
     Struct(String, Vec<Var>),
-    List(Type, Vec<Exp>),
-    HOF(Box<Var>, Box<Exp>),
+    Field(Box<Exp>, String), // s.field
+    List(Option<Type>, Vec<Exp>),
+    HOF(Vec<Var>, Vec<Statement>),
 }
 
+impl Exp {
+    // what to call this expression in an error message about calling it
+    pub fn callee_name(&self) -> String {
+        match self {
+            Exp::Var(var) => var.name.clone(),
+            Exp::Field(_, field) => field.clone(),
+            _ => String::from("expression"),
+        }
+    }
+    // fills in what a declared type tells us about this expression: the name
+    // of an anonymous struct, the type of a list, or the parameter types of a
+    // function
+    pub fn apply_declared_type(&mut self, declared: &Type) {
+        match (declared, self) {
+            (Type::Struct(name), Exp::Struct(struct_name, _)) if struct_name.is_empty() => {
+                *struct_name = name.clone();
+            }
+            (Type::List(element), Exp::List(list_type, items)) => {
+                // nested list and struct literals take their type from the element type
+                for item in items.iter_mut() {
+                    item.apply_declared_type(element);
+                }
+                if list_type.is_none() {
+                    *list_type = Some(declared.clone());
+                }
+            }
+            (Type::HigherOrderFunction(param_types, _), Exp::HOF(params, _))
+                if params.len() == param_types.len() =>
+            {
+                for (param, param_type) in params.iter_mut().zip(param_types) {
+                    if param.var_type.is_none() {
+                        param.var_type = Some(param_type.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// var_type is None until it is known, either from an annotation or a later pass
+#[derive(Debug, PartialEq, Clone)]
 pub struct Var {
-    name: String,
-    var_type: Type,
-    value: Option<Exp>,
+    pub name: String,
+    pub var_type: Option<Type>,
+    pub value: Option<Exp>,
+    pub span: Span,
+}
+
+impl Var {
+    pub fn new(name: &str, var_type: Option<Type>) -> Var {
+        Var {
+            name: String::from(name),
+            var_type,
+            value: None,
+            span: Span::default(),
+        }
+    }
+
+    pub fn spanned(name: &str, var_type: Option<Type>, span: Span) -> Var {
+        Var {
+            span,
+            ..Var::new(name, var_type)
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum UnaryOp {
+    Neg, // -exp
+    Not, // !exp
 }
 
+impl UnaryOp {
+    // the type of `op exp`, or None if the operand doesn't fit the op
+    pub fn result_type(self, operand: &Type) -> Option<Type> {
+        match (self, operand) {
+            (UnaryOp::Neg, Type::Int)
+            | (UnaryOp::Neg, Type::BigInt)
+            | (UnaryOp::Neg, Type::Float) => Some(operand.clone()),
+            (UnaryOp::Not, Type::Bool) => Some(Type::Bool),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnaryOp::Neg => write!(f, "-"),
+            UnaryOp::Not => write!(f, "!"),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum Operation {
     Add,
     Subtract,
@@ -39,3 +140,181 @@ pub enum Operation {
     Equal,
     NotEqual,
 }
+
+impl Operation {
+    // the type of `left op right`, or None if the operands don't fit the op.
+    // arithmetic on an int and a float promotes the int to a float, and on an
+    // int and a bigint promotes the int to a bigint. bigints and floats don't mix
+    pub fn result_type(self, left: &Type, right: &Type) -> Option<Type> {
+        use Operation::*;
+
+        match self {
+            Add | Subtract | Multiply | Divide | Modulo => match (left, right) {
+                (Type::Int, Type::Int) => Some(Type::Int),
+                (Type::BigInt, Type::BigInt)
+                | (Type::Int, Type::BigInt)
+                | (Type::BigInt, Type::Int) => Some(Type::BigInt),
+                (Type::Float, Type::Float)
+                | (Type::Int, Type::Float)
+                | (Type::Float, Type::Int) => Some(Type::Float),
+                _ => None,
+            },
+            And | Or => match (left, right) {
+                (Type::Bool, Type::Bool) => Some(Type::Bool),
+                _ => None,
+            },
+            GreaterThan | LessThan | GreaterEqual | LessEqual => match (left, right) {
+                (Type::Int, Type::Int)
+                | (Type::BigInt, Type::BigInt)
+                | (Type::Int, Type::BigInt)
+                | (Type::BigInt, Type::Int)
+                | (Type::Float, Type::Float)
+                | (Type::Int, Type::Float)
+                | (Type::Float, Type::Int) => Some(Type::Bool),
+                _ => None,
+            },
+            Equal | NotEqual => match (left, right) {
+                (Type::Int, Type::Float)
+                | (Type::Float, Type::Int)
+                | (Type::Int, Type::BigInt)
+                | (Type::BigInt, Type::Int) => Some(Type::Bool),
+                _ if left == right => Some(Type::Bool),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Operation::Add => "+",
+            Operation::Subtract => "-",
+            Operation::Divide => "/",
+            Operation::Multiply => "*",
+            Operation::Modulo => "%",
+            Operation::And => "&&",
+            Operation::Or => "||",
+            Operation::GreaterThan => ">",
+            Operation::LessThan => "<",
+            Operation::GreaterEqual => ">=",
+            Operation::LessEqual => "<=",
+            Operation::Equal => "==",
+            Operation::NotEqual => "!=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn int_arithmetic_stays_int() {
+        assert_eq!(
+            Operation::Add.result_type(&Type::Int, &Type::Int),
+            Some(Type::Int)
+        )
+    }
+
+    #[test]
+    fn mixed_arithmetic_promotes_to_float() {
+        for op in &[
+            Operation::Add,
+            Operation::Subtract,
+            Operation::Multiply,
+            Operation::Divide,
+            Operation::Modulo,
+        ] {
+            assert_eq!(op.result_type(&Type::Int, &Type::Float), Some(Type::Float));
+            assert_eq!(op.result_type(&Type::Float, &Type::Int), Some(Type::Float));
+            assert_eq!(
+                op.result_type(&Type::Float, &Type::Float),
+                Some(Type::Float)
+            );
+        }
+    }
+
+    #[test]
+    fn mixed_arithmetic_promotes_to_bigint() {
+        assert_eq!(
+            Operation::Multiply.result_type(&Type::Int, &Type::BigInt),
+            Some(Type::BigInt)
+        );
+        assert_eq!(
+            Operation::Subtract.result_type(&Type::BigInt, &Type::Int),
+            Some(Type::BigInt)
+        );
+        assert_eq!(
+            Operation::GreaterThan.result_type(&Type::BigInt, &Type::Int),
+            Some(Type::Bool)
+        )
+    }
+
+    #[test]
+    fn bigints_and_floats_dont_mix() {
+        assert_eq!(
+            Operation::Add.result_type(&Type::BigInt, &Type::Float),
+            None
+        );
+        assert_eq!(
+            Operation::Equal.result_type(&Type::Float, &Type::BigInt),
+            None
+        )
+    }
+
+    #[test]
+    fn mixed_comparison_is_bool() {
+        assert_eq!(
+            Operation::LessThan.result_type(&Type::Float, &Type::Int),
+            Some(Type::Bool)
+        );
+        assert_eq!(
+            Operation::Equal.result_type(&Type::Int, &Type::Float),
+            Some(Type::Bool)
+        )
+    }
+
+    #[test]
+    fn arithmetic_rejects_non_numbers() {
+        assert_eq!(Operation::Add.result_type(&Type::Float, &Type::Str), None);
+        assert_eq!(
+            Operation::Multiply.result_type(&Type::Bool, &Type::Int),
+            None
+        )
+    }
+
+    #[test]
+    fn logic_needs_bools() {
+        assert_eq!(
+            Operation::And.result_type(&Type::Bool, &Type::Bool),
+            Some(Type::Bool)
+        );
+        assert_eq!(Operation::Or.result_type(&Type::Float, &Type::Bool), None)
+    }
+
+    #[test]
+    fn neg_needs_a_number() {
+        for t in &[Type::Int, Type::BigInt, Type::Float] {
+            assert_eq!(UnaryOp::Neg.result_type(t), Some(t.clone()));
+        }
+        assert_eq!(UnaryOp::Neg.result_type(&Type::Bool), None);
+        assert_eq!(UnaryOp::Neg.result_type(&Type::Str), None)
+    }
+
+    #[test]
+    fn not_needs_a_bool() {
+        assert_eq!(UnaryOp::Not.result_type(&Type::Bool), Some(Type::Bool));
+        assert_eq!(UnaryOp::Not.result_type(&Type::Int), None)
+    }
+
+    #[test]
+    fn equality_needs_matching_types() {
+        assert_eq!(
+            Operation::NotEqual.result_type(&Type::Str, &Type::Str),
+            Some(Type::Bool)
+        );
+        assert_eq!(Operation::Equal.result_type(&Type::Str, &Type::Int), None)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::ast::{Exp, Function, Statement, StructDef, Var};
+
+// a whole source file. functions and structs can be used anywhere in the
+// program, while globals and statements run from top to bottom
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program {
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Item {
+    Function(Function),
+    Struct(StructDef),
+    Global(Var, Exp), // let name: type = exp;
+    Statement(Statement),
+}
+
+impl Program {
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Function(function) => Some(function),
+            _ => None,
+        })
+    }
+
+    pub fn structs(&self) -> impl Iterator<Item = &StructDef> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Struct(def) => Some(def),
+            _ => None,
+        })
+    }
+
+    pub fn globals(&self) -> impl Iterator<Item = (&Var, &Exp)> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Global(var, exp) => Some((var, exp)),
+            _ => None,
+        })
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions().find(|function| function.name == name)
+    }
+
+    pub fn struct_def(&self, name: &str) -> Option<&StructDef> {
+        self.structs().find(|def| def.name == name)
+    }
+
+    // the first declaration of a global, if it was declared more than once
+    pub fn global(&self, name: &str) -> Option<(&Var, &Exp)> {
+        self.globals().find(|(var, _)| var.name == name)
+    }
+}
+
+// a statement at the top level of a file: function definitions and lets
+// become their own items
+impl From<Statement> for Item {
+    fn from(stmt: Statement) -> Item {
+        match stmt {
+            Statement::Function(function) => Item::Function(function),
+            Statement::Assignment(var, exp) if var.var_type.is_some() => Item::Global(var, exp),
+            stmt => Item::Statement(stmt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+    use crate::types::Type;
+
+    fn program() -> Program {
+        let function = Function {
+            name: String::from("f"),
+            param: vec![],
+            output: Var::new("f", Some(Type::Void)),
+            block: vec![],
+            span: Span::default(),
+        };
+        let point = StructDef {
+            name: String::from("Point"),
+            fields: vec![Var::new("x", Some(Type::Int))],
+            span: Span::default(),
+        };
+        Program {
+            items: vec![
+                Item::from(Statement::Assignment(
+                    Var::new("x", Some(Type::Int)),
+                    Exp::Int(1),
+                )),
+                Item::from(Statement::Function(function)),
+                Item::Struct(point),
+                Item::from(Statement::Assignment(Var::new("x", None), Exp::Int(2))),
+            ],
+        }
+    }
+
+    #[test]
+    fn statements_become_items() {
+        let items = program().items;
+        assert!(matches!(items[0], Item::Global(..)));
+        assert!(matches!(items[1], Item::Function(_)));
+        // reassigning a global is just a statement
+        assert!(matches!(
+            items[3],
+            Item::Statement(Statement::Assignment(..))
+        ));
+    }
+
+    #[test]
+    fn look_up_items_by_name() {
+        let program = program();
+        assert_eq!(program.function("f").map(|f| f.block.len()), Some(0));
+        assert_eq!(program.function("x"), None);
+        assert_eq!(
+            program.struct_def("Point").map(|def| def.fields.len()),
+            Some(1)
+        );
+        assert_eq!(program.global("x").map(|(_, exp)| exp), Some(&Exp::Int(1)));
+        assert_eq!(program.global("f"), None);
+    }
+}
@@ -1,35 +1,67 @@
-use crate::types::Type;
 use crate::ast::{Exp, Var};
+use crate::span::Span;
 
+#[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
     Assignment(Var, Exp),
-	Function(Function),
-    Return(Exp),
+    Function(Function),
+    Return(Exp, Span),
     If(IfStatement),
     For(ForStatement),
     While(WhileStatement),
+    Exp(Exp, Span), // foo(x, y);
 }
 
+impl Statement {
+    // for a let or assignment, the span of the variable
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Assignment(var, _) => var.span,
+            Statement::Function(function) => function.span,
+            Statement::Return(_, span) | Statement::Exp(_, span) => *span,
+            Statement::If(if_stmt) => if_stmt.span,
+            Statement::For(for_stmt) => for_stmt.span,
+            Statement::While(while_stmt) => while_stmt.span,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Function {
-	name: String,
-	param: Vec<Var>,
-	output: Var,
-	block: Vec<Statement>,
+    pub name: String,
+    pub param: Vec<Var>,
+    pub output: Var,
+    pub block: Vec<Statement>,
+    pub span: Span,
+}
+
+// struct name { field: type, }, only allowed at the top level
+#[derive(Debug, PartialEq, Clone)]
+pub struct StructDef {
+    pub name: String,
+    pub fields: Vec<Var>,
+    pub span: Span,
 }
 
+#[derive(Debug, PartialEq, Clone)]
 pub struct IfStatement {
-    condition: Exp,
-    true_branch: Vec<Statement>,
-    false_branch: Option<Vec<Statement>>,
+    pub condition: Exp,
+    pub true_branch: Vec<Statement>,
+    pub false_branch: Option<Vec<Statement>>,
+    pub span: Span,
 }
 
+#[derive(Debug, PartialEq, Clone)]
 pub struct ForStatement {
-    iter: Var,
-    list: (Type, Vec<Exp>),
-    block: Vec<Statement>,
+    pub iter: Var,
+    pub iterable: Exp, // anything of list type
+    pub block: Vec<Statement>,
+    pub span: Span,
 }
 
+#[derive(Debug, PartialEq, Clone)]
 pub struct WhileStatement {
-    condition: Exp,
-    block: Vec<Statement>,
+    pub condition: Exp,
+    pub block: Vec<Statement>,
+    pub span: Span,
 }
@@ -0,0 +1,183 @@
+// programs compiled to a compact instruction set, which the vm runs a lot
+// faster than the interpreter walks the ast. every function is a flat list
+// of instructions working on a stack of values: its locals sit in slots at
+// the bottom of its frame, and globals are looked up by name like the
+// interpreter does, so they can be used before they're defined
+mod compiler;
+mod value;
+mod vm;
+
+pub use compiler::compile;
+pub use value::{Closure, Struct, Value};
+pub use vm::Vm;
+
+use crate::ast::{Operation, UnaryOp};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Op {
+    // pushes a value from the constant pool
+    Constant(u32),
+    Void,
+    True,
+    False,
+    Pop,
+    // swaps the top two values, so a pipe's callee can go under its left side
+    Swap,
+
+    // locals, by slot. define starts a new variable, set changes one
+    GetLocal(u32),
+    SetLocal(u32),
+    DefineLocal(u32),
+    // the same for locals some closure captures, which live in cells
+    GetCell(u32),
+    SetCell(u32),
+    DefineCell(u32),
+    // the running closure's captures
+    GetCapture(u32),
+    SetCapture(u32),
+    // globals, by name
+    GetGlobal(u32),
+    SetGlobal(u32),
+    DefineGlobal(u32),
+
+    Unary(UnaryOp),
+    // never && or ||, which jump instead
+    Binary(Operation),
+
+    Jump(u32),
+    // these pop the condition, which has to be a bool
+    JumpIfFalse(u32),
+    JumpIfTrue(u32),
+    // pops a list into a slot and starts counting from 0 in the next one
+    Iterate(u32),
+    // pushes the next item of the list in a slot, or jumps when there are none
+    Next(u32, u32),
+
+    // calls the value under this many arguments, naming it after a name
+    // when it isn't a function
+    Call(u32, u32),
+    Return,
+    // makes a closure of a function, capturing what the function says to
+    Closure(u32),
+    // builds a list of this many values
+    List(u32),
+    // builds a struct from the values of its fields
+    Struct(u32),
+    Field(u32),
+}
+
+// where a closure's capture comes from when it's made
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Capture {
+    // a local of the function making it
+    Local(u32),
+    // one of the making closure's own captures
+    Capture(u32),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Function {
+    pub name: String,
+    pub params: u32,
+    // params and locals, all of them slots in the function's frame
+    pub slots: u32,
+    // the params some closure captures, which go in cells when it's called
+    pub cells: Vec<u32>,
+    pub captures: Vec<Capture>,
+    pub code: Vec<Op>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bytecode {
+    pub constants: Vec<Value>,
+    // globals, fields and callees, by name
+    pub names: Vec<String>,
+    // a struct literal's name, its field names in the order the struct
+    // declares them, and where in that order each value goes, in the order
+    // the values are pushed
+    pub structs: Vec<(String, Vec<String>, Vec<usize>)>,
+    // every function in the program, starting with its top level
+    pub functions: Vec<Function>,
+}
+
+// ----------- disassembler ---------- \\
+impl fmt::Display for Bytecode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, function) in self.functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "function {} {} ({} params, {} slots)",
+                i, function.name, function.params, function.slots
+            )?;
+            if !function.cells.is_empty() {
+                writeln!(f, "    cells {:?}", function.cells)?;
+            }
+            if !function.captures.is_empty() {
+                writeln!(f, "    captures {:?}", function.captures)?;
+            }
+            for (ip, op) in function.code.iter().enumerate() {
+                let (name, note) = self.describe(*op);
+                if note.is_empty() {
+                    writeln!(f, "{:>6}  {}", ip, name)?;
+                } else {
+                    writeln!(f, "{:>6}  {:<20} ; {}", ip, name, note)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Bytecode {
+    // an instruction and what its operands refer to
+    fn describe(&self, op: Op) -> (String, String) {
+        let name = |i: u32| self.names[i as usize].clone();
+        let plain = |text: &str| (String::from(text), String::new());
+        let with = |text: &str, i: u32| format!("{} {}", text, i);
+        match op {
+            Op::Constant(i) => (
+                with("constant", i),
+                format!("{:?}", self.constants[i as usize]),
+            ),
+            Op::Void => plain("void"),
+            Op::True => plain("true"),
+            Op::False => plain("false"),
+            Op::Pop => plain("pop"),
+            Op::Swap => plain("swap"),
+            Op::GetLocal(slot) => (with("get_local", slot), String::new()),
+            Op::SetLocal(slot) => (with("set_local", slot), String::new()),
+            Op::DefineLocal(slot) => (with("define_local", slot), String::new()),
+            Op::GetCell(slot) => (with("get_cell", slot), String::new()),
+            Op::SetCell(slot) => (with("set_cell", slot), String::new()),
+            Op::DefineCell(slot) => (with("define_cell", slot), String::new()),
+            Op::GetCapture(i) => (with("get_capture", i), String::new()),
+            Op::SetCapture(i) => (with("set_capture", i), String::new()),
+            Op::GetGlobal(i) => (with("get_global", i), name(i)),
+            Op::SetGlobal(i) => (with("set_global", i), name(i)),
+            Op::DefineGlobal(i) => (with("define_global", i), name(i)),
+            Op::Unary(op) => (format!("unary {}", op), String::new()),
+            Op::Binary(op) => (format!("binary {}", op), String::new()),
+            Op::Jump(to) => (with("jump", to), String::new()),
+            Op::JumpIfFalse(to) => (with("jump_if_false", to), String::new()),
+            Op::JumpIfTrue(to) => (with("jump_if_true", to), String::new()),
+            Op::Iterate(slot) => (with("iterate", slot), String::new()),
+            Op::Next(slot, to) => (format!("next {} {}", slot, to), String::new()),
+            Op::Call(args, callee) => (with("call", args), name(callee)),
+            Op::Return => plain("return"),
+            Op::Closure(i) => (with("closure", i), self.functions[i as usize].name.clone()),
+            Op::List(len) => (with("list", len), String::new()),
+            Op::Struct(i) => {
+                let (struct_name, fields, _) = &self.structs[i as usize];
+                (
+                    with("struct", i),
+                    format!("{} {{{}}}", struct_name, fields.join(", ")),
+                )
+            }
+            Op::Field(i) => (with("field", i), name(i)),
+        }
+    }
+}
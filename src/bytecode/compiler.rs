@@ -0,0 +1,634 @@
+use super::{Bytecode, Capture, Function, Op, Value};
+use crate::ast::*;
+use crate::types::StructRegistry;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+// compiles a parsed program, which doesn't have to be type checked: the vm
+// stops on whatever the interpreter would. names are resolved where they're
+// written, so unlike the interpreter a lambda can't see locals declared
+// after it. structs are the ones the program declares, which struct values
+// keep their fields in the order of
+pub fn compile(program: &Program, structs: &StructRegistry) -> Bytecode {
+    let mut compiler = Compiler {
+        structs: structs.clone(),
+        ..Compiler::default()
+    };
+    compiler.program(program);
+    compiler.bytecode
+}
+
+// a block scope's names and the first slot it took
+struct Scope {
+    names: HashMap<String, u32>,
+    start: u32,
+}
+
+// where a name was found, from the function being compiled
+enum Resolved {
+    Local(u32),
+    Capture(u32),
+}
+
+// a function being compiled
+struct Frame {
+    index: usize,
+    code: Vec<Op>,
+    // innermost last. the top level's outermost scope is the globals, which
+    // aren't in here
+    scopes: Vec<Scope>,
+    next_slot: u32,
+    slots: u32,
+    params: u32,
+    // the slots some closure captures, which have to live in cells
+    captured: HashSet<u32>,
+    captures: Vec<(String, Capture)>,
+}
+
+#[derive(Default)]
+struct Compiler {
+    bytecode: Bytecode,
+    structs: StructRegistry,
+    frames: Vec<Frame>,
+    // where each constant and name already is, so they're only stored once
+    constants: HashMap<String, u32>,
+    names: HashMap<String, u32>,
+}
+
+impl Compiler {
+    // ----------- items ---------- \\
+    fn program(&mut self, program: &Program) {
+        self.open("<top level>", &[]);
+        // top level functions can be called before they're defined
+        for function in program.functions() {
+            let index = self.function(&function.name, &function.param, &function.block);
+            self.emit(Op::Closure(index));
+            let name = self.name(&function.name);
+            self.emit(Op::DefineGlobal(name));
+        }
+
+        // the program gives back the value of its last item, when that's an
+        // expression
+        for (i, item) in program.items.iter().enumerate() {
+            match item {
+                Item::Function(_) | Item::Struct(_) => {}
+                Item::Global(var, exp) => {
+                    self.exp(exp);
+                    let name = self.name(&var.name);
+                    self.emit(Op::DefineGlobal(name));
+                }
+                Item::Statement(Statement::Exp(exp, _)) => {
+                    self.exp(exp);
+                    if i == program.items.len() - 1 {
+                        self.emit(Op::Return);
+                    } else {
+                        self.emit(Op::Pop);
+                    }
+                }
+                Item::Statement(stmt) => self.stmt(stmt),
+            }
+        }
+        self.close();
+    }
+
+    // compiles a function or lambda into a function of its own, giving
+    // back its index
+    fn function(&mut self, name: &str, params: &[Var], block: &[Statement]) -> u32 {
+        let index = self.open(name, params);
+        self.stmts(block);
+        self.close();
+        index as u32
+    }
+
+    fn open(&mut self, name: &str, params: &[Var]) -> usize {
+        let index = self.bytecode.functions.len();
+        self.bytecode.functions.push(Function {
+            name: String::from(name),
+            params: params.len() as u32,
+            slots: 0,
+            cells: vec![],
+            captures: vec![],
+            code: vec![],
+        });
+        let mut scopes = vec![];
+        if !self.frames.is_empty() {
+            let names = params
+                .iter()
+                .enumerate()
+                .map(|(slot, param)| (param.name.clone(), slot as u32))
+                .collect();
+            scopes.push(Scope { names, start: 0 });
+        }
+        self.frames.push(Frame {
+            index,
+            code: vec![],
+            scopes,
+            next_slot: params.len() as u32,
+            slots: params.len() as u32,
+            params: params.len() as u32,
+            captured: HashSet::new(),
+            captures: vec![],
+        });
+        index
+    }
+
+    // finishes the innermost function. every use of a slot a closure
+    // captured becomes a use of its cell
+    fn close(&mut self) {
+        self.emit(Op::Void);
+        self.emit(Op::Return);
+        let frame = self.frames.pop().unwrap();
+        let captured = &frame.captured;
+        let params = frame.params;
+        let code = frame
+            .code
+            .into_iter()
+            .map(|op| match op {
+                Op::GetLocal(slot) if captured.contains(&slot) => Op::GetCell(slot),
+                Op::SetLocal(slot) if captured.contains(&slot) => Op::SetCell(slot),
+                Op::DefineLocal(slot) if captured.contains(&slot) => Op::DefineCell(slot),
+                op => op,
+            })
+            .collect();
+        let mut cells: Vec<u32> = captured
+            .iter()
+            .copied()
+            .filter(|slot| *slot < params)
+            .collect();
+        cells.sort_unstable();
+
+        let function = &mut self.bytecode.functions[frame.index];
+        function.slots = frame.slots;
+        function.cells = cells;
+        function.captures = frame.captures.into_iter().map(|(_, from)| from).collect();
+        function.code = code;
+    }
+
+    // ----------- statements ---------- \\
+    fn block(&mut self, block: &[Statement]) {
+        self.push_scope();
+        self.stmts(block);
+        self.pop_scope();
+    }
+
+    fn stmts(&mut self, block: &[Statement]) {
+        for stmt in block {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            // a typed assignment is a let, an untyped one updates a variable
+            Statement::Assignment(var, exp) => {
+                self.exp(exp);
+                if var.var_type.is_some() {
+                    self.define(&var.name);
+                } else {
+                    self.set(&var.name);
+                }
+            }
+            // defined before its body is compiled, so the body can call itself
+            Statement::Function(function) => {
+                self.emit(Op::Void);
+                self.define(&function.name);
+                let index = self.function(&function.name, &function.param, &function.block);
+                self.emit(Op::Closure(index));
+                self.set(&function.name);
+            }
+            Statement::Return(exp, _) => {
+                self.exp(exp);
+                self.emit(Op::Return);
+            }
+            Statement::If(if_stmt) => {
+                self.exp(&if_stmt.condition);
+                let skip_true = self.emit(Op::JumpIfFalse(0));
+                self.block(&if_stmt.true_branch);
+                match &if_stmt.false_branch {
+                    Some(false_branch) => {
+                        let skip_false = self.emit(Op::Jump(0));
+                        self.patch(skip_true);
+                        self.block(false_branch);
+                        self.patch(skip_false);
+                    }
+                    None => self.patch(skip_true),
+                }
+            }
+            // the list and where the loop is in it take two slots no name
+            // points at
+            Statement::For(for_stmt) => {
+                self.exp(&for_stmt.iterable);
+                self.push_scope();
+                let list = self.slot();
+                self.slot();
+                self.emit(Op::Iterate(list));
+                let start = self.here();
+                let exit = self.emit(Op::Next(list, 0));
+                self.define(&for_stmt.iter.name);
+                self.stmts(&for_stmt.block);
+                self.emit(Op::Jump(start));
+                self.patch(exit);
+                self.pop_scope();
+            }
+            Statement::While(while_stmt) => {
+                let start = self.here();
+                self.exp(&while_stmt.condition);
+                let exit = self.emit(Op::JumpIfFalse(0));
+                self.block(&while_stmt.block);
+                self.emit(Op::Jump(start));
+                self.patch(exit);
+            }
+            Statement::Exp(exp, _) => {
+                self.exp(exp);
+                self.emit(Op::Pop);
+            }
+        }
+    }
+
+    // ----------- expressions ---------- \\
+    fn exp(&mut self, exp: &Exp) {
+        match exp {
+            Exp::Var(var) => self.get(&var.name),
+            Exp::Int(i) => self.constant(Value::Int(*i)),
+            Exp::BigInt(i) => self.constant(Value::BigInt(i.clone())),
+            Exp::Float(x) => self.constant(Value::Float(*x)),
+            Exp::Str(s) => self.constant(Value::Str(Rc::from(s.as_str()))),
+            Exp::Bool(true) => {
+                self.emit(Op::True);
+            }
+            Exp::Bool(false) => {
+                self.emit(Op::False);
+            }
+            Exp::Void => {
+                self.emit(Op::Void);
+            }
+
+            Exp::Call(callee, args) => {
+                self.exp(callee);
+                for arg in args {
+                    self.exp(arg);
+                }
+                let name = self.name(&callee.callee_name());
+                self.emit(Op::Call(args.len() as u32, name));
+            }
+            Exp::Unary(op, exp) => {
+                self.exp(exp);
+                self.emit(Op::Unary(*op));
+            }
+            // both sides have to be bools, and the right one only runs
+            // when it has to
+            Exp::Op(Operation::And, left, right) => {
+                self.exp(left);
+                let left_false = self.emit(Op::JumpIfFalse(0));
+                self.exp(right);
+                let right_false = self.emit(Op::JumpIfFalse(0));
+                self.emit(Op::True);
+                let done = self.emit(Op::Jump(0));
+                self.patch(left_false);
+                self.patch(right_false);
+                self.emit(Op::False);
+                self.patch(done);
+            }
+            Exp::Op(Operation::Or, left, right) => {
+                self.exp(left);
+                let left_true = self.emit(Op::JumpIfTrue(0));
+                self.exp(right);
+                let right_true = self.emit(Op::JumpIfTrue(0));
+                self.emit(Op::False);
+                let done = self.emit(Op::Jump(0));
+                self.patch(left_true);
+                self.patch(right_true);
+                self.emit(Op::True);
+                self.patch(done);
+            }
+            Exp::Op(op, left, right) => {
+                self.exp(left);
+                self.exp(right);
+                self.emit(Op::Binary(*op));
+            }
+            // the left side runs first, then the callee goes under it
+            Exp::Pipe(left, right) => {
+                self.exp(left);
+                let (callee, args): (&Exp, &[Exp]) = match &**right {
+                    Exp::Call(callee, args) => (callee, args),
+                    callee => (callee, &[]),
+                };
+                self.exp(callee);
+                self.emit(Op::Swap);
+                for arg in args {
+                    self.exp(arg);
+                }
+                let name = self.name(&callee.callee_name());
+                self.emit(Op::Call(args.len() as u32 + 1, name));
+            }
+
+            Exp::Struct(name, fields) => {
+                for field in fields {
+                    match &field.value {
+                        Some(exp) => self.exp(exp),
+                        None => {
+                            self.emit(Op::Void);
+                        }
+                    }
+                }
+                // values are pushed in the order the literal lists them, but
+                // go in the order the struct declares its fields
+                let mut names: Vec<String> =
+                    fields.iter().map(|field| field.name.clone()).collect();
+                if let Some(declared) = self.structs.fields(name) {
+                    names.sort_by_key(|field| declared.iter().position(|(d, _)| d == field));
+                }
+                let slots = fields
+                    .iter()
+                    .map(|field| names.iter().position(|name| *name == field.name).unwrap())
+                    .collect();
+                self.bytecode.structs.push((name.clone(), names, slots));
+                let index = self.bytecode.structs.len() as u32 - 1;
+                self.emit(Op::Struct(index));
+            }
+            Exp::Field(exp, field) => {
+                self.exp(exp);
+                let name = self.name(field);
+                self.emit(Op::Field(name));
+            }
+            Exp::List(_, items) => {
+                for item in items {
+                    self.exp(item);
+                }
+                self.emit(Op::List(items.len() as u32));
+            }
+            Exp::HOF(params, block) => {
+                let index = self.function("<lambda>", params, block);
+                self.emit(Op::Closure(index));
+            }
+        }
+    }
+
+    // ----------- names ---------- \\
+    fn get(&mut self, name: &str) {
+        let op = match self.resolve(self.frames.len() - 1, name) {
+            Some(Resolved::Local(slot)) => Op::GetLocal(slot),
+            Some(Resolved::Capture(i)) => Op::GetCapture(i),
+            None => Op::GetGlobal(self.name(name)),
+        };
+        self.emit(op);
+    }
+
+    fn set(&mut self, name: &str) {
+        let op = match self.resolve(self.frames.len() - 1, name) {
+            Some(Resolved::Local(slot)) => Op::SetLocal(slot),
+            Some(Resolved::Capture(i)) => Op::SetCapture(i),
+            None => Op::SetGlobal(self.name(name)),
+        };
+        self.emit(op);
+    }
+
+    // a let, which in the same scope as another of the same name changes
+    // that one, like the interpreter
+    fn define(&mut self, name: &str) {
+        let global = self.frames.len() == 1 && self.frame().scopes.is_empty();
+        if global {
+            let name = self.name(name);
+            self.emit(Op::DefineGlobal(name));
+            return;
+        }
+        let existing = self.frame().scopes.last().unwrap().names.get(name).copied();
+        let slot = match existing {
+            Some(slot) => slot,
+            None => {
+                let slot = self.slot();
+                let scope = self.frame_mut().scopes.last_mut().unwrap();
+                scope.names.insert(String::from(name), slot);
+                slot
+            }
+        };
+        self.emit(Op::DefineLocal(slot));
+    }
+
+    // looks a name up in the function at depth and the functions around
+    // it, capturing it into every function in between when it's a local of
+    // an outer one. None means it's a global
+    fn resolve(&mut self, depth: usize, name: &str) -> Option<Resolved> {
+        let frame = &self.frames[depth];
+        if let Some(slot) = frame
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.names.get(name))
+        {
+            return Some(Resolved::Local(*slot));
+        }
+        if let Some(i) = frame
+            .captures
+            .iter()
+            .position(|(captured, _)| captured == name)
+        {
+            return Some(Resolved::Capture(i as u32));
+        }
+        if depth == 0 {
+            return None;
+        }
+        let from = match self.resolve(depth - 1, name)? {
+            Resolved::Local(slot) => {
+                self.frames[depth - 1].captured.insert(slot);
+                Capture::Local(slot)
+            }
+            Resolved::Capture(i) => Capture::Capture(i),
+        };
+        let captures = &mut self.frames[depth].captures;
+        captures.push((String::from(name), from));
+        Some(Resolved::Capture(captures.len() as u32 - 1))
+    }
+
+    fn push_scope(&mut self) {
+        let start = self.frame().next_slot;
+        self.frame_mut().scopes.push(Scope {
+            names: HashMap::new(),
+            start,
+        });
+    }
+
+    // the scope's slots are free for the next one to use
+    fn pop_scope(&mut self) {
+        let frame = self.frame_mut();
+        frame.next_slot = frame.scopes.pop().unwrap().start;
+    }
+
+    fn slot(&mut self) -> u32 {
+        let frame = self.frame_mut();
+        let slot = frame.next_slot;
+        frame.next_slot += 1;
+        frame.slots = frame.slots.max(frame.next_slot);
+        slot
+    }
+
+    // ----------- output ---------- \\
+    fn frame(&self) -> &Frame {
+        self.frames.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    // adds an instruction to the innermost function, giving back where it is
+    fn emit(&mut self, op: Op) -> usize {
+        let code = &mut self.frame_mut().code;
+        code.push(op);
+        code.len() - 1
+    }
+
+    fn here(&self) -> u32 {
+        self.frame().code.len() as u32
+    }
+
+    // points the jump at i to the next instruction
+    fn patch(&mut self, i: usize) {
+        let here = self.here();
+        let op = &mut self.frame_mut().code[i];
+        *op = match *op {
+            Op::Jump(_) => Op::Jump(here),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(here),
+            Op::JumpIfTrue(_) => Op::JumpIfTrue(here),
+            Op::Next(slot, _) => Op::Next(slot, here),
+            op => op,
+        };
+    }
+
+    fn constant(&mut self, value: Value) {
+        // debug output tells every constant apart, down to 0.0 and -0.0
+        let key = format!("{:?}", value);
+        let index = match self.constants.get(&key) {
+            Some(index) => *index,
+            None => {
+                let index = self.bytecode.constants.len() as u32;
+                self.bytecode.constants.push(value);
+                self.constants.insert(key, index);
+                index
+            }
+        };
+        self.emit(Op::Constant(index));
+    }
+
+    fn name(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.names.get(name) {
+            return *index;
+        }
+        let index = self.bytecode.names.len() as u32;
+        self.bytecode.names.push(String::from(name));
+        self.names.insert(String::from(name), index);
+        index
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::tokenizer::tokenizer::tokenize;
+
+    fn compile_source(source: &str) -> Bytecode {
+        compile(
+            &parse(tokenize(source).unwrap()).unwrap(),
+            &StructRegistry::new(),
+        )
+    }
+
+    #[test]
+    fn compile_globals_and_locals() {
+        let bytecode = compile_source("let x: int = 1; if (true) { let x: int = x; x = 2; } x");
+        assert_eq!(bytecode.names, vec![String::from("x")]);
+        assert_eq!(
+            bytecode.functions[0].code,
+            vec![
+                Op::Constant(0),
+                Op::DefineGlobal(0),
+                Op::True,
+                Op::JumpIfFalse(8),
+                Op::GetGlobal(0),
+                Op::DefineLocal(0),
+                Op::Constant(1),
+                Op::SetLocal(0),
+                Op::GetGlobal(0),
+                Op::Return,
+                Op::Void,
+                Op::Return,
+            ]
+        )
+    }
+
+    #[test]
+    fn compile_loop() {
+        let bytecode = compile_source("for x in [1, 2] { while (x > 1 && true) { } }");
+        assert_eq!(
+            bytecode.functions[0].code,
+            vec![
+                Op::Constant(0),
+                Op::Constant(1),
+                Op::List(2),
+                Op::Iterate(0),
+                Op::Next(0, 18),
+                Op::DefineLocal(2),
+                Op::GetLocal(2),
+                Op::Constant(0),
+                Op::Binary(Operation::GreaterThan),
+                Op::JumpIfFalse(14),
+                Op::True,
+                Op::JumpIfFalse(14),
+                Op::True,
+                Op::Jump(15),
+                Op::False,
+                Op::JumpIfFalse(17),
+                Op::Jump(6),
+                Op::Jump(4),
+                Op::Void,
+                Op::Return,
+            ]
+        );
+        assert_eq!(bytecode.functions[0].slots, 3)
+    }
+
+    #[test]
+    fn compile_closures() {
+        let bytecode = compile_source(
+            "func make_adder(n: int) -> (int -> int) {
+                return (a: int) { return a + n; };
+            }
+            make_adder(1)(2) |> print",
+        );
+        assert_eq!(
+            bytecode.to_string(),
+            "function 0 <top level> (0 params, 0 slots)
+     0  closure 1            ; make_adder
+     1  define_global 0      ; make_adder
+     2  get_global 0         ; make_adder
+     3  constant 0           ; Int(1)
+     4  call 1               ; make_adder
+     5  constant 1           ; Int(2)
+     6  call 1               ; expression
+     7  get_global 2         ; print
+     8  swap
+     9  call 1               ; print
+    10  return
+    11  void
+    12  return
+
+function 1 make_adder (1 params, 1 slots)
+    cells [0]
+     0  closure 2            ; <lambda>
+     1  return
+     2  void
+     3  return
+
+function 2 <lambda> (1 params, 1 slots)
+    captures [Local(0)]
+     0  get_local 0
+     1  get_capture 0
+     2  binary +
+     3  return
+     4  void
+     5  return
+"
+        )
+    }
+}
@@ -0,0 +1,137 @@
+use crate::interpreter::{Builtin, BuiltinValue};
+use num_bigint::BigInt;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+// the vm's values, which print and compare the way the interpreter's do.
+// everything bigger than a number is shared, so copying a value onto the
+// stack is cheap
+#[derive(Debug, Clone)]
+pub enum Value {
+    Void,
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
+    Str(Rc<str>),
+    Bool(bool),
+    List(Rc<Vec<Value>>),
+    Struct(Rc<Struct>),
+    Function(Rc<Closure>),
+    Builtin(Builtin),
+    // a local some closure captured. these only ever live in a local's
+    // slot, every instruction reading the slot looks through them
+    Cell(Rc<RefCell<Value>>),
+}
+
+#[derive(Debug)]
+pub struct Struct {
+    pub name: String,
+    // in the order the literal gave them
+    pub fields: Vec<(String, Value)>,
+}
+
+// a function and the cells of the locals it captured, in the order its
+// Function lists them
+#[derive(Debug)]
+pub struct Closure {
+    pub function: usize,
+    pub name: Rc<str>,
+    pub captures: Vec<Rc<RefCell<Value>>>,
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Void => "void",
+            Value::Int(_) => "int",
+            Value::BigInt(_) => "bigint",
+            Value::Float(_) => "float",
+            Value::Str(_) => "str",
+            Value::Bool(_) => "bool",
+            Value::List(_) => "list",
+            Value::Struct(_) => "struct",
+            Value::Function(_) | Value::Builtin(_) => "function",
+            Value::Cell(cell) => cell.borrow().type_name(),
+        }
+    }
+}
+
+impl BuiltinValue for Value {
+    fn void() -> Value {
+        Value::Void
+    }
+
+    fn int(i: i64) -> Value {
+        Value::Int(i)
+    }
+
+    fn length(&self) -> Option<usize> {
+        match self {
+            Value::List(items) => Some(items.len()),
+            Value::Str(s) => Some(s.chars().count()),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        Value::type_name(self)
+    }
+}
+
+// functions are only equal to themselves
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Void, Value::Void) => true,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::BigInt(a), Value::BigInt(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Struct(a), Value::Struct(b)) => a.name == b.name && a.fields == b.fields,
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Builtin(a), Value::Builtin(b)) => a == b,
+            (Value::Cell(a), b) => *a.borrow() == *b,
+            (a, Value::Cell(b)) => *a == *b.borrow(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Void => write!(f, "()"),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::BigInt(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Struct(value) => {
+                write!(f, "{} {{", value.name)?;
+                for (i, (field, value)) in value.fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {}: {}", field, value)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Function(closure) => write!(f, "<func {}>", closure.name),
+            Value::Builtin(builtin) => write!(f, "<func {}>", builtin.name()),
+            Value::Cell(cell) => write!(f, "{}", cell.borrow()),
+        }
+    }
+}
@@ -0,0 +1,670 @@
+use super::{Bytecode, Capture, Closure, Op, Struct, Value};
+use crate::ast::{Operation, UnaryOp};
+use crate::interpreter::{self, Builtin, RuntimeError};
+use std::cell::RefCell;
+use std::io::Write;
+use std::mem;
+use std::rc::Rc;
+
+// frames live on the heap, so calls can go a lot deeper than the
+// interpreter's before we give up on a runaway recursion
+const MAX_CALL_DEPTH: usize = 4096;
+
+// a running call. its callee sits on the stack right under base, and its
+// slots start at base
+struct Frame {
+    closure: Rc<Closure>,
+    ip: usize,
+    base: usize,
+}
+
+pub struct Vm<W: Write> {
+    output: W,
+    stack: Vec<Value>,
+    // the frames of every call under the running one
+    frames: Vec<Frame>,
+    // by name index, None until they're defined
+    globals: Vec<Option<Value>>,
+}
+
+impl Vm<std::io::Stdout> {
+    pub fn new() -> Vm<std::io::Stdout> {
+        Vm::with_output(std::io::stdout())
+    }
+}
+
+impl Default for Vm<std::io::Stdout> {
+    fn default() -> Self {
+        Vm::new()
+    }
+}
+
+impl<W: Write> Vm<W> {
+    // print writes to output instead of stdout
+    pub fn with_output(output: W) -> Vm<W> {
+        Vm {
+            output,
+            stack: vec![],
+            frames: vec![],
+            globals: vec![],
+        }
+    }
+
+    pub fn into_output(self) -> W {
+        self.output
+    }
+
+    // runs a compiled program, returning what Interpreter::run would
+    pub fn run(&mut self, bytecode: &Bytecode) -> Result<Value, RuntimeError> {
+        self.globals = bytecode
+            .names
+            .iter()
+            .map(|name| {
+                Builtin::ALL
+                    .iter()
+                    .find(|builtin| builtin.name() == name)
+                    .map(|builtin| Value::Builtin(*builtin))
+            })
+            .collect();
+
+        let main = &bytecode.functions[0];
+        let closure = Rc::new(Closure {
+            function: 0,
+            name: Rc::from(main.name.as_str()),
+            captures: vec![],
+        });
+        self.stack = vec![Value::Function(Rc::clone(&closure))];
+        self.stack.extend((0..main.slots).map(|_| Value::Void));
+        self.frames.clear();
+
+        let frame = Frame {
+            closure,
+            ip: 0,
+            base: 1,
+        };
+        let result = self.execute(bytecode, frame);
+        self.stack.clear();
+        self.frames.clear();
+        result
+    }
+
+    fn execute(&mut self, bytecode: &Bytecode, mut frame: Frame) -> Result<Value, RuntimeError> {
+        let mut code = &bytecode.functions[frame.closure.function].code[..];
+        loop {
+            let op = code[frame.ip];
+            frame.ip += 1;
+            match op {
+                Op::Constant(i) => self.push(bytecode.constants[i as usize].clone()),
+                Op::Void => self.push(Value::Void),
+                Op::True => self.push(Value::Bool(true)),
+                Op::False => self.push(Value::Bool(false)),
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Swap => {
+                    let len = self.stack.len();
+                    self.stack.swap(len - 1, len - 2);
+                }
+
+                // ----------- variables ---------- \\
+                Op::GetLocal(slot) => {
+                    let value = self.stack[frame.base + slot as usize].clone();
+                    self.push(value);
+                }
+                Op::SetLocal(slot) | Op::DefineLocal(slot) => {
+                    let value = self.pop();
+                    self.stack[frame.base + slot as usize] = value;
+                }
+                Op::GetCell(slot) => {
+                    let value = match &self.stack[frame.base + slot as usize] {
+                        Value::Cell(cell) => cell.borrow().clone(),
+                        value => value.clone(),
+                    };
+                    self.push(value);
+                }
+                Op::SetCell(slot) => {
+                    let value = self.pop();
+                    match &self.stack[frame.base + slot as usize] {
+                        Value::Cell(cell) => *cell.borrow_mut() = value,
+                        _ => self.stack[frame.base + slot as usize] = value,
+                    }
+                }
+                // every let gets a cell of its own, so closures made in
+                // earlier loop iterations keep what they saw
+                Op::DefineCell(slot) => {
+                    let value = self.pop();
+                    self.stack[frame.base + slot as usize] =
+                        Value::Cell(Rc::new(RefCell::new(value)));
+                }
+                Op::GetCapture(i) => {
+                    let value = frame.closure.captures[i as usize].borrow().clone();
+                    self.push(value);
+                }
+                Op::SetCapture(i) => {
+                    let value = self.pop();
+                    *frame.closure.captures[i as usize].borrow_mut() = value;
+                }
+                Op::GetGlobal(i) => match &self.globals[i as usize] {
+                    Some(value) => {
+                        let value = value.clone();
+                        self.push(value);
+                    }
+                    None => return Err(undefined(bytecode, i)),
+                },
+                Op::SetGlobal(i) => {
+                    if self.globals[i as usize].is_none() {
+                        return Err(undefined(bytecode, i));
+                    }
+                    self.globals[i as usize] = Some(self.pop());
+                }
+                Op::DefineGlobal(i) => self.globals[i as usize] = Some(self.pop()),
+
+                // ----------- operations ---------- \\
+                Op::Unary(op) => {
+                    let value = unary(op, self.pop())?;
+                    self.push(value);
+                }
+                Op::Binary(op) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.push(binary(op, left, right)?);
+                }
+
+                // ----------- control flow ---------- \\
+                Op::Jump(to) => frame.ip = to as usize,
+                Op::JumpIfFalse(to) => {
+                    if !self.pop_bool()? {
+                        frame.ip = to as usize;
+                    }
+                }
+                Op::JumpIfTrue(to) => {
+                    if self.pop_bool()? {
+                        frame.ip = to as usize;
+                    }
+                }
+                Op::Iterate(slot) => {
+                    let slot = frame.base + slot as usize;
+                    match self.pop() {
+                        list @ Value::List(_) => {
+                            self.stack[slot] = list;
+                            self.stack[slot + 1] = Value::Int(0);
+                        }
+                        other => return Err(RuntimeError::TypeMismatch("list", other.type_name())),
+                    }
+                }
+                Op::Next(slot, to) => {
+                    let slot = frame.base + slot as usize;
+                    let (items, i) = match (&self.stack[slot], &self.stack[slot + 1]) {
+                        (Value::List(items), Value::Int(i)) => (items, *i as usize),
+                        _ => unreachable!("next without iterate"),
+                    };
+                    match items.get(i) {
+                        Some(item) => {
+                            let item = item.clone();
+                            self.stack[slot + 1] = Value::Int(i as i64 + 1);
+                            self.push(item);
+                        }
+                        None => frame.ip = to as usize,
+                    }
+                }
+
+                // ----------- calls ---------- \\
+                Op::Call(args, name) => {
+                    let callee = self.stack.len() - args as usize - 1;
+                    let closure = match &self.stack[callee] {
+                        Value::Function(closure) => Rc::clone(closure),
+                        Value::Builtin(builtin) => {
+                            let builtin = *builtin;
+                            let args = self.stack.split_off(callee + 1);
+                            let value = builtin.call(args, &mut self.output)?;
+                            self.stack[callee] = value;
+                            continue;
+                        }
+                        _ => {
+                            let name = bytecode.names[name as usize].clone();
+                            return Err(RuntimeError::NotCallable(name));
+                        }
+                    };
+                    let function = &bytecode.functions[closure.function];
+                    if function.params != args {
+                        return Err(RuntimeError::WrongArgCount {
+                            name: String::from(&*closure.name),
+                            expected: function.params as usize,
+                            found: args as usize,
+                        });
+                    }
+                    if self.frames.len() >= MAX_CALL_DEPTH {
+                        return Err(RuntimeError::StackOverflow);
+                    }
+
+                    let base = callee + 1;
+                    self.stack
+                        .extend((function.params..function.slots).map(|_| Value::Void));
+                    for slot in &function.cells {
+                        let slot = &mut self.stack[base + *slot as usize];
+                        let value = mem::replace(slot, Value::Void);
+                        *slot = Value::Cell(Rc::new(RefCell::new(value)));
+                    }
+                    code = &function.code[..];
+                    let caller = mem::replace(
+                        &mut frame,
+                        Frame {
+                            closure,
+                            ip: 0,
+                            base,
+                        },
+                    );
+                    self.frames.push(caller);
+                }
+                Op::Return => {
+                    let value = self.pop();
+                    self.stack.truncate(frame.base - 1);
+                    match self.frames.pop() {
+                        Some(caller) => {
+                            frame = caller;
+                            code = &bytecode.functions[frame.closure.function].code[..];
+                            self.push(value);
+                        }
+                        None => return Ok(value),
+                    }
+                }
+                Op::Closure(i) => {
+                    let function = &bytecode.functions[i as usize];
+                    let captures = function
+                        .captures
+                        .iter()
+                        .map(|capture| match *capture {
+                            Capture::Local(slot) => {
+                                cell(&mut self.stack[frame.base + slot as usize])
+                            }
+                            Capture::Capture(i) => Rc::clone(&frame.closure.captures[i as usize]),
+                        })
+                        .collect();
+                    self.push(Value::Function(Rc::new(Closure {
+                        function: i as usize,
+                        name: Rc::from(function.name.as_str()),
+                        captures,
+                    })));
+                }
+
+                // ----------- data ---------- \\
+                Op::List(len) => {
+                    let items = self.stack.split_off(self.stack.len() - len as usize);
+                    self.push(Value::List(Rc::new(items)));
+                }
+                Op::Struct(i) => {
+                    let (name, fields, slots) = &bytecode.structs[i as usize];
+                    let values = self.stack.split_off(self.stack.len() - slots.len());
+                    let mut ordered = vec![Value::Void; fields.len()];
+                    for (&slot, value) in slots.iter().zip(values) {
+                        ordered[slot] = value;
+                    }
+                    self.push(Value::Struct(Rc::new(Struct {
+                        name: name.clone(),
+                        fields: fields.iter().cloned().zip(ordered).collect(),
+                    })));
+                }
+                Op::Field(i) => {
+                    let field = &bytecode.names[i as usize];
+                    let value = match self.pop() {
+                        Value::Struct(value) => value
+                            .fields
+                            .iter()
+                            .find(|(name, _)| name == field)
+                            .map(|(_, value)| value.clone())
+                            .ok_or_else(|| {
+                                RuntimeError::UnknownField(value.name.clone(), field.clone())
+                            })?,
+                        other => {
+                            return Err(RuntimeError::TypeMismatch("struct", other.type_name()))
+                        }
+                    };
+                    self.push(value);
+                }
+            }
+        }
+    }
+
+    // ----------- stack ---------- \\
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("popped an empty stack")
+    }
+
+    fn pop_bool(&mut self) -> Result<bool, RuntimeError> {
+        match self.pop() {
+            Value::Bool(b) => Ok(b),
+            other => Err(RuntimeError::TypeMismatch("bool", other.type_name())),
+        }
+    }
+}
+
+// the cell a local lives in, moving it into one if no closure captured it yet
+fn cell(slot: &mut Value) -> Rc<RefCell<Value>> {
+    if let Value::Cell(cell) = slot {
+        return Rc::clone(cell);
+    }
+    let cell = Rc::new(RefCell::new(mem::replace(slot, Value::Void)));
+    *slot = Value::Cell(Rc::clone(&cell));
+    cell
+}
+
+fn undefined(bytecode: &Bytecode, name: u32) -> RuntimeError {
+    RuntimeError::UndefinedVariable(bytecode.names[name as usize].clone())
+}
+
+// ----------- operations ---------- \\
+fn unary(op: UnaryOp, value: Value) -> Result<Value, RuntimeError> {
+    match (op, value) {
+        (UnaryOp::Neg, Value::Int(i)) => i
+            .checked_neg()
+            .map(Value::Int)
+            .ok_or(RuntimeError::IntegerOverflow),
+        (UnaryOp::Neg, Value::BigInt(i)) => Ok(Value::BigInt(-i)),
+        (UnaryOp::Neg, Value::Float(x)) => Ok(Value::Float(-x)),
+        (UnaryOp::Neg, other) => Err(RuntimeError::TypeMismatch("number", other.type_name())),
+        (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+        (UnaryOp::Not, other) => Err(RuntimeError::TypeMismatch("bool", other.type_name())),
+    }
+}
+
+// numbers go through the interpreter's arithmetic, so both promote, overflow
+// and compare exactly the same way
+fn binary(op: Operation, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    // the common case, without the round trip
+    if let (Value::Int(a), Value::Int(b)) = (&left, &right) {
+        let (a, b) = (*a, *b);
+        return Ok(match op {
+            Operation::Equal => Value::Bool(a == b),
+            Operation::NotEqual => Value::Bool(a != b),
+            Operation::LessThan => Value::Bool(a < b),
+            Operation::GreaterThan => Value::Bool(a > b),
+            Operation::LessEqual => Value::Bool(a <= b),
+            Operation::GreaterEqual => Value::Bool(a >= b),
+            _ => match interpreter::int_op(op, a, b)? {
+                interpreter::Value::Int(i) => Value::Int(i),
+                other => unreachable!("{} from int arithmetic", other.type_name()),
+            },
+        });
+    }
+    match (number(&left), number(&right)) {
+        (Some(a), Some(b)) => Ok(match interpreter::eval_op(op, a, b)? {
+            interpreter::Value::Int(i) => Value::Int(i),
+            interpreter::Value::BigInt(i) => Value::BigInt(i),
+            interpreter::Value::Float(x) => Value::Float(x),
+            interpreter::Value::Bool(b) => Value::Bool(b),
+            other => unreachable!("{} from arithmetic", other.type_name()),
+        }),
+        (left_number, _) => match op {
+            Operation::Equal => Ok(Value::Bool(left == right)),
+            Operation::NotEqual => Ok(Value::Bool(left != right)),
+            _ => {
+                let found = match left_number {
+                    Some(_) => right.type_name(),
+                    None => left.type_name(),
+                };
+                Err(RuntimeError::TypeMismatch("number", found))
+            }
+        },
+    }
+}
+
+fn number(value: &Value) -> Option<interpreter::Value> {
+    match value {
+        Value::Int(i) => Some(interpreter::Value::Int(*i)),
+        Value::BigInt(i) => Some(interpreter::Value::BigInt(i.clone())),
+        Value::Float(x) => Some(interpreter::Value::Float(*x)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::ast::Program;
+    use crate::bytecode::compile;
+    use crate::interpreter::Interpreter;
+    use crate::parser::parse;
+    use crate::tokenizer::tokenizer::tokenize;
+    use crate::types::{StructRegistry, Type};
+
+    // the structs a program declares, which the tests give the compiler
+    // whether or not the program type checks
+    fn declared(ast: &Program) -> StructRegistry {
+        let mut structs = StructRegistry::new();
+        for def in ast.structs() {
+            let fields = def
+                .fields
+                .iter()
+                .map(|field| {
+                    (
+                        field.name.clone(),
+                        field.var_type.clone().unwrap_or(Type::Void),
+                    )
+                })
+                .collect();
+            structs.declare(&def.name, fields);
+        }
+        structs
+    }
+
+    fn run(source: &str) -> Result<Value, RuntimeError> {
+        let ast = parse(tokenize(source).unwrap()).unwrap();
+        Vm::with_output(vec![]).run(&compile(&ast, &declared(&ast)))
+    }
+
+    // runs source on both the interpreter and the vm, which have to give
+    // back, print and fail with the same things
+    fn agree(source: &str) {
+        let ast = parse(tokenize(source).unwrap()).unwrap();
+        let mut interpreter = Interpreter::with_output(vec![]);
+        let expected = interpreter.run(&ast).map(|value| value.to_string());
+        let mut vm = Vm::with_output(vec![]);
+        let found = vm
+            .run(&compile(&ast, &declared(&ast)))
+            .map(|value| value.to_string());
+        assert_eq!(found, expected, "{}", source);
+        assert_eq!(vm.into_output(), interpreter.into_output(), "{}", source)
+    }
+
+    // ----------- expression tests ---------- \\
+    #[test]
+    fn vm_expressions() {
+        agree("");
+        agree("2 * 3 + 8 / 2 - 7 % 4");
+        agree("1 + 0.5");
+        agree("10000000000000000000n * 10 + 1");
+        agree("1 < 2 && 2 <= 2 && 3 > 2.5 && 1 == 1.0 && 1 != 2");
+        agree("\"a\" == \"a\"");
+        agree("let a: int = 4; -a-1");
+        agree("!(1 > 2)");
+        agree("false && nope");
+        agree("true || nope");
+        agree("let x: [int] = [1, 2 + 1]; x");
+        agree("let p: Point = {x: 1, y: 2.5,}; p");
+    }
+
+    #[test]
+    fn vm_out_of_order_struct_literals() {
+        let program = "struct P { x: int, y: int, }
+            let a: P = P{y: 2, x: 1,};
+            let b: P = P{x: 1, y: 2,};";
+        assert_eq!(run(&format!("{} a == b", program)), Ok(Value::Bool(true)));
+        assert_eq!(
+            run(&format!("{} a", program)).map(|value| value.to_string()),
+            Ok(String::from("P { x: 1, y: 2 }"))
+        );
+        // the values still run in the order the literal lists them
+        agree(&format!(
+            "{} func show(n: int) -> int {{ print(n); return n; }} P{{y: show(2), x: show(1),}}",
+            program
+        ));
+    }
+
+    #[test]
+    fn vm_errors() {
+        agree("1 / 0");
+        agree("1n % 0");
+        agree("9223372036854775807 + 1");
+        agree("-true");
+        agree("!1");
+        agree("1 + true");
+        agree("true < 1");
+        agree("if (1) { }");
+        agree("x");
+        agree("x = 1;");
+        agree("let x: int = 1; x(x)");
+        agree("1(2)");
+        agree("let n: int = 3; for x in n { }");
+        agree("func one(a: int) -> int { return a; } let a: int = 1; one(a, a)");
+        agree("let p: Point = {x: 1,}; p.z");
+        agree("let l: [int] = [1]; l.x");
+        agree("let b: bool = true; len(b)");
+    }
+
+    // ----------- statement tests ---------- \\
+    #[test]
+    fn vm_functions() {
+        agree(
+            "func fib(n: int) -> int {
+                if (n < 2) { return n; }
+                return fib(n - 1) + fib(n - 2);
+            }
+            fib(15)",
+        );
+        agree(
+            "let n: int = 7;
+            let result: bool = is_even(n);
+            func is_even(n: int) -> bool {
+                if (n == 0) { return true; }
+                return is_odd(n - 1);
+            }
+            func is_odd(n: int) -> bool {
+                if (n == 0) { return false; }
+                return is_even(n - 1);
+            }
+            result",
+        );
+        agree(
+            "func sign(x: int) -> int {
+                if (x < 0) { return -1; } elif (x == 0) { return 0; } else { return 1; }
+            }
+            [sign(-5), sign(0), sign(9)]",
+        );
+        agree("return 1; 2");
+    }
+
+    #[test]
+    fn vm_loops_and_scopes() {
+        agree(
+            "let x: int = 0; let total: int = 0;
+            while (x < 5) { total = total + x; x = x + 1; }
+            total",
+        );
+        agree(
+            "let rows: [[int]] = [[1, 2], [3]];
+            let total: int = 0;
+            for row in rows { for x in row { total = total + x; } }
+            total",
+        );
+        agree("let x: int = 1; if (true) { let x: int = 2; x = 3; } x");
+        agree("let x: int = 1; if (true) { x = 3; } x");
+        agree("let x: int = 1; let x: int = 2; x");
+        agree(
+            "func first_big() -> int {
+                for x in [1, 20, 300] { if (x > 10) { return x; } }
+                return 0;
+            }
+            first_big()",
+        );
+        agree("for x in [1, 2] { print(x); } x");
+    }
+
+    // ----------- closure tests ---------- \\
+    #[test]
+    fn vm_closures() {
+        agree(
+            "func make_adder(n: int) -> (int -> int) {
+                return (a: int) { return a + n; };
+            }
+            make_adder(10)(5)",
+        );
+        agree(
+            "func apply(f: (int, int) -> int, a: int, b: int) -> int { return f(a, b); }
+            func add(a: int, b: int,) -> int { return a + b; }
+            let times: (int, int) -> int = (a, b) { return a * b; };
+            struct Ops { double: (int) -> int, }
+            let ops: Ops = {double: (a) { return a * 2; },};
+            [apply(add, 6, 7), apply(times, 6, 7), ops.double(21), (a) { return -a; }(1)]",
+        );
+        agree(
+            "func add(a: int, b: int) -> int { return a + b; }
+            let double: (int -> int) = (a) { return a * 2; };
+            3 |> double |> add(1) |> (x) { return x * 10; }",
+        );
+        agree("func f() -> int { return 1; } print(f); print((a) { return a; }); print(len);");
+    }
+
+    #[test]
+    fn vm_closures_share_variables() {
+        // captures are the variables themselves, not copies of them
+        agree(
+            "func counter() -> (() -> int) {
+                let count: int = 0;
+                return () { count = count + 1; return count; };
+            }
+            let next: (() -> int) = counter();
+            next(); next();
+            print(next());
+            let other: (() -> int) = counter();
+            other()",
+        );
+        // every iteration has an item of its own
+        agree(
+            "let first: (() -> int) = () { return 0; };
+            let last: (() -> int) = first;
+            for x in [1, 2, 3] {
+                let f: (() -> int) = () { return x; };
+                if (x == 1) { first = f; }
+                last = f;
+            }
+            [first(), last()]",
+        );
+        // through a function in between
+        agree(
+            "func outer(n: int) -> (() -> (() -> int)) {
+                return () { return () { n = n + 1; return n; }; };
+            }
+            let f: (() -> int) = outer(1)();
+            f();
+            f()",
+        );
+        // a nested function can call itself
+        agree(
+            "func outer(n: int) -> int {
+                func down(n: int) -> int {
+                    if (n == 0) { return 0; }
+                    return down(n - 1) + 1;
+                }
+                return down(n);
+            }
+            outer(5)",
+        );
+    }
+
+    #[test]
+    fn vm_deep_recursion() {
+        // deeper than the interpreter can go
+        assert_eq!(
+            run("func down(n: int) -> int { if (n == 0) { return 0; } return down(n - 1); } down(1000)"),
+            Ok(Value::Int(0))
+        );
+        assert_eq!(
+            run("func forever(n: int) -> int { return forever(n); } forever(1)"),
+            Err(RuntimeError::StackOverflow)
+        )
+    }
+}
@@ -0,0 +1,496 @@
+pub mod c;
+mod error;
+#[cfg(test)]
+mod harness;
+pub mod llvm;
+pub mod wasm;
+
+pub use error::CodegenError;
+
+use crate::ast::*;
+use crate::types::Type;
+use std::collections::{HashMap, HashSet};
+
+// the C runtime llvm's output links against, and c's output starts with
+pub const RUNTIME: &str = include_str!("codegen/runtime.c");
+
+// how far away a name was found, from the function being lowered
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Reach {
+    Local,
+    Global,
+    // a function around this one, which a backend may have to capture from
+    Outer,
+}
+
+// names in scope while lowering a program. every function gets its own
+// frame of nested block scopes, and the first frame holds the globals
+struct Scopes<T> {
+    frames: Vec<Vec<HashMap<String, T>>>,
+}
+
+impl<T> Scopes<T> {
+    fn new() -> Scopes<T> {
+        Scopes {
+            frames: vec![vec![HashMap::new()]],
+        }
+    }
+
+    fn push_frame(&mut self) {
+        self.frames.push(vec![HashMap::new()]);
+    }
+
+    fn pop_frame(&mut self) {
+        self.frames.pop();
+    }
+
+    fn push(&mut self) {
+        self.frames.last_mut().unwrap().push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.frames.last_mut().unwrap().pop();
+    }
+
+    fn define(&mut self, name: &str, value: T) {
+        let scope = self.frames.last_mut().unwrap().last_mut().unwrap();
+        scope.insert(String::from(name), value);
+    }
+
+    fn define_global(&mut self, name: &str, value: T) {
+        self.frames[0][0].insert(String::from(name), value);
+    }
+
+    fn lookup(&self, name: &str) -> Option<(&T, Reach)> {
+        let innermost = self.frames.len() - 1;
+        for (i, frame) in self.frames.iter().enumerate().rev() {
+            if let Some(value) = frame.iter().rev().find_map(|scope| scope.get(name)) {
+                let reach = match i {
+                    0 => Reach::Global,
+                    i if i == innermost => Reach::Local,
+                    _ => Reach::Outer,
+                };
+                return Some((value, reach));
+            }
+        }
+        None
+    }
+}
+
+// what a return statement gives back where it is
+enum Returns {
+    Main,
+    Expected(Type),
+    // lambdas take the type of their first return
+    Infer(Option<Type>),
+}
+
+impl Returns {
+    // the type a return with a value of type found gives back, which the
+    // first return in a lambda decides. None outside any function
+    fn returning(&mut self, found: &Type) -> Option<Type> {
+        match self {
+            Returns::Main => None,
+            Returns::Expected(output) => Some(output.clone()),
+            Returns::Infer(output) => Some(output.get_or_insert_with(|| found.clone()).clone()),
+        }
+    }
+
+    // what the function gives back, once all its returns have been lowered
+    fn output(&self) -> Type {
+        match self {
+            Returns::Expected(output) | Returns::Infer(Some(output)) => output.clone(),
+            Returns::Main | Returns::Infer(None) => Type::Void,
+        }
+    }
+}
+
+// statements are lowered the same way around in every backend, with each
+// block in a scope of its own
+trait LowerBlock {
+    type Binding;
+
+    fn scopes(&mut self) -> &mut Scopes<Self::Binding>;
+
+    fn stmt(&mut self, stmt: &Statement) -> Result<(), CodegenError>;
+
+    fn block(&mut self, block: &[Statement]) -> Result<(), CodegenError> {
+        self.scopes().push();
+        let result = self.stmts(block);
+        self.scopes().pop();
+        result
+    }
+
+    fn stmts(&mut self, block: &[Statement]) -> Result<(), CodegenError> {
+        for stmt in block {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+}
+
+// the parameter and output types of a function, which the checker has made
+// sure every parameter has
+fn signature(function: &Function) -> Result<(Vec<Type>, Type), CodegenError> {
+    let params = function
+        .param
+        .iter()
+        .map(|param| {
+            param
+                .var_type
+                .clone()
+                .ok_or_else(|| CodegenError::UnknownType(param.name.clone()))
+        })
+        .collect::<Result<_, _>>()?;
+    let output = function.output.var_type.clone().unwrap_or(Type::Void);
+    Ok((params, output))
+}
+
+// every name used inside the lambdas in a block, at any depth. backends
+// that capture box the locals with these names, since a closure might
+// point at them
+fn lambda_names(block: &[Statement]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for stmt in block {
+        stmt_lambda_names(stmt, false, &mut names);
+    }
+    names
+}
+
+fn stmt_lambda_names(stmt: &Statement, inside: bool, names: &mut HashSet<String>) {
+    let block = |block: &[Statement], names: &mut HashSet<String>| {
+        for stmt in block {
+            stmt_lambda_names(stmt, inside, names);
+        }
+    };
+    match stmt {
+        Statement::Assignment(var, exp) => {
+            if inside {
+                names.insert(var.name.clone());
+            }
+            exp_lambda_names(exp, inside, names);
+        }
+        Statement::Function(function) => block(&function.block, names),
+        Statement::Return(exp, _) | Statement::Exp(exp, _) => exp_lambda_names(exp, inside, names),
+        Statement::If(if_stmt) => {
+            exp_lambda_names(&if_stmt.condition, inside, names);
+            block(&if_stmt.true_branch, names);
+            if let Some(false_branch) = &if_stmt.false_branch {
+                block(false_branch, names);
+            }
+        }
+        Statement::For(for_stmt) => {
+            exp_lambda_names(&for_stmt.iterable, inside, names);
+            block(&for_stmt.block, names);
+        }
+        Statement::While(while_stmt) => {
+            exp_lambda_names(&while_stmt.condition, inside, names);
+            block(&while_stmt.block, names);
+        }
+    }
+}
+
+fn exp_lambda_names(exp: &Exp, inside: bool, names: &mut HashSet<String>) {
+    match exp {
+        Exp::Var(var) if inside => {
+            names.insert(var.name.clone());
+        }
+        Exp::HOF(_, block) => {
+            for stmt in block {
+                stmt_lambda_names(stmt, true, names);
+            }
+        }
+        Exp::Call(callee, args) => {
+            exp_lambda_names(callee, inside, names);
+            for arg in args {
+                exp_lambda_names(arg, inside, names);
+            }
+        }
+        Exp::Unary(_, exp) | Exp::Field(exp, _) => exp_lambda_names(exp, inside, names),
+        Exp::Op(_, left, right) | Exp::Pipe(left, right) => {
+            exp_lambda_names(left, inside, names);
+            exp_lambda_names(right, inside, names);
+        }
+        Exp::Struct(_, fields) => {
+            for value in fields.iter().filter_map(|field| field.value.as_ref()) {
+                exp_lambda_names(value, inside, names);
+            }
+        }
+        Exp::List(_, items) => {
+            for item in items {
+                exp_lambda_names(item, inside, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::bytecode::{self, Vm};
+
+    #[test]
+    fn scopes_reach() {
+        let mut scopes = Scopes::new();
+        scopes.define("g", 1);
+        scopes.push_frame();
+        scopes.define("outer", 2);
+        scopes.push_frame();
+        scopes.push();
+        scopes.define("local", 3);
+
+        assert_eq!(scopes.lookup("g"), Some((&1, Reach::Global)));
+        assert_eq!(scopes.lookup("outer"), Some((&2, Reach::Outer)));
+        assert_eq!(scopes.lookup("local"), Some((&3, Reach::Local)));
+
+        scopes.pop();
+        assert_eq!(scopes.lookup("local"), None);
+        scopes.pop_frame();
+        assert_eq!(scopes.lookup("outer"), Some((&2, Reach::Local)));
+    }
+
+    #[test]
+    fn scopes_shadowing() {
+        let mut scopes = Scopes::new();
+        scopes.define_global("x", 1);
+        scopes.push_frame();
+        scopes.define("x", 2);
+        assert_eq!(scopes.lookup("x"), Some((&2, Reach::Local)));
+        // globals go in the first frame wherever they're defined from
+        scopes.define_global("y", 3);
+        scopes.pop_frame();
+        assert_eq!(scopes.lookup("x"), Some((&1, Reach::Global)));
+        assert_eq!(scopes.lookup("y"), Some((&3, Reach::Global)));
+    }
+
+    // ----------- every backend ---------- \\
+    // programs the interpreter, the vm and every native backend have to
+    // print the same for, and whether they need lambdas that capture. the
+    // natives are skipped where their tools aren't installed
+    const PROGRAMS: &[(&str, bool, &str)] = &[
+        (
+            "arithmetic",
+            false,
+            "let x: int = 7;
+            print(x * 3 - 10 / 4 % 3);
+            print(-x);
+            print(1.5 * 2);
+            print(x / 2.0);
+            print(0.1 + 0.2);
+            print(1e20);
+            print(0.00001);
+            print(123456.789);
+            print(-2.5 % 2);
+            print(x > 3 && !(x == 7) || 2.0 == 2);
+            print(-9223372036854775808);
+            print(\"what??!\");",
+        ),
+        (
+            "control flow",
+            false,
+            "func grade(score: int) -> str {
+                if (score >= 90) { return \"A\"; }
+                elif (score >= 80) { return \"B\"; }
+                else { return \"F\"; }
+            }
+            func fib(n: int) -> int {
+                if (n < 2) { return n; }
+                return fib(n - 1) + fib(n - 2);
+            }
+            let i: int = 0;
+            while (i < 3) { print(i); i = i + 1; }
+            for s in [95, 85, 10] { print(grade(s)); }
+            print(fib(20));
+            for x in [1, 2, 3] { if (x == 2) { return; } print(x); }
+            print(\"unreachable\");",
+        ),
+        (
+            "structs and lists",
+            false,
+            "struct Point { x: int, y: float, }
+            struct Line { start: Point, end: Point, }
+            let line: Line = {start: Point {x: 1, y: 2.0,}, end: Point {x: 3, y: 4.5,},};
+            let grid: [[int]] = [[1, 2], [], [3]];
+            let total: int = 0;
+            for row in grid { for cell in row { total = total + cell; } }
+            print(line);
+            print(line.end.y);
+            print(grid);
+            print(total);
+            print(len(grid));
+            print(len(\"héllo\"));
+            print([\"a\", \"b\"]);
+            print([true, false]);
+            print([1.5]);
+            print(grid == [[1, 2], [], [3]]);
+            print(line.start == line.end);
+            print(\"a\" != \"b\");",
+        ),
+        (
+            "anonymous struct literals",
+            false,
+            "struct Point { x: int, y: int, }
+            func flip(p: Point) -> Point { return {x: p.y, y: p.x,}; }
+            let p: Point = {x: 1, y: 2,};
+            p = flip({x: 5, y: 6,});
+            print(p);
+            print(flip(p));
+            print(flip(flip(p)) == p);",
+        ),
+        (
+            "functions as values",
+            false,
+            "func double(x: int) -> int { return x * 2; }
+            func apply(f: (int) -> int, x: int) -> int { return f(x); }
+            func map(xs: [int], f: (int) -> int) -> [int] {
+                let n: int = 0;
+                for x in xs { n = n + f(x); }
+                return [n];
+            }
+            let inc: (int) -> int = (a: int) { return a + 1; };
+            print(apply(double, 4));
+            print(apply(inc, 4));
+            print(apply((a) { return a - 1; }, 4));
+            print([1, 2] |> map((a) { return a * a; }));
+            print(4 |> double |> inc);
+            print([1, 2, 3] |> map(double));
+            print(inc == inc);
+            print(double == double);
+            print(double);
+            print(inc);",
+        ),
+        (
+            "closures",
+            true,
+            "func counter(start: int) -> () -> int {
+                let count: int = start;
+                return () { count = count + 1; return count; };
+            }
+            func adder(a: int) -> (int) -> (int) -> int {
+                return (b: int) { return (c: int) { return a + b + c; }; };
+            }
+            let next: () -> int = counter(10);
+            let other: () -> int = counter(0);
+            print(next());
+            print(next());
+            print(other());
+            print(adder(1)(2)(3));
+
+            let total: int = 0;
+            let add: (int) -> void = (x: int) { total = total + x; };
+            for x in [1, 2, 3] { add(x); }
+            print(total);
+
+            let last: () -> int = () { return 0; };
+            for x in [4, 5] { last = () { return x * 10; }; }
+            print(last());",
+        ),
+        (
+            "evaluation order",
+            false,
+            "let calls: int = 0;
+            func tick(n: int) -> int { print(n); calls = calls + 1; return n; }
+            func pair(a: int, b: int) -> int { return a * 10 + b; }
+            print(pair(tick(1), tick(2)));
+            print(tick(3) - tick(4));
+            print(calls + tick(5) + calls);
+            print([tick(6), tick(7)]);
+            print(false && tick(8) == 8);
+            print(true || tick(9) == 9);
+            print(tick(10) == 10 && tick(11) == 11);
+            let i: int = 0;
+            while (tick(i) < 2 && tick(i) < 2) { i = i + 1; }
+            if (i == 5) { print(0); } elif (tick(12) == 12) { print(12); }",
+        ),
+        (
+            // hoisted functions reading globals, in the orders the checker
+            // lets through
+            "globals read by hoisted functions",
+            false,
+            "let g: int = 41;
+            print(h());
+            func h() -> int { return f() * 2; }
+            func f() -> int { return g + 1; }
+            g = 1;
+            print(h());
+            let later: int = 5;
+            func k() -> int { return later; }
+            print(k());",
+        ),
+        (
+            // well past wasm's first 64KiB page
+            "memory growth",
+            false,
+            "let lists: int = 0;
+            let i: int = 0;
+            while (i < 5000) { let xs: [int] = [i, i, i, i, i, i, i, i]; lists = lists + len(xs); i = i + 1; }
+            print(lists);",
+        ),
+    ];
+
+    // programs that stop with a runtime error, with what each prints first
+    // and the error it stops with
+    const FAILING: &[(&str, &str, &str)] = &[
+        (
+            "print(1); let x: int = 0; print(1 / x);",
+            "1\n",
+            "divide by zero",
+        ),
+        (
+            "let x: int = 9223372036854775807; x + 1",
+            "",
+            "integer overflow",
+        ),
+        (
+            "let x: int = -9223372036854775807; print(x * 2);",
+            "",
+            "integer overflow",
+        ),
+    ];
+
+    // each native backend and whether it can capture
+    const NATIVES: [(harness::RunNative, bool); 3] = [
+        (c::tests::run_native, true),
+        (llvm::tests::run_native, false),
+        (wasm::tests::run_native, true),
+    ];
+
+    #[test]
+    fn programs_run_the_same_everywhere() {
+        for (name, captures, source) in PROGRAMS {
+            let expected = harness::interpret(source);
+
+            let (program, structs) = harness::checked(source);
+            let mut vm = Vm::with_output(vec![]);
+            vm.run(&bytecode::compile(&program, &structs)).unwrap();
+            assert_eq!(
+                String::from_utf8(vm.into_output()).unwrap(),
+                expected,
+                "{} on the vm",
+                name
+            );
+
+            for (run_native, can_capture) in NATIVES {
+                if *captures && !can_capture {
+                    continue;
+                }
+                if let Some(run) = run_native(source) {
+                    assert_eq!(run, (0, expected.clone(), String::new()), "{}", name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn runtime_errors_stop_every_native() {
+        for (source, stdout, error) in FAILING {
+            for (run_native, _) in NATIVES {
+                if let Some(run) = run_native(source) {
+                    let expected = (1, String::from(*stdout), format!("error: {}\n", error));
+                    assert_eq!(run, expected, "{}", source);
+                }
+            }
+        }
+    }
+}
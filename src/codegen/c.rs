@@ -0,0 +1,1541 @@
+// lowers a type checked program to a single standalone C99 file, with the
+// runtime pasted in at the top:
+//
+//     cc -std=c99 out.c -lm -o out
+//
+// ints are int64_t, floats double and bools bool. strings are const char *,
+// and structs, lists and functions are pointers to heap objects that are
+// never freed. function values are closures: a function pointer and an
+// environment of pointers to the boxed locals a lambda captured, so a
+// closure sees its locals change and keeps them alive after they go out of
+// scope, like the interpreter
+use super::{
+    exp_lambda_names, lambda_names, signature, stmt_lambda_names, CodegenError, LowerBlock, Reach,
+    Returns, Scopes, RUNTIME,
+};
+use crate::ast::*;
+use crate::interpreter::Builtin;
+use crate::types::{StructRegistry, Type};
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+
+const PRELUDE: &str = "\
+// ----------- closures ----------
+// every function takes its closure's environment first
+
+typedef void (*pipes_fn)(void);
+
+typedef struct {
+    pipes_fn fn;
+    void *env;
+    const char *name;
+} pipes_closure;
+
+pipes_closure *pipes_closure_new(pipes_fn fn, void *env) {
+    pipes_closure *closure = pipes_alloc(sizeof *closure);
+    closure->fn = fn;
+    closure->env = env;
+    closure->name = \"<lambda>\";
+    return closure;
+}
+
+// functions are only equal to themselves
+bool pipes_closure_eq(pipes_closure *a, pipes_closure *b) {
+    return a == b;
+}
+
+void pipes_show_closure(pipes_closure *closure) {
+    printf(\"<func %s>\", closure->name);
+}
+";
+
+// the structs are the ones check gave back for the program
+pub fn compile(program: &Program, structs: &StructRegistry) -> Result<String, CodegenError> {
+    let mut module = Module::new(structs);
+    module.program(program)?;
+    Ok(module.finish())
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+    // a variable's type and the C lvalue it's used through. variables a
+    // lambda might capture live in a box, and closures take its pointer
+    Var(Type, String, Option<String>),
+    // a named function's C name, parameter types and output type
+    Function(String, Vec<Type>, Type),
+    Builtin(Builtin),
+}
+
+// a lowered expression: its type and the C expression for it, which is
+// empty for void values that don't run anything
+#[derive(Debug, Clone)]
+struct Value {
+    ty: Type,
+    c: String,
+}
+
+impl Value {
+    fn new(ty: Type, c: &str) -> Value {
+        Value {
+            ty,
+            c: String::from(c),
+        }
+    }
+
+    fn void() -> Value {
+        Value::new(Type::Void, "")
+    }
+}
+
+// the function being written
+struct Builder {
+    body: String,
+    indent: usize,
+    returns: Returns,
+    // names used inside the function's lambdas. its locals with these names
+    // are boxed, since a closure might point at them
+    boxed: HashSet<String>,
+    // locals of enclosing functions this one uses, which its closure captures
+    captures: Vec<(String, Type)>,
+}
+
+impl Builder {
+    fn new(returns: Returns, boxed: HashSet<String>) -> Builder {
+        Builder {
+            body: String::new(),
+            indent: 1,
+            returns,
+            boxed,
+            captures: vec![],
+        }
+    }
+}
+
+struct Module<'a> {
+    structs: &'a StructRegistry,
+    scopes: Scopes<Binding>,
+    builder: Builder,
+    // every C name given out ends in a number no other name has, so names
+    // never clash with each other, C keywords or the C library
+    next: usize,
+    // the C struct tag of each struct
+    tags: HashMap<String, String>,
+    types: Vec<String>,
+    prototypes: Vec<String>,
+    globals: Vec<String>,
+    functions: Vec<String>,
+    // the print, equality, call and constructor helpers written so far, by
+    // what they're for
+    helpers: HashMap<String, String>,
+}
+
+impl<'a> Module<'a> {
+    fn new(structs: &'a StructRegistry) -> Module<'a> {
+        Module {
+            structs,
+            scopes: Scopes::new(),
+            builder: Builder::new(Returns::Main, HashSet::new()),
+            next: 0,
+            tags: HashMap::new(),
+            types: vec![],
+            prototypes: vec![],
+            globals: vec![],
+            functions: vec![],
+            helpers: HashMap::new(),
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut c = String::from(RUNTIME);
+        c += "\n";
+        c += PRELUDE;
+        for section in [&self.types, &self.prototypes, &self.globals].iter() {
+            if !section.is_empty() {
+                c += "\n";
+            }
+            for line in section.iter() {
+                c += line;
+            }
+        }
+        for function in &self.functions {
+            c += "\n";
+            c += function;
+        }
+        c += "\nint main(void) {\n";
+        c += &self.builder.body;
+        c += "    return 0;\n}\n";
+        c
+    }
+
+    // ----------- items ---------- \\
+    fn program(&mut self, program: &Program) -> Result<(), CodegenError> {
+        // structs can point at each other in any order
+        for (name, _) in self.structs.iter() {
+            let tag = self.unique(name);
+            self.types.push(format!("struct {};\n", tag));
+            self.tags.insert(name.clone(), tag);
+        }
+        for (name, fields) in self.structs.iter() {
+            let mut definition = format!("\nstruct {} {{\n", self.tags[name]);
+            for (field, field_type) in fields {
+                definition += &format!("    {};\n", self.declare(field_type, &member(field))?);
+            }
+            definition += "};\n";
+            self.types.push(definition);
+        }
+        for builtin in Builtin::ALL.iter() {
+            self.scopes
+                .define_global(builtin.name(), Binding::Builtin(*builtin));
+        }
+
+        // top level functions can be called before they're declared
+        let mut names = vec![];
+        for function in program.functions() {
+            let (params, output) = signature(function)?;
+            let name = self.unique(&function.name);
+            self.scopes.define_global(
+                &function.name,
+                Binding::Function(name.clone(), params, output),
+            );
+            names.push(name);
+        }
+        let mut names = names.into_iter();
+
+        // top level statements run in main, and their blocks' locals are main's
+        let mut boxed = HashSet::new();
+        for item in &program.items {
+            match item {
+                Item::Global(_, exp) => exp_lambda_names(exp, false, &mut boxed),
+                Item::Statement(stmt) => stmt_lambda_names(stmt, false, &mut boxed),
+                _ => {}
+            }
+        }
+        self.builder.boxed = boxed;
+        self.scopes.push_frame();
+        for item in &program.items {
+            match item {
+                Item::Struct(_) => {}
+                Item::Function(function) => {
+                    let name = names.next().unwrap();
+                    self.function(&name, function)?;
+                }
+                Item::Global(var, exp) => self.global(var, exp)?,
+                Item::Statement(stmt) => self.stmt(stmt)?,
+            }
+        }
+        self.scopes.pop_frame();
+        Ok(())
+    }
+
+    fn global(&mut self, var: &Var, exp: &Exp) -> Result<(), CodegenError> {
+        let ty = var.var_type.clone().unwrap_or(Type::Void);
+        let value = self.exp_as(exp, &ty)?;
+        let name = self.unique(&var.name);
+        let declaration = self.declare(&ty, &name)?;
+        self.globals.push(format!("static {};\n", declaration));
+        self.emit(format!("{} = {};", name, bare(&value.c)));
+        self.scopes
+            .define_global(&var.name, Binding::Var(ty, name, None));
+        Ok(())
+    }
+
+    // named functions are called directly, so they can't capture anything
+    fn function(&mut self, name: &str, function: &Function) -> Result<(), CodegenError> {
+        let (_, output) = signature(function)?;
+        let (_, captures) = self.lower_function(
+            name,
+            &function.param,
+            &function.block,
+            Returns::Expected(output),
+        )?;
+        match captures.first() {
+            Some((captured, _)) => Err(CodegenError::Captures(captured.clone())),
+            None => Ok(()),
+        }
+    }
+
+    // writes out a function in a frame of its own, giving back its output
+    // type and the locals of enclosing functions it captured
+    fn lower_function(
+        &mut self,
+        name: &str,
+        params: &[Var],
+        block: &[Statement],
+        returns: Returns,
+    ) -> Result<(Type, Vec<(String, Type)>), CodegenError> {
+        let builder = Builder::new(returns, lambda_names(block));
+        let outer = std::mem::replace(&mut self.builder, builder);
+        self.scopes.push_frame();
+        let mut param_list = vec![String::from("void *env")];
+        for param in params {
+            let param_type = param
+                .var_type
+                .clone()
+                .ok_or_else(|| CodegenError::UnknownType(param.name.clone()))?;
+            let arg = self.unique(&param.name);
+            param_list.push(self.declare(&param_type, &arg)?);
+            if self.builder.boxed.contains(&param.name) {
+                self.local(&param.name, param_type, &arg)?;
+            } else {
+                self.scopes
+                    .define(&param.name, Binding::Var(param_type, arg, None));
+            }
+        }
+        self.stmts(block)?;
+        self.scopes.pop_frame();
+        let builder = std::mem::replace(&mut self.builder, outer);
+
+        let output = builder.returns.output();
+        let header = format!(
+            "static {}",
+            self.declare_return(&output, &format!("{}({})", name, param_list.join(", ")))?
+        );
+        let mut function = String::new();
+        if !builder.captures.is_empty() {
+            function += &format!("struct {}_env {{\n", name);
+            for (captured, ty) in &builder.captures {
+                let pointer = format!("*{}", member(captured));
+                function += &format!("    {};\n", self.declare(ty, &pointer)?);
+            }
+            function += "};\n\n";
+        }
+        function += &format!("{} {{\n", header);
+        if !builder.captures.is_empty() {
+            function += &format!("    struct {}_env *captured = env;\n", name);
+        }
+        function += &builder.body;
+        function += "}\n";
+        self.prototypes.push(format!("{};\n", header));
+        self.functions.push(function);
+        Ok((output, builder.captures))
+    }
+}
+
+impl LowerBlock for Module<'_> {
+    type Binding = Binding;
+
+    fn scopes(&mut self) -> &mut Scopes<Binding> {
+        &mut self.scopes
+    }
+
+    // ----------- statements ---------- \\
+    fn stmt(&mut self, stmt: &Statement) -> Result<(), CodegenError> {
+        match stmt {
+            Statement::Assignment(var, exp) if var.var_type.is_some() => {
+                let declared = var.var_type.clone().unwrap();
+                let value = self.exp_as(exp, &declared)?;
+                self.local(&var.name, declared, &value.c)
+            }
+            Statement::Assignment(var, exp) => match self.lookup(&var.name)? {
+                Binding::Var(ty, lvalue, _) => {
+                    let value = self.exp_as(exp, &ty)?;
+                    self.emit(format!("{} = {};", lvalue, bare(&value.c)));
+                    Ok(())
+                }
+                _ => Err(CodegenError::Unsupported("assigning to a function")),
+            },
+            Statement::Function(function) => {
+                let (params, output) = signature(function)?;
+                let name = self.unique(&function.name);
+                // defined first so the body can call itself
+                self.scopes.define(
+                    &function.name,
+                    Binding::Function(name.clone(), params, output),
+                );
+                self.function(&name, function)
+            }
+            Statement::Return(exp, _) => self.ret(exp),
+            Statement::If(if_stmt) => self.if_stmt(if_stmt),
+            Statement::For(for_stmt) => self.for_stmt(for_stmt),
+            Statement::While(while_stmt) => self.while_stmt(while_stmt),
+            Statement::Exp(exp, _) => {
+                let value = self.exp(exp)?;
+                self.discard(exp, &value);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'a> Module<'a> {
+    fn ret(&mut self, exp: &Exp) -> Result<(), CodegenError> {
+        let value = match &self.builder.returns {
+            Returns::Expected(output) => {
+                let output = output.clone();
+                self.exp_as(exp, &output)?
+            }
+            _ => self.exp(exp)?,
+        };
+        let output = match self.builder.returns.returning(&value.ty) {
+            Some(output) => output,
+            None => {
+                self.discard(exp, &value);
+                self.emit(String::from("return 0;"));
+                return Ok(());
+            }
+        };
+        if output == Type::Void {
+            self.discard(exp, &value);
+            self.emit(String::from("return;"));
+        } else {
+            self.emit(format!("return {};", bare(&value.c)));
+        }
+        Ok(())
+    }
+
+    // elifs stay flat as long as their conditions don't need any setup
+    fn if_stmt(&mut self, if_stmt: &IfStatement) -> Result<(), CodegenError> {
+        let condition = self.exp(&if_stmt.condition)?;
+        self.open(format!("if ({})", bare(&condition.c)));
+        self.block(&if_stmt.true_branch)?;
+        let mut false_branch = &if_stmt.false_branch;
+        while let Some(branch) = false_branch {
+            if let [Statement::If(elif)] = branch.as_slice() {
+                let (setup, condition) = self.isolate(|module| module.exp(&elif.condition))?;
+                if setup.is_empty() {
+                    self.reopen(format!("}} else if ({}) {{", bare(&condition.c)));
+                    self.block(&elif.true_branch)?;
+                    false_branch = &elif.false_branch;
+                    continue;
+                }
+            }
+            self.reopen(String::from("} else {"));
+            self.block(branch)?;
+            break;
+        }
+        self.close();
+        Ok(())
+    }
+
+    fn while_stmt(&mut self, while_stmt: &WhileStatement) -> Result<(), CodegenError> {
+        let (setup, condition) = self.isolate(|module| module.exp(&while_stmt.condition))?;
+        if setup.is_empty() {
+            self.open(format!("while ({})", bare(&condition.c)));
+        } else {
+            // the setup has to run before every check
+            self.open(String::from("while (true)"));
+            self.builder.body += &setup;
+            self.open(format!("if (!{})", condition.c));
+            self.emit(String::from("break;"));
+            self.close();
+        }
+        self.block(&while_stmt.block)?;
+        self.close();
+        Ok(())
+    }
+
+    fn for_stmt(&mut self, for_stmt: &ForStatement) -> Result<(), CodegenError> {
+        let list = self.exp(&for_stmt.iterable)?;
+        let item_type = list.ty.element_type().ok_or(CodegenError::Unsupported(
+            "looping over something that isn't a list",
+        ))?;
+        let name = self.unique("list");
+        self.emit(format!("pipes_list *{} = {};", name, bare(&list.c)));
+        let i = self.unique("i");
+        self.open(format!(
+            "for (int64_t {i} = 0; {i} < {list}->len; {i}++)",
+            i = i,
+            list = name
+        ));
+        let item = self.unslot(&item_type, &format!("{}->items[{}]", name, i))?;
+        self.scopes.push();
+        let result = self
+            .local(&for_stmt.iter.name, item_type, &item)
+            .and_then(|_| self.stmts(&for_stmt.block));
+        self.scopes.pop();
+        result?;
+        self.close();
+        Ok(())
+    }
+
+    // an expression statement, or a value nothing uses
+    fn discard(&mut self, exp: &Exp, value: &Value) {
+        if value.c.is_empty() {
+            return;
+        }
+        if effects(exp) == Effects::Calls {
+            self.emit(format!("{};", value.c));
+        } else {
+            self.emit(format!("(void){};", value.c));
+        }
+    }
+
+    // ----------- expressions ---------- \\
+    fn exp(&mut self, exp: &Exp) -> Result<Value, CodegenError> {
+        match exp {
+            Exp::Var(var) => match self.lookup(&var.name)? {
+                Binding::Var(ty, lvalue, _) => Ok(Value::new(ty, &lvalue)),
+                Binding::Function(name, params, output) => {
+                    let closure = self.closure(&name, &var.name);
+                    Ok(Value::new(
+                        Type::HigherOrderFunction(params, Box::new(output)),
+                        &closure,
+                    ))
+                }
+                Binding::Builtin(_) => Err(CodegenError::Unsupported("builtins as values")),
+            },
+            Exp::Int(i) => Ok(Value::new(Type::Int, &int_literal(*i))),
+            Exp::BigInt(_) => Err(CodegenError::Unsupported("bigints")),
+            Exp::Float(x) => Ok(Value::new(Type::Float, &float_literal(*x))),
+            Exp::Str(s) => Ok(Value::new(Type::Str, &string_literal(s))),
+            Exp::Bool(b) => Ok(Value::new(Type::Bool, &b.to_string())),
+            Exp::Void => Ok(Value::void()),
+
+            Exp::Call(callee, args) => self.call(callee, None, args),
+            Exp::Unary(op, exp) => {
+                let value = self.exp(exp)?;
+                self.unary(*op, value)
+            }
+            Exp::Op(Operation::And, left, right) => self.logic(Operation::And, left, right),
+            Exp::Op(Operation::Or, left, right) => self.logic(Operation::Or, left, right),
+            Exp::Op(op, left, right) => {
+                let mut values = self.ordered(vec![], &[left, right], &[])?;
+                let right = values.pop().unwrap();
+                let left = values.pop().unwrap();
+                self.binary(*op, left, right)
+            }
+            Exp::Pipe(left, right) => {
+                let first = (self.exp(left)?, effects(left));
+                match &**right {
+                    Exp::Call(callee, args) => self.call(callee, Some(first), args),
+                    callee => self.call(callee, Some(first), &[]),
+                }
+            }
+
+            Exp::Struct(name, fields) => self.struct_literal(name, fields),
+            Exp::Field(exp, field) => {
+                let value = self.exp(exp)?;
+                let field_type = match &value.ty {
+                    Type::Struct(name) => self
+                        .structs
+                        .field_type(name, field)
+                        .cloned()
+                        .ok_or_else(|| CodegenError::UnknownType(format!("{}.{}", name, field)))?,
+                    _ => return Err(CodegenError::Unsupported("fields of non structs")),
+                };
+                Ok(Value::new(
+                    field_type,
+                    &format!("{}->{}", value.c, member(field)),
+                ))
+            }
+            Exp::List(list_type, items) => self.list(list_type.as_ref(), items),
+            Exp::HOF(params, block) => self.lambda(params, block),
+        }
+    }
+
+    // struct literals without a name take it from where they're going
+    fn exp_as(&mut self, exp: &Exp, expected: &Type) -> Result<Value, CodegenError> {
+        match (exp, expected) {
+            (Exp::Struct(name, fields), Type::Struct(expected)) if name.is_empty() => {
+                self.struct_literal(expected, fields)
+            }
+            _ => self.exp(exp),
+        }
+    }
+
+    // lowers exps after the values already worked out in before, along
+    // with what running those could do. C doesn't say which order a call's
+    // arguments or an operator's operands run in, so a value is kept in a
+    // temporary whenever the order could show
+    fn ordered(
+        &mut self,
+        before: Vec<(Value, Effects)>,
+        exps: &[&Exp],
+        expected: &[Type],
+    ) -> Result<Vec<Value>, CodegenError> {
+        let mut values = vec![];
+        for (value, own) in before {
+            values.push(self.keep(value, own, exps)?);
+        }
+        for (i, exp) in exps.iter().enumerate() {
+            let value = match expected.get(i) {
+                Some(expected) => self.exp_as(exp, expected)?,
+                None => self.exp(exp)?,
+            };
+            values.push(self.keep(value, effects(exp), &exps[i + 1..])?);
+        }
+        Ok(values)
+    }
+
+    fn keep(&mut self, value: Value, own: Effects, later: &[&Exp]) -> Result<Value, CodegenError> {
+        let later_effects = later.iter().map(|exp| effects(exp)).max();
+        let keep = match (own, later_effects.unwrap_or(Effects::None)) {
+            (_, Effects::Calls) => true,
+            (Effects::Calls, _) => later.iter().any(|exp| reads(exp)),
+            (Effects::Fails, Effects::Fails) => true,
+            _ => false,
+        };
+        if keep {
+            self.temp(value)
+        } else {
+            Ok(value)
+        }
+    }
+
+    // named functions are called directly, anything else through its
+    // closure. a pipe's left side comes in as first
+    fn call(
+        &mut self,
+        callee: &Exp,
+        first: Option<(Value, Effects)>,
+        args: &[Exp],
+    ) -> Result<Value, CodegenError> {
+        let binding = match callee {
+            Exp::Var(var) => Some(self.lookup(&var.name)?),
+            _ => None,
+        };
+        let args: Vec<&Exp> = args.iter().collect();
+        let skip = first.iter().count();
+        let before: Vec<(Value, Effects)> = first.into_iter().collect();
+        match binding {
+            Some(Binding::Function(name, params, output)) => {
+                let values = self.ordered(before, &args, params.get(skip..).unwrap_or(&[]))?;
+                let mut arg_list = vec![String::from("NULL")];
+                arg_list.extend(values.into_iter().map(|value| bare(&value.c).to_string()));
+                Ok(Value::new(
+                    output,
+                    &format!("{}({})", name, arg_list.join(", ")),
+                ))
+            }
+            Some(Binding::Builtin(builtin)) => {
+                let values = self.ordered(before, &args, &[])?;
+                self.builtin(builtin, values)
+            }
+            _ => {
+                // the callee runs after a pipe's left side
+                let mut rest = vec![callee];
+                rest.extend(args.iter());
+                let mut kept = vec![];
+                for (value, own) in before {
+                    kept.push((self.keep(value, own, &rest)?, Effects::None));
+                }
+                let closure = self.exp(callee)?;
+                let (params, output) = match &closure.ty {
+                    Type::HigherOrderFunction(params, output) => (params.clone(), *output.clone()),
+                    _ => return Err(CodegenError::Unsupported("calling a non function")),
+                };
+                kept.push((closure, effects(callee)));
+                let mut values = self.ordered(kept, &args, params.get(skip..).unwrap_or(&[]))?;
+                let closure = values.remove(skip);
+                let call = self.call_fn(&closure.ty)?;
+                let mut arg_list = vec![closure.c];
+                arg_list.extend(values.into_iter().map(|value| bare(&value.c).to_string()));
+                Ok(Value::new(
+                    output,
+                    &format!("{}({})", call, arg_list.join(", ")),
+                ))
+            }
+        }
+    }
+
+    fn builtin(&mut self, builtin: Builtin, args: Vec<Value>) -> Result<Value, CodegenError> {
+        let arg = match args.as_slice() {
+            [arg] => arg,
+            _ => {
+                return Err(CodegenError::Unsupported(
+                    "builtins without exactly one argument",
+                ))
+            }
+        };
+        match builtin {
+            Builtin::Print => {
+                if arg.ty == Type::Void && !arg.c.is_empty() {
+                    self.emit(format!("{};", arg.c));
+                }
+                let show = self.show(&arg.ty, &arg.c)?;
+                self.emit(show);
+                self.emit(String::from("pipes_newline();"));
+                Ok(Value::void())
+            }
+            Builtin::Len => {
+                let len = match arg.ty {
+                    Type::Str => "pipes_str_len",
+                    _ => "pipes_list_len",
+                };
+                Ok(Value::new(Type::Int, &format!("{}({})", len, bare(&arg.c))))
+            }
+        }
+    }
+
+    fn unary(&mut self, op: UnaryOp, value: Value) -> Result<Value, CodegenError> {
+        let c = match (op, &value.ty) {
+            (UnaryOp::Neg, Type::Int) => format!("pipes_sub(0, {})", bare(&value.c)),
+            (UnaryOp::Neg, Type::Float) => format!("(-{})", value.c),
+            (UnaryOp::Not, Type::Bool) => format!("(!{})", value.c),
+            (_, Type::BigInt) => return Err(CodegenError::Unsupported("bigints")),
+            _ => return Err(CodegenError::Unsupported("this unary operator")),
+        };
+        Ok(Value::new(value.ty, &c))
+    }
+
+    // && and || only run the right side when they have to. C's do the same,
+    // unless the right side needs setup that has to run first
+    fn logic(&mut self, op: Operation, left: &Exp, right: &Exp) -> Result<Value, CodegenError> {
+        let left = self.exp(left)?;
+        let (setup, right) = self.isolate(|module| module.exp(right))?;
+        let (symbol, run_right) = match op {
+            Operation::And => ("&&", ""),
+            _ => ("||", "!"),
+        };
+        if setup.is_empty() {
+            return Ok(Value::new(
+                Type::Bool,
+                &format!("({} {} {})", left.c, symbol, right.c),
+            ));
+        }
+        let result = self.unique("t");
+        self.emit(format!("bool {} = {};", result, bare(&left.c)));
+        self.open(format!("if ({}{})", run_right, result));
+        self.builder.body += &setup;
+        self.emit(format!("{} = {};", result, bare(&right.c)));
+        self.close();
+        Ok(Value::new(Type::Bool, &result))
+    }
+
+    fn binary(&mut self, op: Operation, left: Value, right: Value) -> Result<Value, CodegenError> {
+        use Operation::*;
+
+        if left.ty == Type::BigInt || right.ty == Type::BigInt {
+            return Err(CodegenError::Unsupported("bigints"));
+        }
+        if op == Equal {
+            return Ok(Value::new(Type::Bool, &self.equal(&left, &right)?));
+        }
+        if op == NotEqual {
+            let equal = self.equal(&left, &right)?;
+            return Ok(Value::new(Type::Bool, &format!("(!{})", equal)));
+        }
+
+        let (a, b) = (bare(&left.c), bare(&right.c));
+        let comparison = match op {
+            GreaterThan => Some(">"),
+            LessThan => Some("<"),
+            GreaterEqual => Some(">="),
+            LessEqual => Some("<="),
+            _ => None,
+        };
+        // mixed arithmetic promotes the int to a float, which C does by itself
+        let (c, ty) = match (comparison, &left.ty, &right.ty) {
+            (Some(comparison), _, _) => (
+                format!("({} {} {})", left.c, comparison, right.c),
+                Type::Bool,
+            ),
+            (None, Type::Int, Type::Int) => {
+                let function = match op {
+                    Add => "pipes_add",
+                    Subtract => "pipes_sub",
+                    Multiply => "pipes_mul",
+                    Divide => "pipes_div",
+                    _ => "pipes_mod",
+                };
+                (format!("{}({}, {})", function, a, b), Type::Int)
+            }
+            (None, _, _) => {
+                let c = match op {
+                    Add => format!("({} + {})", left.c, right.c),
+                    Subtract => format!("({} - {})", left.c, right.c),
+                    Multiply => format!("({} * {})", left.c, right.c),
+                    Divide => format!("({} / {})", left.c, right.c),
+                    _ => format!("fmod({}, {})", a, b),
+                };
+                (c, Type::Float)
+            }
+        };
+        Ok(Value::new(ty, &c))
+    }
+
+    // ints and floats compare by value, everything else has to match exactly
+    fn equal(&mut self, left: &Value, right: &Value) -> Result<String, CodegenError> {
+        let (a, b) = (bare(&left.c), bare(&right.c));
+        match (&left.ty, &right.ty) {
+            (Type::Str, _) => Ok(format!("pipes_str_eq({}, {})", a, b)),
+            (Type::Void, _) => {
+                let mut parts: Vec<String> = [left, right]
+                    .iter()
+                    .filter(|value| !value.c.is_empty())
+                    .map(|value| format!("(void){}", value.c))
+                    .collect();
+                if parts.is_empty() {
+                    return Ok(String::from("true"));
+                }
+                parts.push(String::from("true"));
+                Ok(format!("({})", parts.join(", ")))
+            }
+            (Type::HigherOrderFunction(..), _) => Ok(format!("pipes_closure_eq({}, {})", a, b)),
+            (Type::List(_), _) | (Type::Struct(_), _) => {
+                let eq = self.eq_fn(&left.ty)?;
+                let a = slot_of(&left.ty, &left.c)?;
+                let b = slot_of(&right.ty, &right.c)?;
+                Ok(format!("{}({}, {})", eq, a, b))
+            }
+            _ => Ok(format!("({} == {})", left.c, right.c)),
+        }
+    }
+
+    // fields are evaluated in the order they're written and passed to the
+    // struct's constructor in the order they were declared
+    fn struct_literal(&mut self, name: &str, fields: &[Var]) -> Result<Value, CodegenError> {
+        if name.is_empty() {
+            return Err(CodegenError::UnknownType(String::from("a struct literal")));
+        }
+        let declared = self
+            .structs
+            .fields(name)
+            .ok_or_else(|| CodegenError::UnknownType(String::from(name)))?
+            .to_vec();
+        let void = Exp::Void;
+        let mut exps = vec![];
+        let mut types = vec![];
+        for field in fields {
+            let field_type = declared
+                .iter()
+                .find(|(name, _)| *name == field.name)
+                .map(|(_, field_type)| field_type.clone())
+                .ok_or_else(|| CodegenError::UnknownType(field.name.clone()))?;
+            exps.push(field.value.as_ref().unwrap_or(&void));
+            types.push(field_type);
+        }
+        let values = self.ordered(vec![], &exps, &types)?;
+
+        let mut args = vec![];
+        for (field, _) in &declared {
+            let value = fields
+                .iter()
+                .position(|literal| literal.name == *field)
+                .map(|i| bare(&values[i].c).to_string())
+                .ok_or_else(|| CodegenError::UnknownType(field.clone()))?;
+            args.push(value);
+        }
+        let constructor = self.constructor(name)?;
+        Ok(Value::new(
+            Type::Struct(String::from(name)),
+            &format!("{}({})", constructor, args.join(", ")),
+        ))
+    }
+
+    fn list(&mut self, list_type: Option<&Type>, items: &[Exp]) -> Result<Value, CodegenError> {
+        let item_type = list_type.and_then(|list_type| list_type.element_type());
+        let expected: Vec<Type> = item_type.into_iter().cycle().take(items.len()).collect();
+        let items: Vec<&Exp> = items.iter().collect();
+        let values = self.ordered(vec![], &items, &expected)?;
+        // an empty list nobody gave a type to never has its items looked at
+        let ty = match (list_type, values.first()) {
+            (Some(list_type), _) => list_type.clone(),
+            (None, Some(first)) => Type::List(Box::new(first.ty.clone())),
+            (None, None) => Type::List(Box::new(Type::Void)),
+        };
+        if values.is_empty() {
+            return Ok(Value::new(ty, "pipes_list_of(0, NULL)"));
+        }
+        let slots = values
+            .iter()
+            .map(|value| slot_of(&value.ty, &value.c))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Value::new(
+            ty,
+            &format!(
+                "pipes_list_of({}, (int64_t[]){{{}}})",
+                slots.len(),
+                slots.join(", ")
+            ),
+        ))
+    }
+
+    // a lambda becomes a function of its own, and its closure points at
+    // the boxes of every local it uses from the functions around it
+    fn lambda(&mut self, params: &[Var], block: &[Statement]) -> Result<Value, CodegenError> {
+        let name = self.unique("lambda");
+        let (output, captures) = self.lower_function(&name, params, block, Returns::Infer(None))?;
+        let params = params
+            .iter()
+            .map(|param| param.var_type.clone().unwrap_or(Type::Void))
+            .collect();
+        let env = if captures.is_empty() {
+            String::from("NULL")
+        } else {
+            let env = self.unique("env");
+            self.emit(format!(
+                "struct {}_env *{env} = pipes_alloc(sizeof *{env});",
+                name,
+                env = env
+            ));
+            for (captured, _) in &captures {
+                let pointer = self.box_of(captured)?;
+                self.emit(format!("{}->{} = {};", env, member(captured), pointer));
+            }
+            env
+        };
+        Ok(Value::new(
+            Type::HigherOrderFunction(params, Box::new(output)),
+            &format!("pipes_closure_new((pipes_fn){}, {})", name, env),
+        ))
+    }
+
+    // ----------- helpers ---------- \\
+    // a statement printing a value of the given type
+    fn show(&mut self, ty: &Type, value: &str) -> Result<String, CodegenError> {
+        Ok(match ty {
+            Type::Void => String::from("pipes_write(\"()\");"),
+            Type::Int => format!("pipes_show_int({});", bare(value)),
+            Type::Bool => format!("pipes_show_bool({});", bare(value)),
+            Type::Float => format!("pipes_show_float({});", bare(value)),
+            Type::Str => format!("pipes_write({});", bare(value)),
+            Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+            Type::HigherOrderFunction(..) => format!("pipes_show_closure({});", bare(value)),
+            Type::List(_) | Type::Struct(_) => {
+                format!("{}({});", self.show_fn(ty)?, slot_of(ty, value)?)
+            }
+        })
+    }
+
+    // a function printing a value of the given type from its slot, written
+    // the first time it's needed
+    fn show_fn(&mut self, ty: &Type) -> Result<String, CodegenError> {
+        match ty {
+            Type::Int => return Ok(String::from("pipes_show_int")),
+            Type::Bool => return Ok(String::from("pipes_show_bool")),
+            _ => {}
+        }
+        let key = format!("show {}", ty);
+        if let Some(name) = self.helpers.get(&key) {
+            return Ok(name.clone());
+        }
+        let name = self.unique("show");
+        self.helpers.insert(key, name.clone());
+        let header = format!("static void {}(int64_t slot)", name);
+        self.prototypes.push(format!("{};\n", header));
+
+        let value = self.unslot(ty, "slot")?;
+        let mut body = String::new();
+        match ty {
+            Type::List(item_type) => {
+                let show = self.show_fn(item_type)?;
+                body += &format!("    pipes_show_list({}, {});\n", bare(&value), show);
+            }
+            Type::Struct(struct_name) => {
+                let fields = self.structs.fields(struct_name).unwrap_or(&[]).to_vec();
+                body += &format!("    {} = {};\n", self.declare(ty, "value")?, value);
+                body += &format!(
+                    "    pipes_write({});\n",
+                    string_literal(&format!("{} {{", struct_name))
+                );
+                for (i, (field, field_type)) in fields.iter().enumerate() {
+                    let separator = if i > 0 { "," } else { "" };
+                    body += &format!(
+                        "    pipes_write({});\n",
+                        string_literal(&format!("{} {}: ", separator, field))
+                    );
+                    let field_value = format!("value->{}", member(field));
+                    body += &format!("    {}\n", self.show(field_type, &field_value)?);
+                }
+                body += "    pipes_write(\" }\");\n";
+            }
+            _ => body += &format!("    {}\n", self.show(ty, &value)?),
+        }
+        self.functions
+            .push(format!("// prints a {}\n{} {{\n{}}}\n", ty, header, body));
+        Ok(name)
+    }
+
+    // a function comparing two values of the given type from their slots,
+    // giving back 1 or 0
+    fn eq_fn(&mut self, ty: &Type) -> Result<String, CodegenError> {
+        let key = format!("eq {}", ty);
+        if let Some(name) = self.helpers.get(&key) {
+            return Ok(name.clone());
+        }
+        let name = self.unique("eq");
+        self.helpers.insert(key, name.clone());
+        let header = format!("static int32_t {}(int64_t a, int64_t b)", name);
+        self.prototypes.push(format!("{};\n", header));
+
+        let a = Value::new(ty.clone(), &self.unslot(ty, "a")?);
+        let b = Value::new(ty.clone(), &self.unslot(ty, "b")?);
+        let mut body = String::new();
+        let result = match ty {
+            Type::List(item_type) => {
+                let eq = self.eq_fn(item_type)?;
+                format!("pipes_list_eq({}, {}, {})", a.c, b.c, eq)
+            }
+            Type::Struct(struct_name) => {
+                let fields = self.structs.fields(struct_name).unwrap_or(&[]).to_vec();
+                if !fields.is_empty() {
+                    body += &format!("    {} = {};\n", self.declare(ty, "x")?, a.c);
+                    body += &format!("    {} = {};\n", self.declare(ty, "y")?, b.c);
+                }
+                let mut all = vec![];
+                for (field, field_type) in &fields {
+                    let x = Value::new(field_type.clone(), &format!("x->{}", member(field)));
+                    let y = Value::new(field_type.clone(), &format!("y->{}", member(field)));
+                    all.push(self.equal(&x, &y)?);
+                }
+                if all.is_empty() {
+                    String::from("true")
+                } else {
+                    all.join(" && ")
+                }
+            }
+            _ => bare(&self.equal(&a, &b)?).to_string(),
+        };
+        body += &format!("    return {};\n", result);
+        self.functions.push(format!(
+            "// compares two {}\n{} {{\n{}}}\n",
+            ty, header, body
+        ));
+        Ok(name)
+    }
+
+    // a function calling a closure of the given type with its environment
+    fn call_fn(&mut self, ty: &Type) -> Result<String, CodegenError> {
+        let key = format!("call {}", ty);
+        if let Some(name) = self.helpers.get(&key) {
+            return Ok(name.clone());
+        }
+        let (params, output) = match ty {
+            Type::HigherOrderFunction(params, output) => (params, output),
+            _ => return Err(CodegenError::Unsupported("calling a non function")),
+        };
+        let name = self.unique("call");
+        self.helpers.insert(key, name.clone());
+
+        let mut param_types = vec![String::from("void *")];
+        let mut param_list = vec![String::from("pipes_closure *f")];
+        let mut args = vec![String::from("f->env")];
+        for (i, param) in params.iter().enumerate() {
+            param_types.push(self.c_type(param)?);
+            param_list.push(self.declare(param, &format!("a{}", i))?);
+            args.push(format!("a{}", i));
+        }
+        let pointer = self.declare_return(output, &format!("(*)({})", param_types.join(", ")))?;
+        let header = format!(
+            "static {}",
+            self.declare_return(output, &format!("{}({})", name, param_list.join(", ")))?
+        );
+        let call = format!("(({})f->fn)({})", pointer, args.join(", "));
+        let body = match **output {
+            Type::Void => format!("    {};\n", call),
+            _ => format!("    return {};\n", call),
+        };
+        self.prototypes.push(format!("{};\n", header));
+        self.functions
+            .push(format!("// calls a {}\n{} {{\n{}}}\n", ty, header, body));
+        Ok(name)
+    }
+
+    // a function putting a struct together on the heap from its fields
+    fn constructor(&mut self, struct_name: &str) -> Result<String, CodegenError> {
+        let key = format!("new {}", struct_name);
+        if let Some(name) = self.helpers.get(&key) {
+            return Ok(name.clone());
+        }
+        let name = self.unique(&format!("new_{}", struct_name));
+        self.helpers.insert(key, name.clone());
+        let fields = self.structs.fields(struct_name).unwrap_or(&[]).to_vec();
+        let ty = Type::Struct(String::from(struct_name));
+
+        let mut param_list = vec![];
+        let mut body = format!(
+            "    {} = pipes_alloc(sizeof *value);\n",
+            self.declare(&ty, "value")?
+        );
+        for (field, field_type) in &fields {
+            let param = self.unique(field);
+            param_list.push(self.declare(field_type, &param)?);
+            body += &format!("    value->{} = {};\n", member(field), param);
+        }
+        body += "    return value;\n";
+        if param_list.is_empty() {
+            param_list.push(String::from("void"));
+        }
+        let header = format!(
+            "static {}",
+            self.declare(&ty, &format!("{}({})", name, param_list.join(", ")))?
+        );
+        self.prototypes.push(format!("{};\n", header));
+        self.functions.push(format!("{} {{\n{}}}\n", header, body));
+        Ok(name)
+    }
+
+    // a named function as a value, a closure without an environment that's
+    // the same every time so it compares equal to itself
+    fn closure(&mut self, function: &str, pipes_name: &str) -> String {
+        let key = format!("closure {}", function);
+        if let Some(name) = self.helpers.get(&key) {
+            return format!("&{}", name);
+        }
+        let name = format!("{}_closure", function);
+        self.globals.push(format!(
+            "static pipes_closure {} = {{(pipes_fn){}, NULL, {}}};\n",
+            name,
+            function,
+            string_literal(pipes_name)
+        ));
+        self.helpers.insert(key, name.clone());
+        format!("&{}", name)
+    }
+
+    // ----------- names ---------- \\
+    // what a name refers to. a local of an enclosing function is captured,
+    // and used through the pointer to its box in the closure's environment
+    fn lookup(&mut self, name: &str) -> Result<Binding, CodegenError> {
+        match self.scopes.lookup(name) {
+            Some((Binding::Var(ty, ..), Reach::Outer)) => {
+                let ty = ty.clone();
+                if !self
+                    .builder
+                    .captures
+                    .iter()
+                    .any(|(captured, _)| captured == name)
+                {
+                    self.builder.captures.push((String::from(name), ty.clone()));
+                }
+                let pointer = format!("captured->{}", member(name));
+                Ok(Binding::Var(ty, format!("(*{})", pointer), Some(pointer)))
+            }
+            Some((binding, _)) => Ok(binding.clone()),
+            None => Err(CodegenError::UndefinedVariable(String::from(name))),
+        }
+    }
+
+    // the pointer to the box a variable a closure captures lives in
+    fn box_of(&mut self, name: &str) -> Result<String, CodegenError> {
+        match self.lookup(name)? {
+            Binding::Var(_, _, Some(pointer)) => Ok(pointer),
+            _ => Err(CodegenError::Captures(String::from(name))),
+        }
+    }
+
+    // a new variable in the current scope, in a box if a lambda might
+    // capture it
+    fn local(&mut self, name: &str, ty: Type, value: &str) -> Result<(), CodegenError> {
+        let c_name = self.unique(name);
+        if self.builder.boxed.contains(name) {
+            let pointer = format!("*{}", c_name);
+            let line = format!(
+                "{} = pipes_alloc(sizeof({}));",
+                self.declare(&ty, &pointer)?,
+                self.c_type(&ty)?
+            );
+            self.emit(line);
+            self.emit(format!("*{} = {};", c_name, bare(value)));
+            self.scopes.define(
+                name,
+                Binding::Var(ty, format!("(*{})", c_name), Some(c_name)),
+            );
+        } else {
+            let line = format!("{} = {};", self.declare(&ty, &c_name)?, bare(value));
+            self.emit(line);
+            self.scopes.define(name, Binding::Var(ty, c_name, None));
+        }
+        Ok(())
+    }
+
+    // keeps a value in a temporary, so what runs after it can't change it
+    fn temp(&mut self, value: Value) -> Result<Value, CodegenError> {
+        if value.ty == Type::Void {
+            if !value.c.is_empty() {
+                self.emit(format!("{};", value.c));
+            }
+            return Ok(Value::void());
+        }
+        if is_constant(&value.c) {
+            return Ok(value);
+        }
+        let temp = self.unique("t");
+        let line = format!("{} = {};", self.declare(&value.ty, &temp)?, bare(&value.c));
+        self.emit(line);
+        Ok(Value::new(value.ty, &temp))
+    }
+
+    fn unique(&mut self, name: &str) -> String {
+        self.next += 1;
+        format!("{}_{}", c_name(name), self.next)
+    }
+
+    // ----------- types ---------- \\
+    // the C type of a value that can be stored somewhere
+    fn c_type(&self, ty: &Type) -> Result<String, CodegenError> {
+        Ok(String::from(match ty {
+            Type::Void => return Err(CodegenError::Unsupported("storing void values")),
+            Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+            Type::Int => "int64_t",
+            Type::Float => "double",
+            Type::Bool => "bool",
+            Type::Str => "const char *",
+            Type::List(_) => "pipes_list *",
+            Type::HigherOrderFunction(..) => "pipes_closure *",
+            Type::Struct(name) => {
+                let tag = self
+                    .tags
+                    .get(name)
+                    .ok_or_else(|| CodegenError::UnknownType(name.clone()))?;
+                return Ok(format!("struct {} *", tag));
+            }
+        }))
+    }
+
+    // a declaration of something of the given type
+    fn declare(&self, ty: &Type, declarator: &str) -> Result<String, CodegenError> {
+        let c_type = self.c_type(ty)?;
+        if c_type.ends_with('*') {
+            Ok(format!("{}{}", c_type, declarator))
+        } else {
+            Ok(format!("{} {}", c_type, declarator))
+        }
+    }
+
+    fn declare_return(&self, ty: &Type, declarator: &str) -> Result<String, CodegenError> {
+        match ty {
+            Type::Void => Ok(format!("void {}", declarator)),
+            ty => self.declare(ty, declarator),
+        }
+    }
+
+    // lists and the helpers carry every value as an int64_t
+    fn unslot(&self, ty: &Type, slot: &str) -> Result<String, CodegenError> {
+        Ok(match ty {
+            Type::Int => String::from(slot),
+            Type::Void => String::new(),
+            Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+            Type::Float => format!("pipes_slot_float({})", slot),
+            Type::Bool => format!("({} != 0)", slot),
+            _ => format!("(({})(intptr_t){})", self.c_type(ty)?, slot),
+        })
+    }
+
+    // ----------- output ---------- \\
+    fn emit(&mut self, line: String) {
+        for _ in 0..self.builder.indent {
+            self.builder.body += "    ";
+        }
+        self.builder.body += &line;
+        self.builder.body += "\n";
+    }
+
+    fn open(&mut self, line: String) {
+        self.emit(format!("{} {{", line));
+        self.builder.indent += 1;
+    }
+
+    fn close(&mut self) {
+        self.builder.indent -= 1;
+        self.emit(String::from("}"));
+    }
+
+    // a line like } else { between two blocks
+    fn reopen(&mut self, line: String) {
+        self.builder.indent -= 1;
+        self.emit(line);
+        self.builder.indent += 1;
+    }
+
+    // lowers something on its own, giving back the statements it needed to
+    // run first separately, indented to go in a block one level down
+    fn isolate<T>(
+        &mut self,
+        lower: impl FnOnce(&mut Self) -> Result<T, CodegenError>,
+    ) -> Result<(String, T), CodegenError> {
+        let outer = std::mem::take(&mut self.builder.body);
+        self.builder.indent += 1;
+        let result = lower(self);
+        self.builder.indent -= 1;
+        let setup = std::mem::replace(&mut self.builder.body, outer);
+        Ok((setup, result?))
+    }
+}
+
+fn slot_of(ty: &Type, value: &str) -> Result<String, CodegenError> {
+    Ok(match ty {
+        Type::Int => String::from(bare(value)),
+        Type::Void if value.is_empty() => String::from("0"),
+        Type::Void => format!("((void){}, 0)", value),
+        Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+        Type::Float => format!("pipes_float_slot({})", bare(value)),
+        Type::Bool => format!("(int64_t){}", value),
+        _ => format!("(int64_t)(intptr_t){}", value),
+    })
+}
+
+// ----------- effects ---------- \\
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Effects {
+    None,
+    // might stop the program, like int arithmetic overflowing
+    Fails,
+    // might print or change variables
+    Calls,
+}
+
+fn effects(exp: &Exp) -> Effects {
+    match exp {
+        Exp::Call(..) | Exp::Pipe(..) => Effects::Calls,
+        Exp::Unary(op, exp) => {
+            let own = match op {
+                UnaryOp::Neg => Effects::Fails,
+                UnaryOp::Not => Effects::None,
+            };
+            max(own, effects(exp))
+        }
+        Exp::Field(exp, _) => effects(exp),
+        Exp::Op(op, left, right) => {
+            let own = match op {
+                Operation::Add
+                | Operation::Subtract
+                | Operation::Multiply
+                | Operation::Divide
+                | Operation::Modulo => Effects::Fails,
+                _ => Effects::None,
+            };
+            max(own, max(effects(left), effects(right)))
+        }
+        Exp::Struct(_, fields) => fields
+            .iter()
+            .filter_map(|field| field.value.as_ref())
+            .map(effects)
+            .fold(Effects::None, max),
+        Exp::List(_, items) => items.iter().map(effects).fold(Effects::None, max),
+        _ => Effects::None,
+    }
+}
+
+// whether exp's value depends on anything that could change
+fn reads(exp: &Exp) -> bool {
+    match exp {
+        Exp::Int(_) | Exp::BigInt(_) | Exp::Float(_) | Exp::Str(_) | Exp::Bool(_) | Exp::Void => {
+            false
+        }
+        // a closure only holds on to boxes, which stay where they are
+        Exp::HOF(..) => false,
+        Exp::Unary(_, exp) => reads(exp),
+        Exp::Op(_, left, right) => reads(left) || reads(right),
+        Exp::Struct(_, fields) => fields
+            .iter()
+            .filter_map(|field| field.value.as_ref())
+            .any(reads),
+        Exp::List(_, items) => items.iter().any(reads),
+        _ => true,
+    }
+}
+
+// ----------- C text ---------- \\
+// a Pipes name as a C identifier. anything past ascii is spelled out
+fn c_name(name: &str) -> String {
+    let mut c = String::new();
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() || ch == '_' {
+            c.push(ch);
+        } else {
+            c += &format!("u{:x}_", ch as u32);
+        }
+    }
+    c
+}
+
+const RESERVED: &[&str] = &[
+    "auto", "bool", "break", "case", "char", "const", "continue", "default", "do", "double",
+    "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int", "long",
+    "register", "restrict", "return", "short", "signed", "sizeof", "static", "struct", "switch",
+    "true", "typedef", "union", "unsigned", "void", "volatile", "while", "errno", "stdin",
+    "stdout", "stderr",
+];
+
+// a struct field or captured variable, which only has to stay clear of
+// keywords and the macros the runtime's headers define
+fn member(name: &str) -> String {
+    let c = c_name(name);
+    if RESERVED.contains(&c.as_str()) {
+        c + "_"
+    } else {
+        c
+    }
+}
+
+// an expression without the parentheses around all of it, for where it
+// doesn't need them
+fn bare(c: &str) -> &str {
+    if !c.starts_with('(') || !c.ends_with(')') {
+        return c;
+    }
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, ch) in c.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 && i != c.len() - 1 {
+                    return c;
+                }
+            }
+            _ => {}
+        }
+    }
+    &c[1..c.len() - 1]
+}
+
+fn is_constant(c: &str) -> bool {
+    let unsigned = c.trim_start_matches("(-").trim_end_matches(')');
+    c.starts_with('"')
+        || unsigned.starts_with(|ch: char| ch.is_ascii_digit())
+        || ["true", "false", "NULL", "INFINITY", "NAN", "INT64_MIN"].contains(&unsigned)
+}
+
+fn int_literal(i: i64) -> String {
+    match i {
+        i64::MIN => String::from("INT64_MIN"),
+        i if i < 0 => format!("({})", i),
+        i => i.to_string(),
+    }
+}
+
+// {:?} always gives the shortest digits that read back as the same double
+fn float_literal(x: f64) -> String {
+    if x.is_nan() {
+        String::from("NAN")
+    } else if x.is_infinite() {
+        String::from(if x > 0.0 { "INFINITY" } else { "(-INFINITY)" })
+    } else if x.is_sign_negative() {
+        format!("({:?})", x)
+    } else {
+        format!("{:?}", x)
+    }
+}
+
+// escapes are octal, which can't run on into the next character the way
+// hex ones do. ? is escaped so nothing reads as a trigraph
+fn string_literal(s: &str) -> String {
+    let mut c = String::from("\"");
+    for byte in s.bytes() {
+        match byte {
+            b'"' | b'\\' | b'?' => {
+                c.push('\\');
+                c.push(byte as char);
+            }
+            b' '..=b'~' => c.push(byte as char),
+            _ => c += &format!("\\{:03o}", byte),
+        }
+    }
+    c.push('"');
+    c
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::codegen::harness::{self, Run};
+    use std::process::Command;
+
+    fn compile_source(source: &str) -> Result<String, CodegenError> {
+        let (program, structs) = harness::checked(source);
+        compile(&program, &structs)
+    }
+
+    // builds the program with cc, which has to accept it as strict C99
+    // without a warning, and runs it. None when cc isn't installed
+    pub fn run_native(source: &str) -> Option<Run> {
+        if !harness::installed("cc") {
+            return None;
+        }
+        let c = compile_source(source).unwrap();
+        Some(harness::in_scratch_dir("c", |dir| {
+            std::fs::write(dir.join("out.c"), c).unwrap();
+            let cc = Command::new("cc")
+                .args([
+                    "-std=c99",
+                    "-pedantic",
+                    "-Wall",
+                    "-Wextra",
+                    "-Wno-unused-parameter",
+                    "out.c",
+                    "-lm",
+                    "-o",
+                    "out",
+                ])
+                .current_dir(dir)
+                .output()
+                .unwrap();
+            assert!(
+                cc.status.success() && cc.stderr.is_empty(),
+                "{}",
+                String::from_utf8_lossy(&cc.stderr)
+            );
+            harness::finished(Command::new(dir.join("out")).output().unwrap())
+        }))
+    }
+
+    // ----------- lowering tests ---------- \\
+    #[test]
+    fn compile_functions() {
+        let c =
+            compile_source("func add(a: int, b: int) -> int { return a + b; } add(1, 2)").unwrap();
+        assert!(c.contains("static int64_t add_1(void *env, int64_t a_2, int64_t b_3);"));
+        assert!(c.contains("    return pipes_add(a_2, b_3);"));
+        assert!(c.contains("int main(void) {\n    add_1(NULL, 1, 2);\n    return 0;\n}"));
+    }
+
+    #[test]
+    fn compile_globals_and_structs() {
+        let c = compile_source(
+            "struct Point { x: int, y: float, double: str, }
+            let origin: Point = {x: 0, y: -0.5, double: \"o\",};",
+        )
+        .unwrap();
+        assert!(c.contains(
+            "struct Point_1 {\n    int64_t x;\n    double y;\n    const char *double_;\n};"
+        ));
+        assert!(c.contains("static struct Point_1 *origin_"));
+        assert!(c.contains("= new_Point_"));
+        assert!(c.contains("origin_6 = new_Point_2(0, -0.5, \"o\");"));
+    }
+
+    #[test]
+    fn compile_names() {
+        assert_eq!(c_name("héllo_2"), "hue9_llo_2");
+        assert_eq!(member("int"), "int_");
+        assert_eq!(member("x"), "x");
+        assert_eq!(
+            string_literal("say \"hi\"??\n"),
+            "\"say \\\"hi\\\"\\?\\?\\012\""
+        );
+        assert_eq!(bare("(a + b)"), "a + b");
+        assert_eq!(bare("(a) + (b)"), "(a) + (b)");
+        assert_eq!(bare("(f(\")\"))"), "f(\")\")");
+        assert_eq!(int_literal(-3), "(-3)");
+        assert_eq!(float_literal(1e20), "1e20");
+        // two functions with the same name get their own C names
+        let c = compile_source(
+            "func f() -> int { func g() -> int { return 1; } return g(); }
+            func h() -> int { func g() -> int { return 2; } return g(); }",
+        )
+        .unwrap();
+        assert!(c.contains("static int64_t g_3(void *env)"));
+        assert!(c.contains("static int64_t g_4(void *env)"));
+    }
+
+    #[test]
+    fn compile_unsupported() {
+        assert_eq!(
+            compile_source("let big: bigint = 99999999999999999999n;"),
+            Err(CodegenError::Unsupported("bigints"))
+        );
+        // only lambdas capture, named functions are called without a closure
+        assert_eq!(
+            compile_source(
+                "func outer(start: int) -> int {
+                    func inner() -> int { return start; }
+                    return inner();
+                }"
+            ),
+            Err(CodegenError::Captures(String::from("start")))
+        );
+        // nothing says what a is here
+        assert_eq!(
+            compile_source("print((a) { return a; });"),
+            Err(CodegenError::UnknownType(String::from("a")))
+        );
+    }
+}
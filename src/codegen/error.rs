@@ -0,0 +1,29 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CodegenError {
+    // something the type checker allows that this backend can't lower yet
+    Unsupported(&'static str),
+    // a lambda parameter or struct literal the checker left untyped
+    UnknownType(String),
+    // a lambda or nested function using a local of the function around it
+    Captures(String),
+    UndefinedVariable(String),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodegenError::Unsupported(what) => write!(f, "{} can't be compiled yet", what),
+            CodegenError::UnknownType(name) => {
+                write!(f, "can't work out the type of {}, try annotating it", name)
+            }
+            CodegenError::Captures(name) => write!(
+                f,
+                "{} is a local of an enclosing function, which can't be captured yet",
+                name
+            ),
+            CodegenError::UndefinedVariable(name) => write!(f, "undefined variable {}", name),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// what the backend tests share: checking a program, running it on the
+// interpreter, and building and running what a backend made of it somewhere
+// out of the way. each backend brings its own run_native
+use crate::ast::Program;
+use crate::interpreter::Interpreter;
+use crate::parser::parse;
+use crate::tokenizer::tokenizer::tokenize;
+use crate::typeck::check;
+use crate::types::StructRegistry;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// how a native build ran: its exit code, stdout and stderr
+pub type Run = (i32, String, String);
+
+// builds and runs a program with a native backend, None when a tool it needs
+// isn't installed
+pub type RunNative = fn(&str) -> Option<Run>;
+
+// the tests only give the backends well typed programs
+pub fn checked(source: &str) -> (Program, StructRegistry) {
+    let mut program = parse(tokenize(source).unwrap()).unwrap();
+    let structs = check(&mut program).unwrap();
+    (program, structs)
+}
+
+// runs the checked program, the way it runs after type checking fills in
+// the names of anonymous structs
+pub fn interpret(source: &str) -> String {
+    let (program, _) = checked(source);
+    let mut interpreter = Interpreter::with_output(vec![]);
+    interpreter.run(&program).unwrap();
+    String::from_utf8(interpreter.into_output()).unwrap()
+}
+
+// native tests are skipped when a tool they need isn't installed
+pub fn installed(tool: &str) -> bool {
+    Command::new(tool).arg("--version").output().is_ok()
+}
+
+// builds in a fresh directory named after the backend, which is removed
+// once build is done with it
+pub fn in_scratch_dir<T>(backend: &str, build: impl FnOnce(&Path) -> T) -> T {
+    static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = std::env::temp_dir().join(format!(
+        "pipes-{}-{}-{}",
+        backend,
+        std::process::id(),
+        BUILDS.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let result = build(&dir);
+    std::fs::remove_dir_all(&dir).ok();
+    result
+}
+
+pub fn finished(output: Output) -> Run {
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
@@ -0,0 +1,1403 @@
+// lowers a type checked program to a textual LLVM IR module, which links
+// against the C runtime in RUNTIME:
+//
+//     llc -filetype=obj -relocation-model=pic out.ll -o out.o
+//     cc out.o runtime.c -o out
+//
+// the module uses opaque pointers, so LLVM 14 needs -opaque-pointers. ints
+// are i64, floats double and bools i1. strings, lists, structs and functions
+// are all pointers. lambdas and nested functions become top level functions,
+// so they can't capture locals yet
+use super::{signature, CodegenError, LowerBlock, Reach, Returns, Scopes};
+use crate::ast::*;
+use crate::interpreter::Builtin;
+use crate::types::{StructRegistry, Type};
+use std::collections::HashSet;
+
+pub use super::RUNTIME;
+
+// the helper printing a function value looks its name up with
+const NAME_FN: &str = "@func.name";
+
+const DECLARATIONS: &str = "\
+declare void @pipes_panic(ptr) noreturn
+declare void @pipes_overflow() noreturn
+declare ptr @pipes_alloc(i64)
+declare i64 @pipes_div(i64, i64)
+declare i64 @pipes_mod(i64, i64)
+declare i64 @pipes_str_len(ptr)
+declare i32 @pipes_str_eq(ptr, ptr)
+declare ptr @pipes_list_new(i64)
+declare i64 @pipes_list_len(ptr)
+declare i64 @pipes_list_get(ptr, i64)
+declare void @pipes_list_set(ptr, i64, i64)
+declare i32 @pipes_list_eq(ptr, ptr, ptr)
+declare void @pipes_write(ptr)
+declare void @pipes_newline()
+declare void @pipes_show_int(i64)
+declare void @pipes_show_bool(i64)
+declare void @pipes_show_float(double)
+declare void @pipes_show_list(ptr, ptr)
+declare { i64, i1 } @llvm.sadd.with.overflow.i64(i64, i64)
+declare { i64, i1 } @llvm.ssub.with.overflow.i64(i64, i64)
+declare { i64, i1 } @llvm.smul.with.overflow.i64(i64, i64)
+";
+
+// the structs are the ones check gave back for the program
+pub fn compile(program: &Program, structs: &StructRegistry) -> Result<String, CodegenError> {
+    let mut module = Module::new(structs);
+    module.program(program)?;
+    Ok(module.finish())
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+    // a variable's type and the alloca or global it lives in
+    Var(Type, String),
+    // a named function's symbol, parameter types and output type
+    Function(String, Vec<Type>, Type),
+    Builtin(Builtin),
+}
+
+// a lowered expression: its type and the operand holding it, which is
+// empty for void
+#[derive(Debug, Clone)]
+struct Operand {
+    ty: Type,
+    value: String,
+}
+
+impl Operand {
+    fn new(ty: Type, value: &str) -> Operand {
+        Operand {
+            ty,
+            value: String::from(value),
+        }
+    }
+
+    fn void() -> Operand {
+        Operand::new(Type::Void, "")
+    }
+}
+
+// the function being written
+struct Builder {
+    allocas: String,
+    body: String,
+    // the block being written to, for phis
+    label: String,
+    next: usize,
+    returns: Returns,
+}
+
+impl Builder {
+    fn new(returns: Returns) -> Builder {
+        Builder {
+            allocas: String::new(),
+            body: String::new(),
+            label: String::from("start"),
+            next: 0,
+            returns,
+        }
+    }
+
+    // the allocas all go in the entry block so loops don't grow the stack
+    fn finish(self, header: &str, closing: &str) -> String {
+        format!(
+            "{} {{\nentry:\n{}  br label %start\nstart:\n{}  {}\n}}\n",
+            header, self.allocas, self.body, closing
+        )
+    }
+}
+
+struct Module<'a> {
+    structs: &'a StructRegistry,
+    scopes: Scopes<Binding>,
+    builder: Builder,
+    types: Vec<String>,
+    strings: Vec<String>,
+    globals: Vec<String>,
+    functions: Vec<String>,
+    // every module level symbol given out, so two functions named f don't clash
+    symbols: HashSet<String>,
+    // the print and equality helpers written so far
+    helpers: HashSet<String>,
+    // the symbol and source name of every named function, for printing them
+    names: Vec<(String, String)>,
+}
+
+impl<'a> Module<'a> {
+    fn new(structs: &'a StructRegistry) -> Module<'a> {
+        Module {
+            structs,
+            scopes: Scopes::new(),
+            builder: Builder::new(Returns::Main),
+            types: vec![],
+            strings: vec![],
+            globals: vec![],
+            functions: vec![],
+            symbols: HashSet::new(),
+            helpers: HashSet::new(),
+            names: vec![],
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if self.helpers.contains(NAME_FN) {
+            self.name_fn();
+        }
+        let mut module = String::new();
+        for definition in &self.types {
+            module += definition;
+        }
+        for (i, s) in self.strings.iter().enumerate() {
+            module += &format!(
+                "@.str.{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n",
+                i,
+                s.len() + 1,
+                escape(s)
+            );
+        }
+        for global in &self.globals {
+            module += global;
+        }
+        module += "\n";
+        module += DECLARATIONS;
+        for function in &self.functions {
+            module += "\n";
+            module += function;
+        }
+        module += "\n";
+        module += &self.builder.finish("define i32 @main()", "ret i32 0");
+        module
+    }
+
+    // ----------- items ---------- \\
+    fn program(&mut self, program: &Program) -> Result<(), CodegenError> {
+        for (name, fields) in self.structs.iter() {
+            let fields = fields
+                .iter()
+                .map(|(_, field_type)| value_type(field_type))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.types.push(format!(
+                "{} = type {{ {} }}\n",
+                struct_type(name),
+                fields.join(", ")
+            ));
+        }
+        for builtin in Builtin::ALL.iter() {
+            self.scopes
+                .define_global(builtin.name(), Binding::Builtin(*builtin));
+        }
+
+        // top level functions can be called before they're declared
+        let mut symbols = vec![];
+        for function in program.functions() {
+            let (params, output) = signature(function)?;
+            let symbol = self.symbol(&format!("fn.{}", function.name));
+            self.scopes.define_global(
+                &function.name,
+                Binding::Function(symbol.clone(), params, output),
+            );
+            symbols.push(symbol);
+        }
+        let mut symbols = symbols.into_iter();
+
+        // top level statements run in main, and their blocks' locals are main's
+        self.scopes.push_frame();
+        for item in &program.items {
+            match item {
+                Item::Struct(_) => {}
+                Item::Function(function) => {
+                    let symbol = symbols.next().unwrap();
+                    self.function(&symbol, function)?;
+                }
+                Item::Global(var, exp) => self.global(var, exp)?,
+                Item::Statement(stmt) => self.stmt(stmt)?,
+            }
+        }
+        self.scopes.pop_frame();
+        Ok(())
+    }
+
+    fn global(&mut self, var: &Var, exp: &Exp) -> Result<(), CodegenError> {
+        let ty = var.var_type.clone().unwrap_or(Type::Void);
+        let value = self.exp_as(exp, &ty)?;
+        let llvm_type = value_type(&ty)?;
+        let symbol = self.symbol(&format!("global.{}", var.name));
+        self.globals.push(format!(
+            "{} = internal global {} zeroinitializer\n",
+            symbol, llvm_type
+        ));
+        self.emit(format!(
+            "store {} {}, ptr {}",
+            llvm_type, value.value, symbol
+        ));
+        self.scopes
+            .define_global(&var.name, Binding::Var(ty, symbol));
+        Ok(())
+    }
+
+    fn function(&mut self, symbol: &str, function: &Function) -> Result<(), CodegenError> {
+        let (_, output) = signature(function)?;
+        self.names
+            .push((String::from(symbol), function.name.clone()));
+        self.lower_function(
+            symbol,
+            &function.param,
+            &function.block,
+            Returns::Expected(output),
+        )?;
+        Ok(())
+    }
+
+    // writes out a function in a frame of its own, giving back its output type
+    fn lower_function(
+        &mut self,
+        symbol: &str,
+        params: &[Var],
+        block: &[Statement],
+        returns: Returns,
+    ) -> Result<Type, CodegenError> {
+        let outer = std::mem::replace(&mut self.builder, Builder::new(returns));
+        self.scopes.push_frame();
+        let mut param_list = vec![];
+        for (i, param) in params.iter().enumerate() {
+            let param_type = param
+                .var_type
+                .clone()
+                .ok_or_else(|| CodegenError::UnknownType(param.name.clone()))?;
+            let arg = format!("%arg.{}", i);
+            param_list.push(format!("{} {}", value_type(&param_type)?, arg));
+            self.local(&param.name, &Operand::new(param_type, &arg))?;
+        }
+        self.stmts(block)?;
+        self.scopes.pop_frame();
+        let builder = std::mem::replace(&mut self.builder, outer);
+
+        let output = builder.returns.output();
+        // the checker made sure every path through a function that gives
+        // something back returns
+        let closing = match output {
+            Type::Void => String::from("ret void"),
+            _ => String::from("unreachable"),
+        };
+        let header = format!(
+            "define internal {} {}({})",
+            return_type(&output)?,
+            symbol,
+            param_list.join(", ")
+        );
+        self.functions.push(builder.finish(&header, &closing));
+        Ok(output)
+    }
+}
+
+impl LowerBlock for Module<'_> {
+    type Binding = Binding;
+
+    fn scopes(&mut self) -> &mut Scopes<Binding> {
+        &mut self.scopes
+    }
+
+    // ----------- statements ---------- \\
+    fn stmt(&mut self, stmt: &Statement) -> Result<(), CodegenError> {
+        match stmt {
+            Statement::Assignment(var, exp) if var.var_type.is_some() => {
+                let declared = var.var_type.clone().unwrap();
+                let value = self.exp_as(exp, &declared)?;
+                self.local(&var.name, &Operand::new(declared, &value.value))
+            }
+            Statement::Assignment(var, exp) => match self.lookup(&var.name)? {
+                Binding::Var(ty, ptr) => {
+                    let value = self.exp_as(exp, &ty)?;
+                    let llvm_type = value_type(&ty)?;
+                    self.emit(format!("store {} {}, ptr {}", llvm_type, value.value, ptr));
+                    Ok(())
+                }
+                _ => Err(CodegenError::Unsupported("assigning to a function")),
+            },
+            Statement::Function(function) => {
+                let (params, output) = signature(function)?;
+                let symbol = self.symbol(&format!("fn.{}", function.name));
+                // defined first so the body can call itself
+                self.scopes.define(
+                    &function.name,
+                    Binding::Function(symbol.clone(), params, output),
+                );
+                self.function(&symbol, function)
+            }
+            Statement::Return(exp, _) => self.ret(exp),
+            Statement::If(if_stmt) => self.if_stmt(if_stmt),
+            Statement::For(for_stmt) => self.for_stmt(for_stmt),
+            Statement::While(while_stmt) => self.while_stmt(while_stmt),
+            Statement::Exp(exp, _) => {
+                self.exp(exp)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'a> Module<'a> {
+    fn ret(&mut self, exp: &Exp) -> Result<(), CodegenError> {
+        let value = match &self.builder.returns {
+            Returns::Expected(output) => {
+                let output = output.clone();
+                self.exp_as(exp, &output)?
+            }
+            _ => self.exp(exp)?,
+        };
+        let output = match self.builder.returns.returning(&value.ty) {
+            Some(output) => output,
+            None => {
+                self.terminate(String::from("ret i32 0"));
+                return Ok(());
+            }
+        };
+        match output {
+            Type::Void => self.terminate(String::from("ret void")),
+            output => {
+                let line = format!("ret {} {}", value_type(&output)?, value.value);
+                self.terminate(line);
+            }
+        }
+        Ok(())
+    }
+
+    fn if_stmt(&mut self, if_stmt: &IfStatement) -> Result<(), CodegenError> {
+        let condition = self.exp(&if_stmt.condition)?;
+        let then = self.label("then");
+        let otherwise = self.label("else");
+        let end = self.label("endif");
+        self.emit(format!(
+            "br i1 {}, label %{}, label %{}",
+            condition.value, then, otherwise
+        ));
+
+        self.start(&then);
+        self.block(&if_stmt.true_branch)?;
+        self.emit(format!("br label %{}", end));
+        self.start(&otherwise);
+        if let Some(false_branch) = &if_stmt.false_branch {
+            self.block(false_branch)?;
+        }
+        self.emit(format!("br label %{}", end));
+        self.start(&end);
+        Ok(())
+    }
+
+    fn while_stmt(&mut self, while_stmt: &WhileStatement) -> Result<(), CodegenError> {
+        let check = self.label("while");
+        let body = self.label("do");
+        let end = self.label("endwhile");
+        self.emit(format!("br label %{}", check));
+
+        self.start(&check);
+        let condition = self.exp(&while_stmt.condition)?;
+        self.emit(format!(
+            "br i1 {}, label %{}, label %{}",
+            condition.value, body, end
+        ));
+        self.start(&body);
+        self.block(&while_stmt.block)?;
+        self.emit(format!("br label %{}", check));
+        self.start(&end);
+        Ok(())
+    }
+
+    fn for_stmt(&mut self, for_stmt: &ForStatement) -> Result<(), CodegenError> {
+        let list = self.exp(&for_stmt.iterable)?;
+        let item_type = list.ty.element_type().ok_or(CodegenError::Unsupported(
+            "looping over something that isn't a list",
+        ))?;
+        let len = self.reg();
+        self.emit(format!(
+            "{} = call i64 @pipes_list_len(ptr {})",
+            len, list.value
+        ));
+        let index = self.alloca("i64");
+        self.emit(format!("store i64 0, ptr {}", index));
+
+        let check = self.label("for");
+        let body = self.label("do");
+        let end = self.label("endfor");
+        self.emit(format!("br label %{}", check));
+
+        self.start(&check);
+        let i = self.reg();
+        self.emit(format!("{} = load i64, ptr {}", i, index));
+        let more = self.reg();
+        self.emit(format!("{} = icmp slt i64 {}, {}", more, i, len));
+        self.emit(format!("br i1 {}, label %{}, label %{}", more, body, end));
+
+        self.start(&body);
+        let slot = self.reg();
+        self.emit(format!(
+            "{} = call i64 @pipes_list_get(ptr {}, i64 {})",
+            slot, list.value, i
+        ));
+        let item = self.unslot(&item_type, &slot)?;
+        self.scopes.push();
+        self.local(&for_stmt.iter.name, &item)?;
+        let result = self.stmts(&for_stmt.block);
+        self.scopes.pop();
+        result?;
+        let next = self.reg();
+        self.emit(format!("{} = add i64 {}, 1", next, i));
+        self.emit(format!("store i64 {}, ptr {}", next, index));
+        self.emit(format!("br label %{}", check));
+        self.start(&end);
+        Ok(())
+    }
+
+    // ----------- expressions ---------- \\
+    fn exp(&mut self, exp: &Exp) -> Result<Operand, CodegenError> {
+        match exp {
+            Exp::Var(var) => match self.lookup(&var.name)? {
+                Binding::Var(ty, ptr) => {
+                    let value = self.reg();
+                    let line = format!("{} = load {}, ptr {}", value, value_type(&ty)?, ptr);
+                    self.emit(line);
+                    Ok(Operand::new(ty, &value))
+                }
+                Binding::Function(symbol, params, output) => Ok(Operand::new(
+                    Type::HigherOrderFunction(params, Box::new(output)),
+                    &symbol,
+                )),
+                Binding::Builtin(_) => Err(CodegenError::Unsupported("builtins as values")),
+            },
+            Exp::Int(i) => Ok(Operand::new(Type::Int, &i.to_string())),
+            Exp::BigInt(_) => Err(CodegenError::Unsupported("bigints")),
+            // hex is the only exact way to write most doubles in LLVM
+            Exp::Float(x) => Ok(Operand::new(
+                Type::Float,
+                &format!("0x{:016X}", x.to_bits()),
+            )),
+            Exp::Str(s) => {
+                let constant = self.string(s);
+                Ok(Operand::new(Type::Str, &constant))
+            }
+            Exp::Bool(b) => Ok(Operand::new(Type::Bool, &b.to_string())),
+            Exp::Void => Ok(Operand::void()),
+
+            Exp::Call(callee, args) => self.call(callee, None, args),
+            Exp::Unary(op, exp) => {
+                let value = self.exp(exp)?;
+                self.unary(*op, value)
+            }
+            Exp::Op(Operation::And, left, right) => self.logic(Operation::And, left, right),
+            Exp::Op(Operation::Or, left, right) => self.logic(Operation::Or, left, right),
+            Exp::Op(op, left, right) => {
+                let left = self.exp(left)?;
+                let right = self.exp(right)?;
+                self.binary(*op, left, right)
+            }
+            Exp::Pipe(left, right) => {
+                let left = self.exp(left)?;
+                match &**right {
+                    Exp::Call(callee, args) => self.call(callee, Some(left), args),
+                    callee => self.call(callee, Some(left), &[]),
+                }
+            }
+
+            Exp::Struct(name, fields) => self.struct_literal(name, fields),
+            Exp::Field(exp, field) => {
+                let value = self.exp(exp)?;
+                let name = match &value.ty {
+                    Type::Struct(name) => name.clone(),
+                    _ => return Err(CodegenError::Unsupported("fields of non structs")),
+                };
+                let (index, field_type) = self.field(&name, field)?;
+                self.load_field(&value, index, &field_type)
+            }
+            Exp::List(list_type, items) => self.list(list_type.as_ref(), items),
+            Exp::HOF(params, block) => {
+                let symbol = self.symbol("lambda");
+                let output = self.lower_function(&symbol, params, block, Returns::Infer(None))?;
+                let params = params
+                    .iter()
+                    .map(|param| param.var_type.clone().unwrap_or(Type::Void))
+                    .collect();
+                Ok(Operand::new(
+                    Type::HigherOrderFunction(params, Box::new(output)),
+                    &symbol,
+                ))
+            }
+        }
+    }
+
+    // struct literals without a name take it from where they're going
+    fn exp_as(&mut self, exp: &Exp, expected: &Type) -> Result<Operand, CodegenError> {
+        match (exp, expected) {
+            (Exp::Struct(name, fields), Type::Struct(expected)) if name.is_empty() => {
+                self.struct_literal(expected, fields)
+            }
+            _ => self.exp(exp),
+        }
+    }
+
+    // named functions are called directly, anything else through a pointer.
+    // a pipe's left side comes in as first
+    fn call(
+        &mut self,
+        callee: &Exp,
+        first: Option<Operand>,
+        args: &[Exp],
+    ) -> Result<Operand, CodegenError> {
+        let binding = match callee {
+            Exp::Var(var) => Some(self.lookup(&var.name)?),
+            _ => None,
+        };
+        let (target, params, output) = match binding {
+            Some(Binding::Function(symbol, params, output)) => (symbol, params, output),
+            Some(Binding::Builtin(builtin)) => {
+                let args = self.args(first, args, &[])?;
+                return self.builtin(builtin, args);
+            }
+            _ => match self.exp(callee)? {
+                Operand {
+                    ty: Type::HigherOrderFunction(params, output),
+                    value,
+                } => (value, params, *output),
+                _ => return Err(CodegenError::Unsupported("calling a non function")),
+            },
+        };
+
+        let args = self.args(first, args, &params)?;
+        let mut arg_list = vec![];
+        for (param, arg) in params.iter().zip(&args) {
+            arg_list.push(format!("{} {}", value_type(param)?, arg.value));
+        }
+        let call = format!(
+            "call {} {}({})",
+            return_type(&output)?,
+            target,
+            arg_list.join(", ")
+        );
+        if output == Type::Void {
+            self.emit(call);
+            return Ok(Operand::void());
+        }
+        let value = self.reg();
+        self.emit(format!("{} = {}", value, call));
+        Ok(Operand::new(output, &value))
+    }
+
+    fn args(
+        &mut self,
+        first: Option<Operand>,
+        args: &[Exp],
+        params: &[Type],
+    ) -> Result<Vec<Operand>, CodegenError> {
+        let mut values: Vec<Operand> = first.into_iter().collect();
+        for arg in args {
+            let value = match params.get(values.len()) {
+                Some(param) => self.exp_as(arg, param)?,
+                None => self.exp(arg)?,
+            };
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    fn builtin(&mut self, builtin: Builtin, args: Vec<Operand>) -> Result<Operand, CodegenError> {
+        let arg = match args.as_slice() {
+            [arg] => arg,
+            _ => {
+                return Err(CodegenError::Unsupported(
+                    "builtins without exactly one argument",
+                ))
+            }
+        };
+        match builtin {
+            Builtin::Print => {
+                let show = self.show_fn(&arg.ty)?;
+                let slot = self.slot_of(arg)?;
+                self.emit(format!("call void {}(i64 {})", show, slot));
+                self.emit(String::from("call void @pipes_newline()"));
+                Ok(Operand::void())
+            }
+            Builtin::Len => {
+                let len = match arg.ty {
+                    Type::Str => "@pipes_str_len",
+                    _ => "@pipes_list_len",
+                };
+                let value = self.reg();
+                self.emit(format!("{} = call i64 {}(ptr {})", value, len, arg.value));
+                Ok(Operand::new(Type::Int, &value))
+            }
+        }
+    }
+
+    fn unary(&mut self, op: UnaryOp, value: Operand) -> Result<Operand, CodegenError> {
+        match (op, &value.ty) {
+            (UnaryOp::Neg, Type::Int) => self.checked("ssub", "0", &value.value),
+            (UnaryOp::Neg, Type::Float) => {
+                let result = self.reg();
+                self.emit(format!("{} = fneg double {}", result, value.value));
+                Ok(Operand::new(Type::Float, &result))
+            }
+            (UnaryOp::Not, Type::Bool) => self.not(&value.value),
+            (_, Type::BigInt) => Err(CodegenError::Unsupported("bigints")),
+            _ => Err(CodegenError::Unsupported("this unary operator")),
+        }
+    }
+
+    fn not(&mut self, value: &str) -> Result<Operand, CodegenError> {
+        let result = self.reg();
+        self.emit(format!("{} = xor i1 {}, true", result, value));
+        Ok(Operand::new(Type::Bool, &result))
+    }
+
+    // && and || only run the right side when they have to
+    fn logic(&mut self, op: Operation, left: &Exp, right: &Exp) -> Result<Operand, CodegenError> {
+        let left = self.exp(left)?;
+        let left_end = self.builder.label.clone();
+        let rhs = self.label("rhs");
+        let end = self.label("endlogic");
+        let (on_true, on_false, short) = match op {
+            Operation::And => (&rhs, &end, "false"),
+            _ => (&end, &rhs, "true"),
+        };
+        self.emit(format!(
+            "br i1 {}, label %{}, label %{}",
+            left.value, on_true, on_false
+        ));
+
+        self.start(&rhs);
+        let right = self.exp(right)?;
+        let right_end = self.builder.label.clone();
+        self.emit(format!("br label %{}", end));
+        self.start(&end);
+        let result = self.reg();
+        self.emit(format!(
+            "{} = phi i1 [ {}, %{} ], [ {}, %{} ]",
+            result, short, left_end, right.value, right_end
+        ));
+        Ok(Operand::new(Type::Bool, &result))
+    }
+
+    fn binary(
+        &mut self,
+        op: Operation,
+        left: Operand,
+        right: Operand,
+    ) -> Result<Operand, CodegenError> {
+        use Operation::*;
+
+        if op == Equal {
+            return self.equal(&left, &right);
+        }
+        if op == NotEqual {
+            let equal = self.equal(&left, &right)?;
+            return self.not(&equal.value);
+        }
+
+        let result = self.reg();
+        if left.ty == Type::Int && right.ty == Type::Int {
+            let (a, b) = (&left.value, &right.value);
+            let line = match op {
+                Add => return self.checked("sadd", a, b),
+                Subtract => return self.checked("ssub", a, b),
+                Multiply => return self.checked("smul", a, b),
+                Divide => format!("{} = call i64 @pipes_div(i64 {}, i64 {})", result, a, b),
+                Modulo => format!("{} = call i64 @pipes_mod(i64 {}, i64 {})", result, a, b),
+                _ => format!("{} = icmp {} i64 {}, {}", result, int_comparison(op), a, b),
+            };
+            self.emit(line);
+            let ty = if op.result_type(&Type::Int, &Type::Int) == Some(Type::Bool) {
+                Type::Bool
+            } else {
+                Type::Int
+            };
+            return Ok(Operand::new(ty, &result));
+        }
+
+        // mixed arithmetic promotes the int to a float
+        let a = self.float(&left)?;
+        let b = self.float(&right)?;
+        let (line, ty) = match op {
+            Add => (
+                format!("{} = fadd double {}, {}", result, a, b),
+                Type::Float,
+            ),
+            Subtract => (
+                format!("{} = fsub double {}, {}", result, a, b),
+                Type::Float,
+            ),
+            Multiply => (
+                format!("{} = fmul double {}, {}", result, a, b),
+                Type::Float,
+            ),
+            Divide => (
+                format!("{} = fdiv double {}, {}", result, a, b),
+                Type::Float,
+            ),
+            Modulo => (
+                format!("{} = frem double {}, {}", result, a, b),
+                Type::Float,
+            ),
+            _ => (
+                format!(
+                    "{} = fcmp {} double {}, {}",
+                    result,
+                    float_comparison(op),
+                    a,
+                    b
+                ),
+                Type::Bool,
+            ),
+        };
+        self.emit(line);
+        Ok(Operand::new(ty, &result))
+    }
+
+    // a + b etc, stopping the program if the result doesn't fit in an int
+    fn checked(&mut self, intrinsic: &str, a: &str, b: &str) -> Result<Operand, CodegenError> {
+        let pair = self.reg();
+        self.emit(format!(
+            "{} = call {{ i64, i1 }} @llvm.{}.with.overflow.i64(i64 {}, i64 {})",
+            pair, intrinsic, a, b
+        ));
+        let result = self.reg();
+        self.emit(format!(
+            "{} = extractvalue {{ i64, i1 }} {}, 0",
+            result, pair
+        ));
+        let overflowed = self.reg();
+        self.emit(format!(
+            "{} = extractvalue {{ i64, i1 }} {}, 1",
+            overflowed, pair
+        ));
+        let overflow = self.label("overflow");
+        let ok = self.label("ok");
+        self.emit(format!(
+            "br i1 {}, label %{}, label %{}",
+            overflowed, overflow, ok
+        ));
+        self.start(&overflow);
+        self.emit(String::from("call void @pipes_overflow()"));
+        self.emit(String::from("unreachable"));
+        self.start(&ok);
+        Ok(Operand::new(Type::Int, &result))
+    }
+
+    fn float(&mut self, value: &Operand) -> Result<String, CodegenError> {
+        match value.ty {
+            Type::Float => Ok(value.value.clone()),
+            Type::Int => {
+                let result = self.reg();
+                self.emit(format!("{} = sitofp i64 {} to double", result, value.value));
+                Ok(result)
+            }
+            _ => Err(CodegenError::Unsupported("bigints")),
+        }
+    }
+
+    // ints and floats compare by value, everything else has to match exactly
+    fn equal(&mut self, left: &Operand, right: &Operand) -> Result<Operand, CodegenError> {
+        let result = self.reg();
+        let line = match (&left.ty, &right.ty) {
+            (Type::Int, Type::Int) | (Type::Bool, Type::Bool) => format!(
+                "{} = icmp eq {} {}, {}",
+                result,
+                value_type(&left.ty)?,
+                left.value,
+                right.value
+            ),
+            (Type::Float, _) | (_, Type::Float) => {
+                let a = self.float(left)?;
+                let b = self.float(right)?;
+                format!("{} = fcmp oeq double {}, {}", result, a, b)
+            }
+            (Type::HigherOrderFunction(..), _) => {
+                format!("{} = icmp eq ptr {}, {}", result, left.value, right.value)
+            }
+            (Type::Void, _) => return Ok(Operand::new(Type::Bool, "true")),
+            (Type::Str, _) => {
+                let equal = self.reg();
+                self.emit(format!(
+                    "{} = call i32 @pipes_str_eq(ptr {}, ptr {})",
+                    equal, left.value, right.value
+                ));
+                format!("{} = icmp ne i32 {}, 0", result, equal)
+            }
+            _ => {
+                let eq = self.eq_fn(&left.ty)?;
+                let a = self.slot_of(left)?;
+                let b = self.slot_of(right)?;
+                let equal = self.reg();
+                self.emit(format!("{} = call i32 {}(i64 {}, i64 {})", equal, eq, a, b));
+                format!("{} = icmp ne i32 {}, 0", result, equal)
+            }
+        };
+        self.emit(line);
+        Ok(Operand::new(Type::Bool, &result))
+    }
+
+    // fields are evaluated in the order they're written but stored in the
+    // order they were declared
+    fn struct_literal(&mut self, name: &str, fields: &[Var]) -> Result<Operand, CodegenError> {
+        if name.is_empty() {
+            return Err(CodegenError::UnknownType(String::from("a struct literal")));
+        }
+        let declared = self
+            .structs
+            .fields(name)
+            .ok_or_else(|| CodegenError::UnknownType(String::from(name)))?
+            .to_vec();
+        let mut values = vec![];
+        for field in fields {
+            let field_type = declared
+                .iter()
+                .find(|(name, _)| *name == field.name)
+                .map(|(_, field_type)| field_type.clone())
+                .ok_or_else(|| CodegenError::UnknownType(field.name.clone()))?;
+            let value = match &field.value {
+                Some(exp) => self.exp_as(exp, &field_type)?,
+                None => Operand::void(),
+            };
+            values.push((field.name.clone(), value));
+        }
+
+        let size = self.reg();
+        self.emit(format!(
+            "{} = getelementptr {}, ptr null, i32 1",
+            size,
+            struct_type(name)
+        ));
+        let bytes = self.reg();
+        self.emit(format!("{} = ptrtoint ptr {} to i64", bytes, size));
+        let ptr = self.reg();
+        self.emit(format!("{} = call ptr @pipes_alloc(i64 {})", ptr, bytes));
+        for (i, (field, field_type)) in declared.iter().enumerate() {
+            let value = values
+                .iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, value)| value.value.clone())
+                .ok_or_else(|| CodegenError::UnknownType(field.clone()))?;
+            let field_ptr = self.field_ptr(name, &ptr, i);
+            let line = format!(
+                "store {} {}, ptr {}",
+                value_type(field_type)?,
+                value,
+                field_ptr
+            );
+            self.emit(line);
+        }
+        Ok(Operand::new(Type::Struct(String::from(name)), &ptr))
+    }
+
+    // where a field is in its struct and its type
+    fn field(&self, name: &str, field: &str) -> Result<(usize, Type), CodegenError> {
+        let fields = self.structs.fields(name).unwrap_or(&[]);
+        fields
+            .iter()
+            .position(|(field_name, _)| field_name == field)
+            .map(|i| (i, fields[i].1.clone()))
+            .ok_or_else(|| CodegenError::UnknownType(format!("{}.{}", name, field)))
+    }
+
+    fn field_ptr(&mut self, name: &str, ptr: &str, index: usize) -> String {
+        let field_ptr = self.reg();
+        self.emit(format!(
+            "{} = getelementptr inbounds {}, ptr {}, i32 0, i32 {}",
+            field_ptr,
+            struct_type(name),
+            ptr,
+            index
+        ));
+        field_ptr
+    }
+
+    fn load_field(
+        &mut self,
+        value: &Operand,
+        index: usize,
+        field_type: &Type,
+    ) -> Result<Operand, CodegenError> {
+        let name = match &value.ty {
+            Type::Struct(name) => name.clone(),
+            _ => return Err(CodegenError::Unsupported("fields of non structs")),
+        };
+        let field_ptr = self.field_ptr(&name, &value.value, index);
+        let result = self.reg();
+        let line = format!(
+            "{} = load {}, ptr {}",
+            result,
+            value_type(field_type)?,
+            field_ptr
+        );
+        self.emit(line);
+        Ok(Operand::new(field_type.clone(), &result))
+    }
+
+    fn list(&mut self, list_type: Option<&Type>, items: &[Exp]) -> Result<Operand, CodegenError> {
+        let mut values = vec![];
+        for item in items {
+            values.push(self.exp(item)?);
+        }
+        // an empty list nobody gave a type to never has its items looked at
+        let ty = match (list_type, values.first()) {
+            (Some(list_type), _) => list_type.clone(),
+            (None, Some(first)) => Type::List(Box::new(first.ty.clone())),
+            (None, None) => Type::List(Box::new(Type::Void)),
+        };
+
+        let list = self.reg();
+        self.emit(format!(
+            "{} = call ptr @pipes_list_new(i64 {})",
+            list,
+            values.len()
+        ));
+        for (i, value) in values.iter().enumerate() {
+            let slot = self.slot_of(value)?;
+            self.emit(format!(
+                "call void @pipes_list_set(ptr {}, i64 {}, i64 {})",
+                list, i, slot
+            ));
+        }
+        Ok(Operand::new(ty, &list))
+    }
+
+    // ----------- slots ---------- \\
+    // lists and the helpers below carry every value as an i64
+    fn slot_of(&mut self, value: &Operand) -> Result<String, CodegenError> {
+        let cast = match value.ty {
+            Type::Int => return Ok(value.value.clone()),
+            Type::Void => return Ok(String::from("0")),
+            Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+            Type::Float => "bitcast double",
+            Type::Bool => "zext i1",
+            _ => "ptrtoint ptr",
+        };
+        let slot = self.reg();
+        self.emit(format!("{} = {} {} to i64", slot, cast, value.value));
+        Ok(slot)
+    }
+
+    fn unslot(&mut self, ty: &Type, slot: &str) -> Result<Operand, CodegenError> {
+        let cast = match ty {
+            Type::Int => return Ok(Operand::new(Type::Int, slot)),
+            Type::Void => return Ok(Operand::void()),
+            Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+            Type::Float => "bitcast i64 {} to double",
+            Type::Bool => "trunc i64 {} to i1",
+            _ => "inttoptr i64 {} to ptr",
+        };
+        let value = self.reg();
+        self.emit(format!("{} = {}", value, cast.replace("{}", slot)));
+        Ok(Operand::new(ty.clone(), &value))
+    }
+
+    // ----------- helpers ---------- \\
+    // a function printing a value of the given type from its slot, written
+    // the first time it's needed
+    fn show_fn(&mut self, ty: &Type) -> Result<String, CodegenError> {
+        let symbol = llvm_name('@', &format!("show.{}", ty));
+        if !self.helpers.insert(symbol.clone()) {
+            return Ok(symbol);
+        }
+        let outer = std::mem::replace(&mut self.builder, Builder::new(Returns::Main));
+        match ty {
+            Type::Void => self.write("()"),
+            Type::Int => self.emit(String::from("call void @pipes_show_int(i64 %slot)")),
+            Type::Bool => self.emit(String::from("call void @pipes_show_bool(i64 %slot)")),
+            Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+            Type::Float => {
+                let x = self.unslot(ty, "%slot")?;
+                self.emit(format!("call void @pipes_show_float(double {})", x.value));
+            }
+            Type::Str => {
+                let s = self.unslot(ty, "%slot")?;
+                self.emit(format!("call void @pipes_write(ptr {})", s.value));
+            }
+            Type::List(item_type) => {
+                let list = self.unslot(ty, "%slot")?;
+                let show = self.show_fn(item_type)?;
+                self.emit(format!(
+                    "call void @pipes_show_list(ptr {}, ptr {})",
+                    list.value, show
+                ));
+            }
+            Type::Struct(name) => {
+                let value = self.unslot(ty, "%slot")?;
+                let fields = self.structs.fields(name).unwrap_or(&[]).to_vec();
+                self.write(&format!("{} {{", name));
+                for (i, (field, field_type)) in fields.iter().enumerate() {
+                    let separator = if i > 0 { "," } else { "" };
+                    self.write(&format!("{} {}: ", separator, field));
+                    let field_value = self.load_field(&value, i, field_type)?;
+                    let show = self.show_fn(field_type)?;
+                    let slot = self.slot_of(&field_value)?;
+                    self.emit(format!("call void {}(i64 {})", show, slot));
+                }
+                self.write(" }");
+            }
+            Type::HigherOrderFunction(..) => {
+                self.helpers.insert(String::from(NAME_FN));
+                let function = self.unslot(ty, "%slot")?;
+                let name = self.reg();
+                self.emit(format!(
+                    "{} = call ptr {}(ptr {})",
+                    name, NAME_FN, function.value
+                ));
+                self.write("<func ");
+                self.emit(format!("call void @pipes_write(ptr {})", name));
+                self.write(">");
+            }
+        }
+        let builder = std::mem::replace(&mut self.builder, outer);
+        let header = format!("define internal void {}(i64 %slot)", symbol);
+        self.functions.push(builder.finish(&header, "ret void"));
+        Ok(symbol)
+    }
+
+    // a function comparing two values of the given type from their slots,
+    // giving back 1 or 0
+    fn eq_fn(&mut self, ty: &Type) -> Result<String, CodegenError> {
+        let symbol = llvm_name('@', &format!("eq.{}", ty));
+        if !self.helpers.insert(symbol.clone()) {
+            return Ok(symbol);
+        }
+        let outer = std::mem::replace(&mut self.builder, Builder::new(Returns::Main));
+        let a = self.unslot(ty, "%a")?;
+        let b = self.unslot(ty, "%b")?;
+        let result = match ty {
+            Type::List(item_type) => {
+                let eq = self.eq_fn(item_type)?;
+                let result = self.reg();
+                self.emit(format!(
+                    "{} = call i32 @pipes_list_eq(ptr {}, ptr {}, ptr {})",
+                    result, a.value, b.value, eq
+                ));
+                result
+            }
+            _ => {
+                let equal = match ty {
+                    Type::Struct(name) => {
+                        let fields = self.structs.fields(name).unwrap_or(&[]).to_vec();
+                        let mut all = String::from("true");
+                        for (i, (_, field_type)) in fields.iter().enumerate() {
+                            let a_field = self.load_field(&a, i, field_type)?;
+                            let b_field = self.load_field(&b, i, field_type)?;
+                            let equal = self.equal(&a_field, &b_field)?;
+                            let both = self.reg();
+                            self.emit(format!("{} = and i1 {}, {}", both, all, equal.value));
+                            all = both;
+                        }
+                        all
+                    }
+                    _ => self.equal(&a, &b)?.value,
+                };
+                let result = self.reg();
+                self.emit(format!("{} = zext i1 {} to i32", result, equal));
+                result
+            }
+        };
+        let builder = std::mem::replace(&mut self.builder, outer);
+        let header = format!("define internal i32 {}(i64 %a, i64 %b)", symbol);
+        self.functions
+            .push(builder.finish(&header, &format!("ret i32 {}", result)));
+        Ok(symbol)
+    }
+
+    // a function giving back the name of the function it's given, written
+    // once every named function has its symbol. anything else is a lambda
+    fn name_fn(&mut self) {
+        let mut name = self.string("<lambda>");
+        let mut body = String::new();
+        for (i, (symbol, source)) in self.names.clone().iter().enumerate() {
+            let constant = self.string(source);
+            body += &format!("  %is.{} = icmp eq ptr %f, {}\n", i, symbol);
+            body += &format!(
+                "  %name.{} = select i1 %is.{}, ptr {}, ptr {}\n",
+                i, i, constant, name
+            );
+            name = format!("%name.{}", i);
+        }
+        self.functions.push(format!(
+            "define internal ptr {}(ptr %f) {{\n{}  ret ptr {}\n}}\n",
+            NAME_FN, body, name
+        ));
+    }
+
+    fn write(&mut self, s: &str) {
+        let constant = self.string(s);
+        self.emit(format!("call void @pipes_write(ptr {})", constant));
+    }
+
+    // ----------- names ---------- \\
+    // what a name refers to, refusing locals of an enclosing function
+    fn lookup(&self, name: &str) -> Result<Binding, CodegenError> {
+        match self.scopes.lookup(name) {
+            Some((Binding::Var(..), Reach::Outer)) => {
+                Err(CodegenError::Captures(String::from(name)))
+            }
+            Some((binding, _)) => Ok(binding.clone()),
+            None => Err(CodegenError::UndefinedVariable(String::from(name))),
+        }
+    }
+
+    // a new variable in the current scope, stored in an alloca of its own
+    fn local(&mut self, name: &str, value: &Operand) -> Result<(), CodegenError> {
+        let llvm_type = value_type(&value.ty)?;
+        let n = self.next();
+        let ptr = llvm_name('%', &format!("{}.{}", name, n));
+        self.builder.allocas += &format!("  {} = alloca {}\n", ptr, llvm_type);
+        self.emit(format!("store {} {}, ptr {}", llvm_type, value.value, ptr));
+        self.scopes
+            .define(name, Binding::Var(value.ty.clone(), ptr));
+        Ok(())
+    }
+
+    fn alloca(&mut self, llvm_type: &str) -> String {
+        let ptr = self.reg();
+        self.builder.allocas += &format!("  {} = alloca {}\n", ptr, llvm_type);
+        ptr
+    }
+
+    // a module level symbol nothing else has used
+    fn symbol(&mut self, base: &str) -> String {
+        let mut symbol = llvm_name('@', base);
+        let mut n = 1;
+        while !self.symbols.insert(symbol.clone()) {
+            n += 1;
+            symbol = llvm_name('@', &format!("{}.{}", base, n));
+        }
+        symbol
+    }
+
+    // string constants are shared by everything that uses the same text
+    fn string(&mut self, s: &str) -> String {
+        let index = match self.strings.iter().position(|existing| existing == s) {
+            Some(index) => index,
+            None => {
+                self.strings.push(String::from(s));
+                self.strings.len() - 1
+            }
+        };
+        format!("@.str.{}", index)
+    }
+
+    // ----------- instructions ---------- \\
+    fn next(&mut self) -> usize {
+        self.builder.next += 1;
+        self.builder.next
+    }
+
+    fn reg(&mut self) -> String {
+        format!("%t{}", self.next())
+    }
+
+    fn label(&mut self, name: &str) -> String {
+        format!("{}.{}", name, self.next())
+    }
+
+    fn emit(&mut self, line: String) {
+        self.builder.body += "  ";
+        self.builder.body += &line;
+        self.builder.body += "\n";
+    }
+
+    fn start(&mut self, label: &str) {
+        self.builder.body += label;
+        self.builder.body += ":\n";
+        self.builder.label = String::from(label);
+    }
+
+    // ends the block, anything after it is unreachable until the next label
+    fn terminate(&mut self, line: String) {
+        self.emit(line);
+        let dead = self.label("dead");
+        self.start(&dead);
+    }
+}
+
+// the LLVM type of a value that can be stored somewhere
+fn value_type(ty: &Type) -> Result<&'static str, CodegenError> {
+    match ty {
+        Type::Void => Err(CodegenError::Unsupported("storing void values")),
+        Type::BigInt => Err(CodegenError::Unsupported("bigints")),
+        Type::Int => Ok("i64"),
+        Type::Float => Ok("double"),
+        Type::Bool => Ok("i1"),
+        Type::Str | Type::Struct(_) | Type::List(_) | Type::HigherOrderFunction(..) => Ok("ptr"),
+    }
+}
+
+fn return_type(ty: &Type) -> Result<&'static str, CodegenError> {
+    match ty {
+        Type::Void => Ok("void"),
+        ty => value_type(ty),
+    }
+}
+
+fn struct_type(name: &str) -> String {
+    llvm_name('%', &format!("struct.{}", name))
+}
+
+fn int_comparison(op: Operation) -> &'static str {
+    match op {
+        Operation::GreaterThan => "sgt",
+        Operation::LessThan => "slt",
+        Operation::GreaterEqual => "sge",
+        _ => "sle",
+    }
+}
+
+// ordered, so comparing with NaN is false like it is in the interpreter
+fn float_comparison(op: Operation) -> &'static str {
+    match op {
+        Operation::GreaterThan => "ogt",
+        Operation::LessThan => "olt",
+        Operation::GreaterEqual => "oge",
+        _ => "ole",
+    }
+}
+
+// a symbol or register, quoted when it isn't a plain identifier
+fn llvm_name(sigil: char, name: &str) -> String {
+    let plain = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if plain {
+        format!("{}{}", sigil, name)
+    } else {
+        format!("{}\"{}\"", sigil, name)
+    }
+}
+
+// the inside of a c"..." constant
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for byte in s.bytes() {
+        if byte.is_ascii_graphic() && byte != b'"' && byte != b'\\' || byte == b' ' {
+            escaped.push(byte as char);
+        } else {
+            escaped += &format!("\\{:02X}", byte);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::codegen::harness::{self, Run};
+    use std::process::Command;
+
+    fn compile_source(source: &str) -> Result<String, CodegenError> {
+        let (program, structs) = harness::checked(source);
+        compile(&program, &structs)
+    }
+
+    // builds the program with llc and cc and runs it. None when llc or cc
+    // aren't installed
+    pub fn run_native(source: &str) -> Option<Run> {
+        let version = Command::new("llc").arg("--version").output().ok()?;
+        let version = String::from_utf8_lossy(&version.stdout).into_owned();
+        if !harness::installed("cc") {
+            return None;
+        }
+        let ir = compile_source(source).unwrap();
+        Some(harness::in_scratch_dir("llvm", |dir| {
+            std::fs::write(dir.join("out.ll"), ir).unwrap();
+            std::fs::write(dir.join("runtime.c"), RUNTIME).unwrap();
+
+            let mut llc = Command::new("llc");
+            // opaque pointers are the default from LLVM 15
+            if version.contains("LLVM version 14") {
+                llc.arg("-opaque-pointers");
+            }
+            let llc = llc
+                .args([
+                    "-filetype=obj",
+                    "-relocation-model=pic",
+                    "out.ll",
+                    "-o",
+                    "out.o",
+                ])
+                .current_dir(dir)
+                .output()
+                .unwrap();
+            assert!(
+                llc.status.success(),
+                "{}",
+                String::from_utf8_lossy(&llc.stderr)
+            );
+            let cc = Command::new("cc")
+                .args(["out.o", "runtime.c", "-lm", "-o", "out"])
+                .current_dir(dir)
+                .output()
+                .unwrap();
+            assert!(
+                cc.status.success(),
+                "{}",
+                String::from_utf8_lossy(&cc.stderr)
+            );
+            harness::finished(Command::new(dir.join("out")).output().unwrap())
+        }))
+    }
+
+    // ----------- lowering tests ---------- \\
+    #[test]
+    fn compile_functions() {
+        let ir =
+            compile_source("func add(a: int, b: int) -> int { return a + b; } add(1, 2)").unwrap();
+        assert!(ir.contains("define internal i64 @fn.add(i64 %arg.0, i64 %arg.1)"));
+        assert!(ir.contains("call { i64, i1 } @llvm.sadd.with.overflow.i64"));
+        assert!(ir.contains("%t1 = call i64 @fn.add(i64 1, i64 2)"));
+        assert!(ir.contains("define i32 @main()"));
+    }
+
+    #[test]
+    fn compile_globals_and_structs() {
+        let ir = compile_source(
+            "struct Point { x: int, y: float, name: str, }
+            let origin: Point = {x: 0, y: 0.5, name: \"o\",};",
+        )
+        .unwrap();
+        assert!(ir.contains("%struct.Point = type { i64, double, ptr }"));
+        assert!(ir.contains("@global.origin = internal global ptr zeroinitializer"));
+        assert!(ir.contains("c\"o\\00\""));
+        assert!(ir.contains("store double 0x3FE0000000000000"));
+    }
+
+    #[test]
+    fn compile_names() {
+        assert_eq!(llvm_name('%', "x.1"), "%x.1");
+        assert_eq!(llvm_name('@', "show.[int]"), "@\"show.[int]\"");
+        assert_eq!(escape("say \"hi\"\n"), "say \\22hi\\22\\0A");
+        // two functions with the same name get their own symbols
+        let ir = compile_source(
+            "func f() -> int { func g() -> int { return 1; } return g(); }
+            func h() -> int { func g() -> int { return 2; } return g(); }",
+        )
+        .unwrap();
+        assert!(ir.contains("define internal i64 @fn.g()"));
+        assert!(ir.contains("define internal i64 @fn.g.2()"));
+    }
+
+    #[test]
+    fn compile_unsupported() {
+        assert_eq!(
+            compile_source("let big: bigint = 99999999999999999999n;"),
+            Err(CodegenError::Unsupported("bigints"))
+        );
+        assert_eq!(
+            compile_source(
+                "func counter(start: int) -> (int) -> int {
+                    return (step: int) { return start + step; };
+                }"
+            ),
+            Err(CodegenError::Captures(String::from("start")))
+        );
+        // nothing says what a is here
+        assert_eq!(
+            compile_source("print((a) { return a; });"),
+            Err(CodegenError::UnknownType(String::from("a")))
+        );
+    }
+}
@@ -0,0 +1,248 @@
+// the runtime every program compiled by codegen::llvm links against, and
+// codegen::c pastes into its output. values cross into lists and the
+// print/equality helpers as 64 bit slots: ints as they are, floats by their
+// bits, bools as 0 or 1, everything else as a pointer. nothing is ever freed
+#include <inttypes.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct {
+    int64_t len;
+    int64_t items[];
+} pipes_list;
+
+typedef void (*pipes_show_fn)(int64_t);
+typedef int32_t (*pipes_eq_fn)(int64_t, int64_t);
+
+// ----------- errors ----------
+
+void pipes_panic(const char *message) {
+    fflush(stdout);
+    fprintf(stderr, "error: %s\n", message);
+    exit(1);
+}
+
+void pipes_overflow(void) {
+    pipes_panic("integer overflow");
+}
+
+void *pipes_alloc(int64_t size) {
+    void *memory = malloc(size > 0 ? (size_t)size : 1);
+    if (memory == NULL) {
+        pipes_panic("out of memory");
+    }
+    return memory;
+}
+
+// ----------- slots ----------
+
+int64_t pipes_float_slot(double x) {
+    int64_t slot;
+    memcpy(&slot, &x, sizeof slot);
+    return slot;
+}
+
+double pipes_slot_float(int64_t slot) {
+    double x;
+    memcpy(&x, &slot, sizeof x);
+    return x;
+}
+
+// ----------- ints ----------
+
+int64_t pipes_add(int64_t a, int64_t b) {
+    if ((b > 0 && a > INT64_MAX - b) || (b < 0 && a < INT64_MIN - b)) {
+        pipes_overflow();
+    }
+    return a + b;
+}
+
+int64_t pipes_sub(int64_t a, int64_t b) {
+    if ((b < 0 && a > INT64_MAX + b) || (b > 0 && a < INT64_MIN + b)) {
+        pipes_overflow();
+    }
+    return a - b;
+}
+
+int64_t pipes_mul(int64_t a, int64_t b) {
+    if (a > 0 ? (b > 0 ? a > INT64_MAX / b : b < INT64_MIN / a)
+              : (b > 0 ? a < INT64_MIN / b : a != 0 && b < INT64_MAX / a)) {
+        pipes_overflow();
+    }
+    return a * b;
+}
+
+int64_t pipes_div(int64_t a, int64_t b) {
+    if (b == 0) {
+        pipes_panic("divide by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        pipes_overflow();
+    }
+    return a / b;
+}
+
+int64_t pipes_mod(int64_t a, int64_t b) {
+    if (b == 0) {
+        pipes_panic("divide by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        pipes_overflow();
+    }
+    return a % b;
+}
+
+// ----------- strings ----------
+
+// in chars, not bytes
+int64_t pipes_str_len(const char *s) {
+    int64_t len = 0;
+    for (; *s; s++) {
+        if ((*s & 0xC0) != 0x80) {
+            len++;
+        }
+    }
+    return len;
+}
+
+int32_t pipes_str_eq(const char *a, const char *b) {
+    return strcmp(a, b) == 0;
+}
+
+// ----------- lists ----------
+
+pipes_list *pipes_list_new(int64_t len) {
+    pipes_list *list = pipes_alloc(sizeof(pipes_list) + len * sizeof(int64_t));
+    list->len = len;
+    return list;
+}
+
+pipes_list *pipes_list_of(int64_t len, const int64_t *items) {
+    pipes_list *list = pipes_list_new(len);
+    if (len > 0) {
+        memcpy(list->items, items, len * sizeof(int64_t));
+    }
+    return list;
+}
+
+int64_t pipes_list_len(pipes_list *list) {
+    return list->len;
+}
+
+int64_t pipes_list_get(pipes_list *list, int64_t i) {
+    return list->items[i];
+}
+
+void pipes_list_set(pipes_list *list, int64_t i, int64_t item) {
+    list->items[i] = item;
+}
+
+int32_t pipes_list_eq(pipes_list *a, pipes_list *b, pipes_eq_fn eq) {
+    if (a->len != b->len) {
+        return 0;
+    }
+    for (int64_t i = 0; i < a->len; i++) {
+        if (!eq(a->items[i], b->items[i])) {
+            return 0;
+        }
+    }
+    return 1;
+}
+
+// ----------- printing ----------
+// everything prints the way the interpreter prints it
+
+void pipes_write(const char *s) {
+    fputs(s, stdout);
+}
+
+void pipes_newline(void) {
+    putchar('\n');
+}
+
+void pipes_show_int(int64_t i) {
+    printf("%" PRId64, i);
+}
+
+void pipes_show_bool(int64_t b) {
+    fputs(b ? "true" : "false", stdout);
+}
+
+// the shortest digits that read back as the same double, laid out like
+// Rust's {:?}: plain from 1e-4 up to 1e16, scientific outside of that
+void pipes_show_float(double x) {
+    if (isnan(x)) {
+        fputs("NaN", stdout);
+        return;
+    }
+    if (isinf(x)) {
+        fputs(x > 0 ? "inf" : "-inf", stdout);
+        return;
+    }
+    if (x == 0) {
+        fputs(signbit(x) ? "-0.0" : "0.0", stdout);
+        return;
+    }
+
+    char buf[32];
+    int precision = 1;
+    for (; precision < 17; precision++) {
+        snprintf(buf, sizeof buf, "%.*e", precision - 1, x);
+        if (strtod(buf, NULL) == x) {
+            break;
+        }
+    }
+    snprintf(buf, sizeof buf, "%.*e", precision - 1, x);
+
+    // buf is [-]d.ddde[+-]xx
+    char *e = strchr(buf, 'e');
+    int exp = atoi(e + 1);
+    *e = '\0';
+    int negative = buf[0] == '-';
+    char digits[20];
+    int n = 0;
+    for (char *c = buf + negative; *c; c++) {
+        if (*c != '.') {
+            digits[n++] = *c;
+        }
+    }
+    digits[n] = '\0';
+
+    if (negative) {
+        putchar('-');
+    }
+    if (exp < -4 || exp >= 16) {
+        putchar(digits[0]);
+        if (n > 1) {
+            printf(".%s", digits + 1);
+        }
+        printf("e%d", exp);
+    } else if (exp < 0) {
+        fputs("0.", stdout);
+        for (int i = -1; i > exp; i--) {
+            putchar('0');
+        }
+        fputs(digits, stdout);
+    } else {
+        for (int i = 0; i <= exp; i++) {
+            putchar(i < n ? digits[i] : '0');
+        }
+        putchar('.');
+        fputs(n > exp + 1 ? digits + exp + 1 : "0", stdout);
+    }
+}
+
+void pipes_show_list(pipes_list *list, pipes_show_fn show) {
+    putchar('[');
+    for (int64_t i = 0; i < list->len; i++) {
+        if (i > 0) {
+            fputs(", ", stdout);
+        }
+        show(list->items[i]);
+    }
+    putchar(']');
+}
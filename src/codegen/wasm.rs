@@ -0,0 +1,1533 @@
+// lowers a type checked program to a WebAssembly module, as text or as a
+// binary, which runs in the host HOST is from node or a browser:
+//
+//     node host.js out.wasm
+//
+// ints are i64, floats f64 and bools i32. everything else is an i32
+// pointer into linear memory, handed out by a bump allocator and never
+// freed: strings are their length in bytes and then the bytes, lists their
+// length and then a 64 bit slot per item, and structs 8 bytes per field.
+// function values are closures like codegen::c's: a table index, an
+// environment of pointers to the boxed locals a lambda captured, and a name.
+// printing and fmod come from the host
+mod module;
+
+use super::{
+    exp_lambda_names, lambda_names, signature, stmt_lambda_names, CodegenError, LowerBlock, Reach,
+    Returns, Scopes,
+};
+use crate::ast::*;
+use crate::interpreter::Builtin;
+use crate::types::{StructRegistry, Type};
+use module::{asm, Func, Import, Instr, Signature, ValType, Wasm};
+use std::collections::{HashMap, HashSet};
+
+pub const HOST: &str = include_str!("wasm/host.js");
+
+// the structs are the ones check gave back for the program
+pub fn compile(program: &Program, structs: &StructRegistry) -> Result<String, CodegenError> {
+    Ok(lower(program, structs)?.to_text())
+}
+
+pub fn compile_binary(
+    program: &Program,
+    structs: &StructRegistry,
+) -> Result<Vec<u8>, CodegenError> {
+    Ok(lower(program, structs)?.to_binary())
+}
+
+fn lower(program: &Program, structs: &StructRegistry) -> Result<Wasm, CodegenError> {
+    let mut module = Module::new(structs);
+    module.runtime();
+    module.program(program)?;
+    Ok(module.finish())
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+    Var(Type, Storage),
+    // a named function's symbol, parameter types and output type
+    Function(String, Vec<Type>, Type),
+    Builtin(Builtin),
+}
+
+// where a variable's value lives
+#[derive(Debug, Clone)]
+enum Storage {
+    Local(String),
+    Global(String),
+    // a local pointing at the box the value is in, for variables a lambda
+    // might capture
+    Boxed(String),
+    // the box at this index in the closure's environment
+    Captured(u32),
+}
+
+// a lowered expression: its type and the code leaving it on the stack,
+// which leaves nothing for void values
+#[derive(Debug, Clone)]
+struct Value {
+    ty: Type,
+    code: Vec<Instr>,
+}
+
+impl Value {
+    fn new(ty: Type, code: Vec<Instr>) -> Value {
+        Value { ty, code }
+    }
+
+    fn void() -> Value {
+        Value::new(Type::Void, vec![])
+    }
+}
+
+// the function being written
+struct Builder {
+    body: Vec<Instr>,
+    locals: Vec<(String, ValType)>,
+    returns: Returns,
+    // names used inside the function's lambdas. its locals with these names
+    // are boxed, since a closure might point at them
+    boxed: HashSet<String>,
+    // locals of enclosing functions this one uses, which its closure captures
+    captures: Vec<(String, Type)>,
+}
+
+impl Builder {
+    fn new(returns: Returns, boxed: HashSet<String>) -> Builder {
+        Builder {
+            body: vec![],
+            locals: vec![],
+            returns,
+            boxed,
+            captures: vec![],
+        }
+    }
+}
+
+struct Module<'a> {
+    structs: &'a StructRegistry,
+    scopes: Scopes<Binding>,
+    builder: Builder,
+    // every symbol given out ends in a number no other one has
+    next: usize,
+    wasm: Wasm,
+    // the addresses of the strings in the data segment
+    strings: HashMap<String, u32>,
+    // the print and equality helpers and static closures made so far, by
+    // what they're for
+    helpers: HashMap<String, String>,
+}
+
+impl<'a> Module<'a> {
+    fn new(structs: &'a StructRegistry) -> Module<'a> {
+        Module {
+            structs,
+            scopes: Scopes::new(),
+            builder: Builder::new(Returns::Main, HashSet::new()),
+            next: 0,
+            wasm: Wasm {
+                // nothing lives at 0, so it's never a valid pointer
+                data_start: 8,
+                heap: String::from("pipes.heap"),
+                exports: vec![String::from("main")],
+                ..Wasm::default()
+            },
+            strings: HashMap::new(),
+            helpers: HashMap::new(),
+        }
+    }
+
+    fn finish(mut self) -> Wasm {
+        self.wasm.funcs.push(Func {
+            name: String::from("main"),
+            params: vec![],
+            result: None,
+            locals: self.builder.locals,
+            body: self.builder.body,
+            comment: Some(String::from("the program's top level")),
+        });
+        self.wasm
+    }
+
+    // ----------- runtime ---------- \\
+    fn runtime(&mut self) {
+        use ValType::*;
+
+        let imports = vec![
+            ("write", vec![I32], None),
+            ("show_int", vec![I64], None),
+            ("show_float", vec![F64], None),
+            ("fmod", vec![F64, F64], Some(F64)),
+            ("panic", vec![I32], None),
+        ];
+        for (field, params, result) in imports {
+            self.wasm.imports.push(Import {
+                module: "host",
+                field,
+                name: format!("host.{}", field),
+                signature: Signature { params, result },
+            });
+        }
+
+        let out_of_memory = self.string("out of memory");
+        let overflow = self.string("integer overflow");
+        let divide_by_zero = self.string("divide by zero");
+        let (true_, false_) = (self.string("true"), self.string("false"));
+        let lambda = self.string("<lambda>");
+        let (func, close) = (self.string("<func "), self.string(">"));
+
+        self.runtime_func(
+            "pipes.alloc",
+            &[("size", I32)],
+            Some(I32),
+            &[("pointer", I32)],
+            &format!(
+                "global.get $pipes.heap local.set $pointer
+                global.get $pipes.heap local.get $size i32.add
+                i32.const 7 i32.add i32.const -8 i32.and global.set $pipes.heap
+                block $done loop $grow
+                    global.get $pipes.heap memory.size i32.const 16 i32.shl i32.le_u br_if $done
+                    i32.const 1 memory.grow i32.const -1 i32.eq
+                    if i32.const {} call $pipes.panic end
+                    br $grow
+                end end
+                local.get $pointer",
+                out_of_memory
+            ),
+        );
+        self.runtime_func(
+            "pipes.panic",
+            &[("message", I32)],
+            None,
+            &[],
+            "local.get $message call $host.panic unreachable",
+        );
+        self.runtime_func(
+            "pipes.overflow",
+            &[],
+            None,
+            &[],
+            &format!("i32.const {} call $pipes.panic", overflow),
+        );
+
+        // ints, which stop the program when they overflow
+        self.runtime_func(
+            "pipes.add",
+            &[("a", I64), ("b", I64)],
+            Some(I64),
+            &[("r", I64)],
+            "local.get $a local.get $b i64.add local.set $r
+            local.get $a local.get $r i64.xor local.get $b local.get $r i64.xor
+            i64.and i64.const 0 i64.lt_s
+            if call $pipes.overflow end
+            local.get $r",
+        );
+        self.runtime_func(
+            "pipes.sub",
+            &[("a", I64), ("b", I64)],
+            Some(I64),
+            &[("r", I64)],
+            "local.get $a local.get $b i64.sub local.set $r
+            local.get $a local.get $b i64.xor local.get $a local.get $r i64.xor
+            i64.and i64.const 0 i64.lt_s
+            if call $pipes.overflow end
+            local.get $r",
+        );
+        // the product overflowed when dividing it by a doesn't give back b
+        self.runtime_func(
+            "pipes.mul",
+            &[("a", I64), ("b", I64)],
+            Some(I64),
+            &[("r", I64)],
+            "local.get $a local.get $b i64.mul local.set $r
+            local.get $a i64.eqz i32.eqz
+            if
+                local.get $a i64.const -1 i64.eq
+                if
+                    local.get $b i64.const -9223372036854775808 i64.eq
+                    if call $pipes.overflow end
+                else
+                    local.get $r local.get $a i64.div_s local.get $b i64.ne
+                    if call $pipes.overflow end
+                end
+            end
+            local.get $r",
+        );
+        for (name, op) in [("pipes.div", "i64.div_s"), ("pipes.mod", "i64.rem_s")].iter() {
+            self.runtime_func(
+                name,
+                &[("a", I64), ("b", I64)],
+                Some(I64),
+                &[],
+                &format!(
+                    "local.get $b i64.eqz
+                    if i32.const {} call $pipes.panic end
+                    local.get $a i64.const -9223372036854775808 i64.eq
+                    local.get $b i64.const -1 i64.eq i32.and
+                    if call $pipes.overflow end
+                    local.get $a local.get $b {}",
+                    divide_by_zero, op
+                ),
+            );
+        }
+
+        // strings, in chars rather than bytes
+        self.runtime_func(
+            "pipes.str_len",
+            &[("s", I32)],
+            Some(I64),
+            &[("i", I32), ("end", I32), ("n", I64)],
+            "local.get $s i32.const 4 i32.add local.tee $i
+            local.get $s i32.load i32.add local.set $end
+            block $done loop $next
+                local.get $i local.get $end i32.ge_u br_if $done
+                local.get $i i32.load8_u i32.const 192 i32.and i32.const 128 i32.ne
+                if local.get $n i64.const 1 i64.add local.set $n end
+                local.get $i i32.const 1 i32.add local.set $i
+                br $next
+            end end
+            local.get $n",
+        );
+        self.runtime_func(
+            "pipes.str_eq",
+            &[("a", I32), ("b", I32)],
+            Some(I32),
+            &[("i", I32), ("len", I32)],
+            "local.get $a i32.load local.tee $len local.get $b i32.load i32.ne
+            if i32.const 0 return end
+            block $done loop $next
+                local.get $i local.get $len i32.ge_u br_if $done
+                local.get $a local.get $i i32.add i32.load8_u offset=4
+                local.get $b local.get $i i32.add i32.load8_u offset=4
+                i32.ne if i32.const 0 return end
+                local.get $i i32.const 1 i32.add local.set $i
+                br $next
+            end end
+            i32.const 1",
+        );
+
+        // lists
+        self.runtime_func(
+            "pipes.list_new",
+            &[("len", I32)],
+            Some(I32),
+            &[("list", I32)],
+            "local.get $len i32.const 3 i32.shl i32.const 8 i32.add call $pipes.alloc
+            local.tee $list local.get $len i32.store
+            local.get $list",
+        );
+        self.runtime_func(
+            "pipes.list_len",
+            &[("list", I32)],
+            Some(I64),
+            &[],
+            "local.get $list i32.load i64.extend_i32_u",
+        );
+
+        // closures, which every function takes the environment of last
+        self.runtime_func(
+            "pipes.closure",
+            &[("index", I32), ("env", I32)],
+            Some(I32),
+            &[("closure", I32)],
+            &format!(
+                "i32.const 12 call $pipes.alloc local.tee $closure local.get $index i32.store
+                local.get $closure local.get $env i32.store offset=4
+                local.get $closure i32.const {} i32.store offset=8
+                local.get $closure",
+                lambda
+            ),
+        );
+
+        // printing
+        self.runtime_func(
+            "pipes.show_bool",
+            &[("b", I32)],
+            None,
+            &[],
+            &format!(
+                "local.get $b
+                if i32.const {} call $host.write
+                else i32.const {} call $host.write end",
+                true_, false_
+            ),
+        );
+        self.runtime_func(
+            "pipes.show_closure",
+            &[("closure", I32)],
+            None,
+            &[],
+            &format!(
+                "i32.const {} call $host.write
+                local.get $closure i32.load offset=8 call $host.write
+                i32.const {} call $host.write",
+                func, close
+            ),
+        );
+    }
+
+    fn runtime_func(
+        &mut self,
+        name: &str,
+        params: &[(&str, ValType)],
+        result: Option<ValType>,
+        locals: &[(&str, ValType)],
+        body: &str,
+    ) {
+        let named = |vars: &[(&str, ValType)]| {
+            vars.iter()
+                .map(|(name, ty)| (String::from(*name), *ty))
+                .collect()
+        };
+        self.wasm.funcs.push(Func {
+            name: String::from(name),
+            params: named(params),
+            result,
+            locals: named(locals),
+            body: asm(body),
+            comment: None,
+        });
+    }
+
+    // ----------- items ---------- \\
+    fn program(&mut self, program: &Program) -> Result<(), CodegenError> {
+        for builtin in Builtin::ALL.iter() {
+            self.scopes
+                .define_global(builtin.name(), Binding::Builtin(*builtin));
+        }
+
+        // top level functions can be called before they're declared
+        let mut names = vec![];
+        for function in program.functions() {
+            let (params, output) = signature(function)?;
+            let name = self.unique(&function.name);
+            self.scopes.define_global(
+                &function.name,
+                Binding::Function(name.clone(), params, output),
+            );
+            names.push(name);
+        }
+        let mut names = names.into_iter();
+
+        // top level statements run in main, and their blocks' locals are main's
+        let mut boxed = HashSet::new();
+        for item in &program.items {
+            match item {
+                Item::Global(_, exp) => exp_lambda_names(exp, false, &mut boxed),
+                Item::Statement(stmt) => stmt_lambda_names(stmt, false, &mut boxed),
+                _ => {}
+            }
+        }
+        self.builder.boxed = boxed;
+        self.scopes.push_frame();
+        for item in &program.items {
+            match item {
+                Item::Struct(_) => {}
+                Item::Function(function) => {
+                    let name = names.next().unwrap();
+                    self.function(&name, function)?;
+                }
+                Item::Global(var, exp) => self.global(var, exp)?,
+                Item::Statement(stmt) => self.stmt(stmt)?,
+            }
+        }
+        self.scopes.pop_frame();
+        Ok(())
+    }
+
+    fn global(&mut self, var: &Var, exp: &Exp) -> Result<(), CodegenError> {
+        let ty = var.var_type.clone().unwrap_or(Type::Void);
+        let value = self.exp_as(exp, &ty)?;
+        let name = self.unique(&var.name);
+        self.wasm.globals.push((name.clone(), val_type(&ty)?));
+        self.emit(value.code);
+        self.emit(vec![Instr::Global("global.set", name.clone())]);
+        self.scopes
+            .define_global(&var.name, Binding::Var(ty, Storage::Global(name)));
+        Ok(())
+    }
+
+    // named functions are called directly, so they can't capture anything
+    fn function(&mut self, name: &str, function: &Function) -> Result<(), CodegenError> {
+        let (_, output) = signature(function)?;
+        let (_, captures) = self.lower_function(
+            name,
+            &function.param,
+            &function.block,
+            Returns::Expected(output),
+        )?;
+        match captures.first() {
+            Some((captured, _)) => Err(CodegenError::Captures(captured.clone())),
+            None => Ok(()),
+        }
+    }
+
+    // writes out a function in a frame of its own, giving back its output
+    // type and the locals of enclosing functions it captured
+    fn lower_function(
+        &mut self,
+        name: &str,
+        params: &[Var],
+        block: &[Statement],
+        returns: Returns,
+    ) -> Result<(Type, Vec<(String, Type)>), CodegenError> {
+        let builder = Builder::new(returns, lambda_names(block));
+        let outer = std::mem::replace(&mut self.builder, builder);
+        self.scopes.push_frame();
+        let mut wasm_params = vec![];
+        for param in params {
+            let param_type = param
+                .var_type
+                .clone()
+                .ok_or_else(|| CodegenError::UnknownType(param.name.clone()))?;
+            let arg = self.unique(&param.name);
+            wasm_params.push((arg.clone(), val_type(&param_type)?));
+            if self.builder.boxed.contains(&param.name) {
+                self.local(&param.name, param_type, vec![local_get(&arg)])?;
+            } else {
+                self.scopes
+                    .define(&param.name, Binding::Var(param_type, Storage::Local(arg)));
+            }
+        }
+        wasm_params.push((String::from("env"), ValType::I32));
+        self.stmts(block)?;
+        self.scopes.pop_frame();
+        let mut builder = std::mem::replace(&mut self.builder, outer);
+
+        let output = builder.returns.output();
+        // the checker made sure every path through a function that gives
+        // something back returns, but the end still needs a value to validate
+        let returns = matches!(block.last(), Some(Statement::Return(..)));
+        if output != Type::Void && !returns {
+            builder.body.push(Instr::Plain("unreachable"));
+        }
+        self.wasm.funcs.push(Func {
+            name: String::from(name),
+            params: wasm_params,
+            result: result_type(&output)?,
+            locals: builder.locals,
+            body: builder.body,
+            comment: None,
+        });
+        Ok((output, builder.captures))
+    }
+}
+
+impl LowerBlock for Module<'_> {
+    type Binding = Binding;
+
+    fn scopes(&mut self) -> &mut Scopes<Binding> {
+        &mut self.scopes
+    }
+
+    // ----------- statements ---------- \\
+    fn stmt(&mut self, stmt: &Statement) -> Result<(), CodegenError> {
+        match stmt {
+            Statement::Assignment(var, exp) if var.var_type.is_some() => {
+                let declared = var.var_type.clone().unwrap();
+                let value = self.exp_as(exp, &declared)?;
+                self.local(&var.name, declared, value.code)
+            }
+            Statement::Assignment(var, exp) => match self.lookup(&var.name)? {
+                Binding::Var(ty, storage) => {
+                    let value = self.exp_as(exp, &ty)?;
+                    let code = store(&ty, &storage, value.code)?;
+                    self.emit(code);
+                    Ok(())
+                }
+                _ => Err(CodegenError::Unsupported("assigning to a function")),
+            },
+            Statement::Function(function) => {
+                let (params, output) = signature(function)?;
+                let name = self.unique(&function.name);
+                // defined first so the body can call itself
+                self.scopes.define(
+                    &function.name,
+                    Binding::Function(name.clone(), params, output),
+                );
+                self.function(&name, function)
+            }
+            Statement::Return(exp, _) => self.ret(exp),
+            Statement::If(if_stmt) => self.if_stmt(if_stmt),
+            Statement::For(for_stmt) => self.for_stmt(for_stmt),
+            Statement::While(while_stmt) => self.while_stmt(while_stmt),
+            Statement::Exp(exp, _) => {
+                let value = self.exp(exp)?;
+                self.discard(value);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'a> Module<'a> {
+    fn ret(&mut self, exp: &Exp) -> Result<(), CodegenError> {
+        let value = match &self.builder.returns {
+            Returns::Expected(output) => {
+                let output = output.clone();
+                self.exp_as(exp, &output)?
+            }
+            _ => self.exp(exp)?,
+        };
+        // the top level gives nothing back
+        let output = self
+            .builder
+            .returns
+            .returning(&value.ty)
+            .unwrap_or(Type::Void);
+        if output == Type::Void {
+            self.discard(value);
+        } else {
+            self.emit(value.code);
+        }
+        self.emit(vec![Instr::Plain("return")]);
+        Ok(())
+    }
+
+    fn if_stmt(&mut self, if_stmt: &IfStatement) -> Result<(), CodegenError> {
+        let condition = self.exp(&if_stmt.condition)?;
+        self.emit(condition.code);
+        self.emit(vec![Instr::Block("if", None, None)]);
+        self.block(&if_stmt.true_branch)?;
+        if let Some(false_branch) = &if_stmt.false_branch {
+            self.emit(vec![Instr::Else]);
+            self.block(false_branch)?;
+        }
+        self.emit(vec![Instr::End]);
+        Ok(())
+    }
+
+    fn while_stmt(&mut self, while_stmt: &WhileStatement) -> Result<(), CodegenError> {
+        let (done, next) = (self.unique("done"), self.unique("next"));
+        let condition = self.exp(&while_stmt.condition)?;
+        self.emit(vec![
+            Instr::Block("block", Some(done.clone()), None),
+            Instr::Block("loop", Some(next.clone()), None),
+        ]);
+        self.emit(condition.code);
+        self.emit(vec![Instr::Plain("i32.eqz"), Instr::Br("br_if", done)]);
+        self.block(&while_stmt.block)?;
+        self.emit(vec![Instr::Br("br", next), Instr::End, Instr::End]);
+        Ok(())
+    }
+
+    fn for_stmt(&mut self, for_stmt: &ForStatement) -> Result<(), CodegenError> {
+        let list = self.exp(&for_stmt.iterable)?;
+        let item_type = list.ty.element_type().ok_or(CodegenError::Unsupported(
+            "looping over something that isn't a list",
+        ))?;
+        let name = self.new_local("list", ValType::I32);
+        let i = self.new_local("i", ValType::I32);
+        let (done, next) = (self.unique("done"), self.unique("next"));
+        self.emit(list.code);
+        self.emit(vec![
+            local_set(&name),
+            Instr::I32Const(0),
+            local_set(&i),
+            Instr::Block("block", Some(done.clone()), None),
+            Instr::Block("loop", Some(next.clone()), None),
+            local_get(&i),
+            local_get(&name),
+            Instr::Memory("i32.load", 0),
+            Instr::Plain("i32.ge_u"),
+            Instr::Br("br_if", done),
+        ]);
+        let mut item = item_at(&name, &i);
+        item.extend(unslot(&item_type)?);
+        self.scopes.push();
+        let result = self
+            .local(&for_stmt.iter.name, item_type, item)
+            .and_then(|_| self.stmts(&for_stmt.block));
+        self.scopes.pop();
+        result?;
+        self.emit(vec![
+            local_get(&i),
+            Instr::I32Const(1),
+            Instr::Plain("i32.add"),
+            local_set(&i),
+            Instr::Br("br", next),
+            Instr::End,
+            Instr::End,
+        ]);
+        Ok(())
+    }
+
+    // an expression statement, or a value nothing uses
+    fn discard(&mut self, value: Value) {
+        self.emit(value.code);
+        if value.ty != Type::Void {
+            self.emit(vec![Instr::Plain("drop")]);
+        }
+    }
+
+    // ----------- expressions ---------- \\
+    // the stack runs everything in the order it's written, so unlike
+    // codegen::c nothing has to be kept in temporaries to keep that order
+    fn exp(&mut self, exp: &Exp) -> Result<Value, CodegenError> {
+        match exp {
+            Exp::Var(var) => match self.lookup(&var.name)? {
+                Binding::Var(ty, storage) => {
+                    let code = load(&ty, &storage)?;
+                    Ok(Value::new(ty, code))
+                }
+                Binding::Function(name, params, output) => {
+                    let closure = self.closure(&name, &var.name);
+                    Ok(Value::new(
+                        Type::HigherOrderFunction(params, Box::new(output)),
+                        vec![Instr::I32Const(closure as i32)],
+                    ))
+                }
+                Binding::Builtin(_) => Err(CodegenError::Unsupported("builtins as values")),
+            },
+            Exp::Int(i) => Ok(Value::new(Type::Int, vec![Instr::I64Const(*i)])),
+            Exp::BigInt(_) => Err(CodegenError::Unsupported("bigints")),
+            Exp::Float(x) => Ok(Value::new(Type::Float, vec![Instr::F64Const(*x)])),
+            Exp::Str(s) => {
+                let address = self.string(s);
+                Ok(Value::new(Type::Str, vec![Instr::I32Const(address as i32)]))
+            }
+            Exp::Bool(b) => Ok(Value::new(Type::Bool, vec![Instr::I32Const(*b as i32)])),
+            Exp::Void => Ok(Value::void()),
+
+            Exp::Call(callee, args) => self.call(callee, None, args),
+            Exp::Unary(op, exp) => {
+                let value = self.exp(exp)?;
+                unary(*op, value)
+            }
+            Exp::Op(Operation::And, left, right) => self.logic(Operation::And, left, right),
+            Exp::Op(Operation::Or, left, right) => self.logic(Operation::Or, left, right),
+            Exp::Op(op, left, right) => {
+                let left = self.exp(left)?;
+                let right = self.exp(right)?;
+                self.binary(*op, left, right)
+            }
+            Exp::Pipe(left, right) => {
+                let first = self.exp(left)?;
+                match &**right {
+                    Exp::Call(callee, args) => self.call(callee, Some(first), args),
+                    callee => self.call(callee, Some(first), &[]),
+                }
+            }
+
+            Exp::Struct(name, fields) => self.struct_literal(name, fields),
+            Exp::Field(exp, field) => {
+                let mut value = self.exp(exp)?;
+                let (index, field_type) = match &value.ty {
+                    Type::Struct(name) => self
+                        .structs
+                        .fields(name)
+                        .and_then(|fields| {
+                            fields
+                                .iter()
+                                .position(|(declared, _)| declared == field)
+                                .map(|i| (i, fields[i].1.clone()))
+                        })
+                        .ok_or_else(|| CodegenError::UnknownType(format!("{}.{}", name, field)))?,
+                    _ => return Err(CodegenError::Unsupported("fields of non structs")),
+                };
+                value
+                    .code
+                    .push(load_op(val_type(&field_type)?, 8 * index as u32));
+                Ok(Value::new(field_type, value.code))
+            }
+            Exp::List(list_type, items) => self.list(list_type.as_ref(), items),
+            Exp::HOF(params, block) => self.lambda(params, block),
+        }
+    }
+
+    // struct literals without a name take it from where they're going
+    fn exp_as(&mut self, exp: &Exp, expected: &Type) -> Result<Value, CodegenError> {
+        match (exp, expected) {
+            (Exp::Struct(name, fields), Type::Struct(expected)) if name.is_empty() => {
+                self.struct_literal(expected, fields)
+            }
+            _ => self.exp(exp),
+        }
+    }
+
+    fn args(&mut self, args: &[Exp], expected: &[Type]) -> Result<Vec<Value>, CodegenError> {
+        let mut values = vec![];
+        for (i, arg) in args.iter().enumerate() {
+            values.push(match expected.get(i) {
+                Some(expected) => self.exp_as(arg, expected)?,
+                None => self.exp(arg)?,
+            });
+        }
+        Ok(values)
+    }
+
+    // named functions are called directly, anything else through the
+    // table. a pipe's left side comes in as first
+    fn call(
+        &mut self,
+        callee: &Exp,
+        first: Option<Value>,
+        args: &[Exp],
+    ) -> Result<Value, CodegenError> {
+        let binding = match callee {
+            Exp::Var(var) => Some(self.lookup(&var.name)?),
+            _ => None,
+        };
+        let skip = first.iter().count();
+        let mut values: Vec<Value> = first.into_iter().collect();
+        match binding {
+            Some(Binding::Function(name, params, output)) => {
+                values.extend(self.args(args, params.get(skip..).unwrap_or(&[]))?);
+                let mut code: Vec<Instr> =
+                    values.into_iter().flat_map(|value| value.code).collect();
+                code.extend(vec![Instr::I32Const(0), Instr::Call(name)]);
+                Ok(Value::new(output, code))
+            }
+            Some(Binding::Builtin(builtin)) => {
+                values.extend(self.args(args, &[])?);
+                self.builtin(builtin, values)
+            }
+            _ => {
+                // the callee runs after a pipe's left side
+                let closure = self.exp(callee)?;
+                let (params, output) = match &closure.ty {
+                    Type::HigherOrderFunction(params, output) => (params.clone(), *output.clone()),
+                    _ => return Err(CodegenError::Unsupported("calling a non function")),
+                };
+                let temp = self.new_local("callee", ValType::I32);
+                values.push(Value::new(closure.ty, closure.code));
+                let mut code: Vec<Instr> =
+                    values.into_iter().flat_map(|value| value.code).collect();
+                code.push(local_set(&temp));
+                for value in self.args(args, params.get(skip..).unwrap_or(&[]))? {
+                    code.extend(value.code);
+                }
+                let mut signature = Signature {
+                    params: params.iter().map(val_type).collect::<Result<_, _>>()?,
+                    result: result_type(&output)?,
+                };
+                signature.params.push(ValType::I32);
+                code.extend(vec![
+                    local_get(&temp),
+                    Instr::Memory("i32.load", 4),
+                    local_get(&temp),
+                    Instr::Memory("i32.load", 0),
+                    Instr::CallIndirect(signature),
+                ]);
+                Ok(Value::new(output, code))
+            }
+        }
+    }
+
+    fn builtin(&mut self, builtin: Builtin, args: Vec<Value>) -> Result<Value, CodegenError> {
+        let arg = match args.as_slice() {
+            [arg] => arg.clone(),
+            _ => {
+                return Err(CodegenError::Unsupported(
+                    "builtins without exactly one argument",
+                ))
+            }
+        };
+        let mut code = arg.code;
+        match builtin {
+            Builtin::Print => {
+                code.extend(self.show(&arg.ty)?);
+                code.extend(self.write("\n"));
+                Ok(Value::new(Type::Void, code))
+            }
+            Builtin::Len => {
+                let len = match arg.ty {
+                    Type::Str => "pipes.str_len",
+                    _ => "pipes.list_len",
+                };
+                code.push(Instr::Call(String::from(len)));
+                Ok(Value::new(Type::Int, code))
+            }
+        }
+    }
+
+    // && and || only run the right side when they have to
+    fn logic(&mut self, op: Operation, left: &Exp, right: &Exp) -> Result<Value, CodegenError> {
+        let left = self.exp(left)?;
+        let right = self.exp(right)?;
+        let mut code = left.code;
+        code.push(Instr::Block("if", None, Some(ValType::I32)));
+        match op {
+            Operation::And => {
+                code.extend(right.code);
+                code.extend(vec![Instr::Else, Instr::I32Const(0)]);
+            }
+            _ => {
+                code.extend(vec![Instr::I32Const(1), Instr::Else]);
+                code.extend(right.code);
+            }
+        }
+        code.push(Instr::End);
+        Ok(Value::new(Type::Bool, code))
+    }
+
+    fn binary(&mut self, op: Operation, left: Value, right: Value) -> Result<Value, CodegenError> {
+        use Operation::*;
+
+        if left.ty == Type::BigInt || right.ty == Type::BigInt {
+            return Err(CodegenError::Unsupported("bigints"));
+        }
+        // mixed arithmetic and comparisons promote the int to a float
+        let float = match (&left.ty, &right.ty) {
+            (Type::Int, Type::Float) | (Type::Float, Type::Int) => true,
+            (ty, _) => *ty == Type::Float,
+        };
+        let mut code = left.code;
+        if float && left.ty == Type::Int {
+            code.push(Instr::Plain("f64.convert_i64_s"));
+        }
+        code.extend(right.code);
+        if float && right.ty == Type::Int {
+            code.push(Instr::Plain("f64.convert_i64_s"));
+        }
+        let operands = if float { Type::Float } else { left.ty };
+
+        let call = |name: &str| Instr::Call(String::from(name));
+        let (instr, ty) = match (op, &operands) {
+            (Equal, _) | (NotEqual, _) => {
+                code.extend(self.equal(&operands)?);
+                if op == NotEqual {
+                    code.push(Instr::Plain("i32.eqz"));
+                }
+                return Ok(Value::new(Type::Bool, code));
+            }
+            (GreaterThan, Type::Int) => (Instr::Plain("i64.gt_s"), Type::Bool),
+            (LessThan, Type::Int) => (Instr::Plain("i64.lt_s"), Type::Bool),
+            (GreaterEqual, Type::Int) => (Instr::Plain("i64.ge_s"), Type::Bool),
+            (LessEqual, Type::Int) => (Instr::Plain("i64.le_s"), Type::Bool),
+            (GreaterThan, Type::Float) => (Instr::Plain("f64.gt"), Type::Bool),
+            (LessThan, Type::Float) => (Instr::Plain("f64.lt"), Type::Bool),
+            (GreaterEqual, Type::Float) => (Instr::Plain("f64.ge"), Type::Bool),
+            (LessEqual, Type::Float) => (Instr::Plain("f64.le"), Type::Bool),
+            (Add, Type::Int) => (call("pipes.add"), Type::Int),
+            (Subtract, Type::Int) => (call("pipes.sub"), Type::Int),
+            (Multiply, Type::Int) => (call("pipes.mul"), Type::Int),
+            (Divide, Type::Int) => (call("pipes.div"), Type::Int),
+            (Modulo, Type::Int) => (call("pipes.mod"), Type::Int),
+            (Add, Type::Float) => (Instr::Plain("f64.add"), Type::Float),
+            (Subtract, Type::Float) => (Instr::Plain("f64.sub"), Type::Float),
+            (Multiply, Type::Float) => (Instr::Plain("f64.mul"), Type::Float),
+            (Divide, Type::Float) => (Instr::Plain("f64.div"), Type::Float),
+            (Modulo, Type::Float) => (call("host.fmod"), Type::Float),
+            _ => return Err(CodegenError::Unsupported("this operator on these types")),
+        };
+        code.push(instr);
+        Ok(Value::new(ty, code))
+    }
+
+    // code comparing the two values of the given type on the stack, ints
+    // and floats by value and everything else exactly, leaving 1 or 0
+    fn equal(&mut self, ty: &Type) -> Result<Vec<Instr>, CodegenError> {
+        Ok(match ty {
+            Type::Void => vec![Instr::I32Const(1)],
+            Type::Int => vec![Instr::Plain("i64.eq")],
+            Type::Float => vec![Instr::Plain("f64.eq")],
+            // functions are only equal to themselves
+            Type::Bool | Type::HigherOrderFunction(..) => vec![Instr::Plain("i32.eq")],
+            Type::Str => vec![Instr::Call(String::from("pipes.str_eq"))],
+            Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+            Type::List(_) | Type::Struct(_) => vec![Instr::Call(self.eq_fn(ty)?)],
+        })
+    }
+
+    // fields are evaluated in the order they're written and stored where
+    // they were declared
+    fn struct_literal(&mut self, name: &str, fields: &[Var]) -> Result<Value, CodegenError> {
+        if name.is_empty() {
+            return Err(CodegenError::UnknownType(String::from("a struct literal")));
+        }
+        let declared = self
+            .structs
+            .fields(name)
+            .ok_or_else(|| CodegenError::UnknownType(String::from(name)))?
+            .to_vec();
+        if let Some((missing, _)) = declared
+            .iter()
+            .find(|(field, _)| !fields.iter().any(|literal| literal.name == *field))
+        {
+            return Err(CodegenError::UnknownType(missing.clone()));
+        }
+        let pointer = self.new_local(name, ValType::I32);
+        let mut code = vec![
+            Instr::I32Const(8 * declared.len() as i32),
+            Instr::Call(String::from("pipes.alloc")),
+            local_set(&pointer),
+        ];
+        let void = Exp::Void;
+        for field in fields {
+            let (index, field_type) = declared
+                .iter()
+                .position(|(name, _)| *name == field.name)
+                .map(|i| (i, declared[i].1.clone()))
+                .ok_or_else(|| CodegenError::UnknownType(field.name.clone()))?;
+            let store = store_op(val_type(&field_type)?, 8 * index as u32);
+            let value = self.exp_as(field.value.as_ref().unwrap_or(&void), &field_type)?;
+            code.push(local_get(&pointer));
+            code.extend(value.code);
+            code.push(store);
+        }
+        code.push(local_get(&pointer));
+        Ok(Value::new(Type::Struct(String::from(name)), code))
+    }
+
+    fn list(&mut self, list_type: Option<&Type>, items: &[Exp]) -> Result<Value, CodegenError> {
+        let item_type = list_type.and_then(|list_type| list_type.element_type());
+        let expected: Vec<Type> = item_type.into_iter().cycle().take(items.len()).collect();
+        let values = self.args(items, &expected)?;
+        // an empty list nobody gave a type to never has its items looked at
+        let ty = match (list_type, values.first()) {
+            (Some(list_type), _) => list_type.clone(),
+            (None, Some(first)) => Type::List(Box::new(first.ty.clone())),
+            (None, None) => Type::List(Box::new(Type::Void)),
+        };
+        let pointer = self.new_local("list", ValType::I32);
+        let mut code = vec![
+            Instr::I32Const(values.len() as i32),
+            Instr::Call(String::from("pipes.list_new")),
+            local_set(&pointer),
+        ];
+        for (i, value) in values.into_iter().enumerate() {
+            code.push(local_get(&pointer));
+            code.extend(value.code);
+            code.extend(slot(&value.ty)?);
+            code.push(Instr::Memory("i64.store", 8 + 8 * i as u32));
+        }
+        code.push(local_get(&pointer));
+        Ok(Value::new(ty, code))
+    }
+
+    // a lambda becomes a function of its own in the table, and its closure
+    // points at the boxes of every local it uses from the functions around it
+    fn lambda(&mut self, params: &[Var], block: &[Statement]) -> Result<Value, CodegenError> {
+        let name = self.unique("lambda");
+        let (output, captures) = self.lower_function(&name, params, block, Returns::Infer(None))?;
+        let index = self.table(&name);
+        let params = params
+            .iter()
+            .map(|param| param.var_type.clone().unwrap_or(Type::Void))
+            .collect();
+        let mut code = vec![];
+        let env = if captures.is_empty() {
+            Instr::I32Const(0)
+        } else {
+            let env = self.new_local("env", ValType::I32);
+            code.extend(vec![
+                Instr::I32Const(4 * captures.len() as i32),
+                Instr::Call(String::from("pipes.alloc")),
+                local_set(&env),
+            ]);
+            for (i, (captured, _)) in captures.iter().enumerate() {
+                code.push(local_get(&env));
+                code.extend(self.box_of(captured)?);
+                code.push(Instr::Memory("i32.store", 4 * i as u32));
+            }
+            local_get(&env)
+        };
+        code.extend(vec![
+            Instr::I32Const(index as i32),
+            env,
+            Instr::Call(String::from("pipes.closure")),
+        ]);
+        Ok(Value::new(
+            Type::HigherOrderFunction(params, Box::new(output)),
+            code,
+        ))
+    }
+
+    // ----------- helpers ---------- \\
+    // code printing the value of the given type on the stack
+    fn show(&mut self, ty: &Type) -> Result<Vec<Instr>, CodegenError> {
+        let call = |name: &str| Ok(vec![Instr::Call(String::from(name))]);
+        match ty {
+            Type::Void => Ok(self.write("()")),
+            Type::Int => call("host.show_int"),
+            Type::Float => call("host.show_float"),
+            Type::Bool => call("pipes.show_bool"),
+            Type::Str => call("host.write"),
+            Type::BigInt => Err(CodegenError::Unsupported("bigints")),
+            Type::HigherOrderFunction(..) => call("pipes.show_closure"),
+            Type::List(_) | Type::Struct(_) => Ok(vec![Instr::Call(self.show_fn(ty)?)]),
+        }
+    }
+
+    fn write(&mut self, text: &str) -> Vec<Instr> {
+        let address = self.string(text);
+        vec![
+            Instr::I32Const(address as i32),
+            Instr::Call(String::from("host.write")),
+        ]
+    }
+
+    // a function printing a list or struct, written the first time it's needed
+    fn show_fn(&mut self, ty: &Type) -> Result<String, CodegenError> {
+        let key = format!("show {}", ty);
+        if let Some(name) = self.helpers.get(&key) {
+            return Ok(name.clone());
+        }
+        let name = self.unique("show");
+        self.helpers.insert(key, name.clone());
+
+        let mut body = vec![];
+        let mut locals = vec![];
+        match ty {
+            Type::List(item_type) => {
+                locals.push((String::from("i"), ValType::I32));
+                body.extend(self.write("["));
+                body.extend(asm("block $done loop $next
+                    local.get $i local.get $value i32.load i32.ge_u br_if $done
+                    local.get $i if"));
+                body.extend(self.write(", "));
+                body.push(Instr::End);
+                body.extend(item_at("value", "i"));
+                body.extend(unslot(item_type)?);
+                body.extend(self.show(item_type)?);
+                body.extend(asm(
+                    "local.get $i i32.const 1 i32.add local.set $i br $next end end",
+                ));
+                body.extend(self.write("]"));
+            }
+            Type::Struct(struct_name) => {
+                let fields = self.structs.fields(struct_name).unwrap_or(&[]).to_vec();
+                body.extend(self.write(&format!("{} {{", struct_name)));
+                for (i, (field, field_type)) in fields.iter().enumerate() {
+                    let separator = if i > 0 { "," } else { "" };
+                    body.extend(self.write(&format!("{} {}: ", separator, field)));
+                    body.push(local_get("value"));
+                    body.push(load_op(val_type(field_type)?, 8 * i as u32));
+                    body.extend(self.show(field_type)?);
+                }
+                body.extend(self.write(" }"));
+            }
+            _ => return Err(CodegenError::Unsupported("printing this type")),
+        }
+        self.wasm.funcs.push(Func {
+            name: name.clone(),
+            params: vec![(String::from("value"), ValType::I32)],
+            result: None,
+            locals,
+            body,
+            comment: Some(format!("prints a {}", ty)),
+        });
+        Ok(name)
+    }
+
+    // a function comparing two lists or structs, giving back 1 or 0
+    fn eq_fn(&mut self, ty: &Type) -> Result<String, CodegenError> {
+        let key = format!("eq {}", ty);
+        if let Some(name) = self.helpers.get(&key) {
+            return Ok(name.clone());
+        }
+        let name = self.unique("eq");
+        self.helpers.insert(key, name.clone());
+
+        let differ = asm("i32.eqz if i32.const 0 return end");
+        let mut body = vec![];
+        let mut locals = vec![];
+        match ty {
+            Type::List(item_type) => {
+                locals.push((String::from("i"), ValType::I32));
+                body.extend(asm("local.get $a i32.load local.get $b i32.load i32.ne
+                    if i32.const 0 return end
+                    block $done loop $next
+                    local.get $i local.get $a i32.load i32.ge_u br_if $done"));
+                for list in ["a", "b"].iter() {
+                    body.extend(item_at(list, "i"));
+                    body.extend(unslot(item_type)?);
+                }
+                body.extend(self.equal(item_type)?);
+                body.extend(differ);
+                body.extend(asm(
+                    "local.get $i i32.const 1 i32.add local.set $i br $next end end",
+                ));
+            }
+            Type::Struct(struct_name) => {
+                let fields = self.structs.fields(struct_name).unwrap_or(&[]).to_vec();
+                for (i, (_, field_type)) in fields.iter().enumerate() {
+                    for value in ["a", "b"].iter() {
+                        body.push(local_get(value));
+                        body.push(load_op(val_type(field_type)?, 8 * i as u32));
+                    }
+                    body.extend(self.equal(field_type)?);
+                    body.extend(differ.clone());
+                }
+            }
+            _ => return Err(CodegenError::Unsupported("comparing this type")),
+        }
+        body.push(Instr::I32Const(1));
+        self.wasm.funcs.push(Func {
+            name: name.clone(),
+            params: vec![
+                (String::from("a"), ValType::I32),
+                (String::from("b"), ValType::I32),
+            ],
+            result: Some(ValType::I32),
+            locals,
+            body,
+            comment: Some(format!("compares two {}", ty)),
+        });
+        Ok(name)
+    }
+
+    // a named function as a value, a closure without an environment in the
+    // data segment, so it compares equal to itself
+    fn closure(&mut self, function: &str, pipes_name: &str) -> u32 {
+        let key = format!("closure {}", function);
+        if let Some(address) = self.helpers.get(&key) {
+            return address.parse().unwrap();
+        }
+        let index = self.table(function);
+        let name = self.string(pipes_name);
+        let mut bytes = vec![];
+        for word in [index, 0, name].iter() {
+            bytes.extend(word.to_le_bytes());
+        }
+        let address = self.data(&bytes);
+        self.helpers.insert(key, address.to_string());
+        address
+    }
+
+    // the index a function has in the table, for call_indirect
+    fn table(&mut self, function: &str) -> u32 {
+        let table = &mut self.wasm.table;
+        match table.iter().position(|name| name == function) {
+            Some(index) => index as u32,
+            None => {
+                table.push(String::from(function));
+                table.len() as u32 - 1
+            }
+        }
+    }
+
+    // the address of a string in the data segment
+    fn string(&mut self, s: &str) -> u32 {
+        if let Some(address) = self.strings.get(s) {
+            return *address;
+        }
+        let mut bytes = (s.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(s.as_bytes());
+        let address = self.data(&bytes);
+        self.strings.insert(String::from(s), address);
+        address
+    }
+
+    fn data(&mut self, bytes: &[u8]) -> u32 {
+        let data = &mut self.wasm.data;
+        while !data.len().is_multiple_of(4) {
+            data.push(0);
+        }
+        let address = self.wasm.data_start + data.len() as u32;
+        data.extend(bytes);
+        address
+    }
+
+    // ----------- names ---------- \\
+    // what a name refers to. a local of an enclosing function is captured,
+    // and used through the pointer to its box in the closure's environment
+    fn lookup(&mut self, name: &str) -> Result<Binding, CodegenError> {
+        match self.scopes.lookup(name) {
+            Some((Binding::Var(ty, _), Reach::Outer)) => {
+                let ty = ty.clone();
+                let captures = &mut self.builder.captures;
+                let index = match captures.iter().position(|(captured, _)| captured == name) {
+                    Some(index) => index,
+                    None => {
+                        captures.push((String::from(name), ty.clone()));
+                        captures.len() - 1
+                    }
+                };
+                Ok(Binding::Var(ty, Storage::Captured(index as u32)))
+            }
+            Some((binding, _)) => Ok(binding.clone()),
+            None => Err(CodegenError::UndefinedVariable(String::from(name))),
+        }
+    }
+
+    // code leaving the pointer to the box a captured variable lives in
+    fn box_of(&mut self, name: &str) -> Result<Vec<Instr>, CodegenError> {
+        match self.lookup(name)? {
+            Binding::Var(_, Storage::Boxed(local)) => Ok(vec![local_get(&local)]),
+            Binding::Var(_, Storage::Captured(index)) => Ok(captured(index)),
+            _ => Err(CodegenError::Captures(String::from(name))),
+        }
+    }
+
+    // a new variable in the current scope set to the value value leaves, in
+    // a box if a lambda might capture it
+    fn local(&mut self, name: &str, ty: Type, value: Vec<Instr>) -> Result<(), CodegenError> {
+        let val = val_type(&ty)?;
+        if self.builder.boxed.contains(name) {
+            let local = self.new_local(name, ValType::I32);
+            self.emit(vec![
+                Instr::I32Const(8),
+                Instr::Call(String::from("pipes.alloc")),
+                Instr::Local("local.tee", local.clone()),
+            ]);
+            self.emit(value);
+            self.emit(vec![store_op(val, 0)]);
+            self.scopes
+                .define(name, Binding::Var(ty, Storage::Boxed(local)));
+        } else {
+            let local = self.new_local(name, val);
+            self.emit(value);
+            self.emit(vec![local_set(&local)]);
+            self.scopes
+                .define(name, Binding::Var(ty, Storage::Local(local)));
+        }
+        Ok(())
+    }
+
+    fn new_local(&mut self, name: &str, ty: ValType) -> String {
+        let local = self.unique(name);
+        self.builder.locals.push((local.clone(), ty));
+        local
+    }
+
+    fn unique(&mut self, name: &str) -> String {
+        self.next += 1;
+        format!("{}.{}", wasm_name(name), self.next)
+    }
+
+    fn emit(&mut self, code: Vec<Instr>) {
+        self.builder.body.extend(code);
+    }
+}
+
+fn unary(op: UnaryOp, value: Value) -> Result<Value, CodegenError> {
+    let mut code = vec![];
+    match (op, &value.ty) {
+        (UnaryOp::Neg, Type::Int) => {
+            code.push(Instr::I64Const(0));
+            code.extend(value.code);
+            code.push(Instr::Call(String::from("pipes.sub")));
+        }
+        (UnaryOp::Neg, Type::Float) => {
+            code.extend(value.code);
+            code.push(Instr::Plain("f64.neg"));
+        }
+        (UnaryOp::Not, Type::Bool) => {
+            code.extend(value.code);
+            code.push(Instr::Plain("i32.eqz"));
+        }
+        (_, Type::BigInt) => return Err(CodegenError::Unsupported("bigints")),
+        _ => return Err(CodegenError::Unsupported("this unary operator")),
+    }
+    Ok(Value::new(value.ty, code))
+}
+
+// ----------- types ---------- \\
+// the wasm type of a value that can be stored somewhere
+fn val_type(ty: &Type) -> Result<ValType, CodegenError> {
+    match ty {
+        Type::Void => Err(CodegenError::Unsupported("storing void values")),
+        Type::BigInt => Err(CodegenError::Unsupported("bigints")),
+        Type::Int => Ok(ValType::I64),
+        Type::Float => Ok(ValType::F64),
+        _ => Ok(ValType::I32),
+    }
+}
+
+fn result_type(ty: &Type) -> Result<Option<ValType>, CodegenError> {
+    match ty {
+        Type::Void => Ok(None),
+        ty => val_type(ty).map(Some),
+    }
+}
+
+fn load(ty: &Type, storage: &Storage) -> Result<Vec<Instr>, CodegenError> {
+    let val = val_type(ty)?;
+    Ok(match storage {
+        Storage::Local(local) => vec![local_get(local)],
+        Storage::Global(global) => vec![Instr::Global("global.get", global.clone())],
+        Storage::Boxed(local) => vec![local_get(local), load_op(val, 0)],
+        Storage::Captured(index) => {
+            let mut code = captured(*index);
+            code.push(load_op(val, 0));
+            code
+        }
+    })
+}
+
+fn store(ty: &Type, storage: &Storage, value: Vec<Instr>) -> Result<Vec<Instr>, CodegenError> {
+    let val = val_type(ty)?;
+    let mut code = match storage {
+        Storage::Boxed(local) => vec![local_get(local)],
+        Storage::Captured(index) => captured(*index),
+        _ => vec![],
+    };
+    code.extend(value);
+    code.push(match storage {
+        Storage::Local(local) => local_set(local),
+        Storage::Global(global) => Instr::Global("global.set", global.clone()),
+        _ => store_op(val, 0),
+    });
+    Ok(code)
+}
+
+// the box at index in the closure's environment
+fn captured(index: u32) -> Vec<Instr> {
+    vec![local_get("env"), Instr::Memory("i32.load", 4 * index)]
+}
+
+fn load_op(ty: ValType, offset: u32) -> Instr {
+    let op = match ty {
+        ValType::I32 => "i32.load",
+        ValType::I64 => "i64.load",
+        ValType::F64 => "f64.load",
+    };
+    Instr::Memory(op, offset)
+}
+
+fn store_op(ty: ValType, offset: u32) -> Instr {
+    let op = match ty {
+        ValType::I32 => "i32.store",
+        ValType::I64 => "i64.store",
+        ValType::F64 => "f64.store",
+    };
+    Instr::Memory(op, offset)
+}
+
+// the slot of item i in a list, both locals
+fn item_at(list: &str, i: &str) -> Vec<Instr> {
+    vec![
+        local_get(list),
+        local_get(i),
+        Instr::I32Const(3),
+        Instr::Plain("i32.shl"),
+        Instr::Plain("i32.add"),
+        Instr::Memory("i64.load", 8),
+    ]
+}
+
+// lists carry every value as an i64: floats by their bits, and bools and
+// pointers as they are
+fn slot(ty: &Type) -> Result<Vec<Instr>, CodegenError> {
+    Ok(match ty {
+        Type::Int => vec![],
+        Type::Void => vec![Instr::I64Const(0)],
+        Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+        Type::Float => vec![Instr::Plain("i64.reinterpret_f64")],
+        _ => vec![Instr::Plain("i64.extend_i32_u")],
+    })
+}
+
+fn unslot(ty: &Type) -> Result<Vec<Instr>, CodegenError> {
+    Ok(match ty {
+        Type::Int => vec![],
+        Type::Void => vec![Instr::Plain("drop")],
+        Type::BigInt => return Err(CodegenError::Unsupported("bigints")),
+        Type::Float => vec![Instr::Plain("f64.reinterpret_i64")],
+        _ => vec![Instr::Plain("i32.wrap_i64")],
+    })
+}
+
+fn local_get(local: &str) -> Instr {
+    Instr::Local("local.get", String::from(local))
+}
+
+fn local_set(local: &str) -> Instr {
+    Instr::Local("local.set", String::from(local))
+}
+
+// a Pipes name as a wasm identifier. anything past ascii is spelled out
+fn wasm_name(name: &str) -> String {
+    let mut wasm = String::new();
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() || ch == '_' {
+            wasm.push(ch);
+        } else {
+            wasm += &format!("u{:x}_", ch as u32);
+        }
+    }
+    wasm
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::codegen::harness::{self, Run};
+    use std::process::Command;
+
+    fn lower_source(source: &str) -> Result<Wasm, CodegenError> {
+        let (program, structs) = harness::checked(source);
+        lower(&program, &structs)
+    }
+
+    // runs the binary module in node with HOST, which has to load it. None
+    // when node isn't installed
+    pub fn run_native(source: &str) -> Option<Run> {
+        if !harness::installed("node") {
+            return None;
+        }
+        let wasm = lower_source(source).unwrap().to_binary();
+        Some(harness::in_scratch_dir("wasm", |dir| {
+            std::fs::write(dir.join("out.wasm"), wasm).unwrap();
+            std::fs::write(dir.join("host.js"), HOST).unwrap();
+            harness::finished(
+                Command::new("node")
+                    .args(["host.js", "out.wasm"])
+                    .current_dir(dir)
+                    .output()
+                    .unwrap(),
+            )
+        }))
+    }
+
+    // ----------- lowering tests ---------- \\
+    #[test]
+    fn compile_functions() {
+        let wasm =
+            lower_source("func add(a: int, b: int) -> int { return a + b; } add(1, 2)").unwrap();
+        let wat = wasm.to_text();
+        assert!(wat.contains(
+            "  (func $add.1 (param $a.2 i64) (param $b.3 i64) (param $env i32) (result i64)\n    \
+             local.get $a.2\n    local.get $b.3\n    call $pipes.add\n    return\n  )"
+        ));
+        assert!(wat.contains(
+            "  (func $main\n    i64.const 1\n    i64.const 2\n    i32.const 0\n    call $add.1\n    drop\n  )"
+        ));
+        assert!(wat.contains("  (import \"host\" \"write\" (func $host.write (param i32)))\n"));
+        assert!(wat.contains("  (export \"main\" (func $main))\n"));
+        assert_eq!(&wasm.to_binary()[..4], b"\0asm");
+    }
+
+    #[test]
+    fn compile_data() {
+        let wasm = lower_source(
+            "struct Point { x: int, y: float, }
+            let origin: Point = {x: 0, y: -0.5,};
+            print(\"hi\");
+            print(\"hi\");",
+        )
+        .unwrap();
+        let wat = wasm.to_text();
+        assert!(wat.contains("  (global $origin.2 (mut i32) (i32.const 0))\n"));
+        assert!(wat.contains("    f64.const -0.5\n    f64.store offset=8\n"));
+        // strings are stored once, after their length
+        assert_eq!(wat.matches("\\02\\00\\00\\00hi").count(), 1);
+        assert_eq!(wasm.data_start, 8);
+        assert_eq!(wasm_name("héllo"), "hue9_llo");
+    }
+
+    #[test]
+    fn compile_unsupported() {
+        assert_eq!(
+            lower_source("let big: bigint = 99999999999999999999n;").err(),
+            Some(CodegenError::Unsupported("bigints"))
+        );
+        assert_eq!(
+            lower_source(
+                "func outer(start: int) -> int {
+                    func inner() -> int { return start; }
+                    return inner();
+                }"
+            )
+            .err(),
+            Some(CodegenError::Captures(String::from("start")))
+        );
+        assert_eq!(
+            lower_source("print((a) { return a; });").err(),
+            Some(CodegenError::UnknownType(String::from("a")))
+        );
+    }
+}
@@ -0,0 +1,86 @@
+// the host every module codegen::wasm compiles runs in. from node:
+//
+//     node host.js out.wasm
+//
+// which exits with 1 after printing the error when the program panics. in
+// a browser, load this as a script and call
+//
+//     pipes.run(bytes, (text) => { ... })
+//
+// which rejects with a pipes.PipesError instead
+"use strict";
+
+class PipesError extends Error {}
+
+// the shortest digits that read back as the same double, laid out like
+// Rust's {:?}: plain from 1e-4 up to 1e16, scientific outside of that
+function showFloat(x) {
+  if (Number.isNaN(x)) {
+    return "NaN";
+  }
+  if (!Number.isFinite(x)) {
+    return x > 0 ? "inf" : "-inf";
+  }
+  if (x === 0) {
+    return Object.is(x, -0) ? "-0.0" : "0.0";
+  }
+
+  // toExponential is [-]d.ddde[+-]x
+  const [mantissa, exponent] = x.toExponential().split("e");
+  const exp = Number(exponent);
+  const sign = x < 0 ? "-" : "";
+  const digits = mantissa.replace("-", "").replace(".", "");
+  if (exp < -4 || exp >= 16) {
+    const fraction = digits.length > 1 ? "." + digits.slice(1) : "";
+    return `${sign}${digits[0]}${fraction}e${exp}`;
+  }
+  if (exp < 0) {
+    return `${sign}0.${"0".repeat(-exp - 1)}${digits}`;
+  }
+  const whole = digits.slice(0, exp + 1).padEnd(exp + 1, "0");
+  return `${sign}${whole}.${digits.slice(exp + 1) || "0"}`;
+}
+
+// runs a compiled program, handing everything it prints to write
+async function run(bytes, write) {
+  let memory;
+  const decoder = new TextDecoder();
+  // strings are their length and then their bytes
+  const string = (pointer) => {
+    const len = new DataView(memory.buffer).getUint32(pointer, true);
+    return decoder.decode(new Uint8Array(memory.buffer, pointer + 4, len));
+  };
+  const host = {
+    write: (pointer) => write(string(pointer)),
+    show_int: (i) => write(i.toString()),
+    show_float: (x) => write(showFloat(x)),
+    fmod: (a, b) => a % b,
+    panic: (pointer) => {
+      throw new PipesError(string(pointer));
+    },
+  };
+  const { instance } = await WebAssembly.instantiate(bytes, { host });
+  memory = instance.exports.memory;
+  instance.exports.main();
+}
+
+if (typeof module !== "undefined") {
+  module.exports = { run, showFloat, PipesError };
+} else {
+  globalThis.pipes = { run, showFloat, PipesError };
+}
+
+if (typeof require !== "undefined" && require.main === module) {
+  const fs = require("fs");
+  // everything is written at the end, in one go
+  const output = [];
+  const flush = () => fs.writeSync(1, output.join(""));
+  run(fs.readFileSync(process.argv[2]), (text) => output.push(text)).then(flush, (error) => {
+    flush();
+    if (!(error instanceof PipesError)) {
+      throw error;
+    }
+    fs.writeSync(2, `error: ${error.message}\n`);
+    process.exitCode = 1;
+  });
+}
@@ -0,0 +1,752 @@
+// the module codegen::wasm builds, written out as text or binary. names
+// stay names until the binary is written, where they become indices
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ValType {
+    I32,
+    I64,
+    F64,
+}
+
+impl ValType {
+    fn code(self) -> u8 {
+        match self {
+            ValType::I32 => 0x7F,
+            ValType::I64 => 0x7E,
+            ValType::F64 => 0x7C,
+        }
+    }
+}
+
+impl fmt::Display for ValType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValType::I32 => write!(f, "i32"),
+            ValType::I64 => write!(f, "i64"),
+            ValType::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Signature {
+    pub params: Vec<ValType>,
+    pub result: Option<ValType>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Instr {
+    // anything without an immediate, like i64.add or drop
+    Plain(&'static str),
+    I32Const(i32),
+    I64Const(i64),
+    F64Const(f64),
+    // local.get, local.set or local.tee
+    Local(&'static str, String),
+    // global.get or global.set
+    Global(&'static str, String),
+    Call(String),
+    CallIndirect(Signature),
+    // block, loop or if, with its label and result
+    Block(&'static str, Option<String>, Option<ValType>),
+    Else,
+    End,
+    // br or br_if
+    Br(&'static str, String),
+    // a load or store and its offset
+    Memory(&'static str, u32),
+}
+
+pub struct Import {
+    pub module: &'static str,
+    pub field: &'static str,
+    pub name: String,
+    pub signature: Signature,
+}
+
+pub struct Func {
+    pub name: String,
+    pub params: Vec<(String, ValType)>,
+    pub result: Option<ValType>,
+    pub locals: Vec<(String, ValType)>,
+    pub body: Vec<Instr>,
+    // written above the function in the text format
+    pub comment: Option<String>,
+}
+
+impl Func {
+    pub fn signature(&self) -> Signature {
+        Signature {
+            params: self.params.iter().map(|(_, ty)| *ty).collect(),
+            result: self.result,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Wasm {
+    pub imports: Vec<Import>,
+    pub funcs: Vec<Func>,
+    // mutable globals, all starting at zero
+    pub globals: Vec<(String, ValType)>,
+    // the functions call_indirect can reach, by their index in the table
+    pub table: Vec<String>,
+    // the initial contents of memory from data_start
+    pub data: Vec<u8>,
+    pub data_start: u32,
+    // the global pointing at the first free byte after data
+    pub heap: String,
+    pub exports: Vec<String>,
+}
+
+// ----------- opcodes ---------- \\
+// everything the backend and its runtime use
+const OPCODES: &[(&str, u8)] = &[
+    ("unreachable", 0x00),
+    ("return", 0x0F),
+    ("drop", 0x1A),
+    ("i32.load", 0x28),
+    ("i64.load", 0x29),
+    ("f64.load", 0x2B),
+    ("i32.load8_u", 0x2D),
+    ("i32.store", 0x36),
+    ("i64.store", 0x37),
+    ("f64.store", 0x39),
+    ("i32.store8", 0x3A),
+    ("memory.size", 0x3F),
+    ("memory.grow", 0x40),
+    ("i32.eqz", 0x45),
+    ("i32.eq", 0x46),
+    ("i32.ne", 0x47),
+    ("i32.lt_u", 0x49),
+    ("i32.gt_u", 0x4B),
+    ("i32.le_u", 0x4D),
+    ("i32.ge_u", 0x4F),
+    ("i64.eqz", 0x50),
+    ("i64.eq", 0x51),
+    ("i64.ne", 0x52),
+    ("i64.lt_s", 0x53),
+    ("i64.gt_s", 0x55),
+    ("i64.le_s", 0x57),
+    ("i64.ge_s", 0x59),
+    ("f64.eq", 0x61),
+    ("f64.ne", 0x62),
+    ("f64.lt", 0x63),
+    ("f64.gt", 0x64),
+    ("f64.le", 0x65),
+    ("f64.ge", 0x66),
+    ("i32.add", 0x6A),
+    ("i32.sub", 0x6B),
+    ("i32.mul", 0x6C),
+    ("i32.and", 0x71),
+    ("i32.or", 0x72),
+    ("i32.shl", 0x74),
+    ("i32.shr_u", 0x76),
+    ("i64.add", 0x7C),
+    ("i64.sub", 0x7D),
+    ("i64.mul", 0x7E),
+    ("i64.div_s", 0x7F),
+    ("i64.rem_s", 0x81),
+    ("i64.and", 0x83),
+    ("i64.xor", 0x85),
+    ("f64.neg", 0x9A),
+    ("f64.add", 0xA0),
+    ("f64.sub", 0xA1),
+    ("f64.mul", 0xA2),
+    ("f64.div", 0xA3),
+    ("i32.wrap_i64", 0xA7),
+    ("i64.extend_i32_u", 0xAD),
+    ("f64.convert_i64_s", 0xB9),
+    ("i64.reinterpret_f64", 0xBD),
+    ("f64.reinterpret_i64", 0xBF),
+];
+
+fn opcode(name: &str) -> Option<(&'static str, u8)> {
+    OPCODES
+        .iter()
+        .find(|(op, _)| *op == name)
+        .map(|(op, code)| (*op, *code))
+}
+
+// log2 of the bytes a load or store moves
+fn alignment(op: &str) -> u32 {
+    if op.contains('8') && !op.starts_with("i64") && !op.starts_with("f64") {
+        0
+    } else if op.starts_with("i32") {
+        2
+    } else {
+        3
+    }
+}
+
+// ----------- assembler ---------- \\
+// instructions in the flat text format, for the runtime's functions:
+//
+//     local.get $a i64.const 1 i64.add
+//     block $end i32.load offset=4 br_if $end end
+pub fn asm(text: &str) -> Vec<Instr> {
+    let mut tokens = text.split_whitespace().peekable();
+    let mut instrs = vec![];
+    let name = |token: Option<&str>| String::from(token.unwrap().trim_start_matches('$'));
+    while let Some(token) = tokens.next() {
+        let instr = match token {
+            "i32.const" => Instr::I32Const(tokens.next().unwrap().parse().unwrap()),
+            "i64.const" => Instr::I64Const(tokens.next().unwrap().parse().unwrap()),
+            "f64.const" => Instr::F64Const(tokens.next().unwrap().parse().unwrap()),
+            "local.get" => Instr::Local("local.get", name(tokens.next())),
+            "local.set" => Instr::Local("local.set", name(tokens.next())),
+            "local.tee" => Instr::Local("local.tee", name(tokens.next())),
+            "global.get" => Instr::Global("global.get", name(tokens.next())),
+            "global.set" => Instr::Global("global.set", name(tokens.next())),
+            "call" => Instr::Call(name(tokens.next())),
+            "br" => Instr::Br("br", name(tokens.next())),
+            "br_if" => Instr::Br("br_if", name(tokens.next())),
+            "else" => Instr::Else,
+            "end" => Instr::End,
+            "block" | "loop" | "if" => {
+                let kind = match token {
+                    "block" => "block",
+                    "loop" => "loop",
+                    _ => "if",
+                };
+                let label = match tokens.peek() {
+                    Some(label) if label.starts_with('$') => name(tokens.next()),
+                    _ => String::new(),
+                };
+                Instr::Block(kind, Some(label).filter(|label| !label.is_empty()), None)
+            }
+            op => {
+                let (op, _) = opcode(op).unwrap_or_else(|| panic!("unknown instruction {}", op));
+                if op.contains(".load") || op.contains(".store") {
+                    let offset = match tokens.peek() {
+                        Some(offset) if offset.starts_with("offset=") => {
+                            tokens.next().unwrap()[7..].parse().unwrap()
+                        }
+                        _ => 0,
+                    };
+                    Instr::Memory(op, offset)
+                } else {
+                    Instr::Plain(op)
+                }
+            }
+        };
+        instrs.push(instr);
+    }
+    instrs
+}
+
+// ----------- text ---------- \\
+impl Wasm {
+    // every signature in the module, in the order the binary's type section
+    // lists them
+    fn signatures(&self) -> Vec<Signature> {
+        let mut signatures = vec![];
+        let mut add = |signature: Signature| {
+            if !signatures.contains(&signature) {
+                signatures.push(signature);
+            }
+        };
+        for import in &self.imports {
+            add(import.signature.clone());
+        }
+        for func in &self.funcs {
+            add(func.signature());
+            for instr in &func.body {
+                if let Instr::CallIndirect(signature) = instr {
+                    add(signature.clone());
+                }
+            }
+        }
+        signatures
+    }
+
+    pub fn to_text(&self) -> String {
+        let signatures = self.signatures();
+        let mut text = String::from("(module\n");
+        for (i, signature) in signatures.iter().enumerate() {
+            text += &format!("  (type $sig.{} (func{}))\n", i, signature_text(signature));
+        }
+        for import in &self.imports {
+            text += &format!(
+                "  (import \"{}\" \"{}\" (func ${}{}))\n",
+                import.module,
+                import.field,
+                import.name,
+                signature_text(&import.signature)
+            );
+        }
+        text += &format!("  (memory (export \"memory\") {})\n", self.pages());
+        for (name, ty) in &self.globals {
+            text += &format!("  (global ${} (mut {}) ({}.const 0))\n", name, ty, ty);
+        }
+        text += &format!(
+            "  (global ${} (mut i32) (i32.const {}))\n",
+            self.heap,
+            self.heap_start()
+        );
+        if !self.table.is_empty() {
+            text += &format!("  (table {} funcref)\n", self.table.len());
+            let names: Vec<String> = self.table.iter().map(|name| format!("${}", name)).collect();
+            text += &format!("  (elem (i32.const 0) func {})\n", names.join(" "));
+        }
+        if !self.data.is_empty() {
+            text += &format!(
+                "  (data (i32.const {}) \"{}\")\n",
+                self.data_start,
+                escape(&self.data)
+            );
+        }
+        for func in &self.funcs {
+            text += "\n";
+            if let Some(comment) = &func.comment {
+                text += &format!("  ;; {}\n", comment);
+            }
+            text += &format!("  (func ${}", func.name);
+            for (name, ty) in &func.params {
+                text += &format!(" (param ${} {})", name, ty);
+            }
+            if let Some(result) = func.result {
+                text += &format!(" (result {})", result);
+            }
+            text += "\n";
+            for (name, ty) in &func.locals {
+                text += &format!("    (local ${} {})\n", name, ty);
+            }
+            let mut depth = 2;
+            for instr in &func.body {
+                if let Instr::Else | Instr::End = instr {
+                    depth -= 1;
+                }
+                text += &"  ".repeat(depth);
+                text += &instr_text(instr, &signatures);
+                text += "\n";
+                if let Instr::Block(..) | Instr::Else = instr {
+                    depth += 1;
+                }
+            }
+            text += "  )\n";
+        }
+        for export in &self.exports {
+            text += &format!("  (export \"{}\" (func ${}))\n", export, export);
+        }
+        text += ")\n";
+        text
+    }
+
+    fn heap_start(&self) -> u32 {
+        (self.data_start + self.data.len() as u32 + 7) & !7
+    }
+
+    // enough to hold the data, the allocator grows memory past that
+    fn pages(&self) -> u32 {
+        self.heap_start() / 65536 + 1
+    }
+}
+
+fn signature_text(signature: &Signature) -> String {
+    let mut text = String::new();
+    if !signature.params.is_empty() {
+        let params: Vec<String> = signature.params.iter().map(|ty| ty.to_string()).collect();
+        text += &format!(" (param {})", params.join(" "));
+    }
+    if let Some(result) = signature.result {
+        text += &format!(" (result {})", result);
+    }
+    text
+}
+
+fn instr_text(instr: &Instr, signatures: &[Signature]) -> String {
+    match instr {
+        Instr::Plain(op) => String::from(*op),
+        Instr::I32Const(n) => format!("i32.const {}", n),
+        Instr::I64Const(n) => format!("i64.const {}", n),
+        Instr::F64Const(x) if x.is_nan() => String::from("f64.const nan"),
+        Instr::F64Const(x) if x.is_infinite() && *x > 0.0 => String::from("f64.const inf"),
+        Instr::F64Const(x) if x.is_infinite() => String::from("f64.const -inf"),
+        Instr::F64Const(x) => format!("f64.const {:?}", x),
+        Instr::Local(op, name) | Instr::Global(op, name) | Instr::Br(op, name) => {
+            format!("{} ${}", op, name)
+        }
+        Instr::Call(name) => format!("call ${}", name),
+        Instr::CallIndirect(signature) => {
+            let index = signatures.iter().position(|s| s == signature).unwrap();
+            format!("call_indirect (type $sig.{})", index)
+        }
+        Instr::Block(kind, label, result) => {
+            let mut text = String::from(*kind);
+            if let Some(label) = label {
+                text += &format!(" ${}", label);
+            }
+            if let Some(result) = result {
+                text += &format!(" (result {})", result);
+            }
+            text
+        }
+        Instr::Else => String::from("else"),
+        Instr::End => String::from("end"),
+        Instr::Memory(op, 0) => String::from(*op),
+        Instr::Memory(op, offset) => format!("{} offset={}", op, offset),
+    }
+}
+
+// the inside of a "..." string
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for &byte in bytes {
+        if byte.is_ascii_graphic() && byte != b'"' && byte != b'\\' || byte == b' ' {
+            escaped.push(byte as char);
+        } else {
+            escaped += &format!("\\{:02x}", byte);
+        }
+    }
+    escaped
+}
+
+// ----------- binary ---------- \\
+impl Wasm {
+    pub fn to_binary(&self) -> Vec<u8> {
+        let signatures = self.signatures();
+        let type_index =
+            |signature: &Signature| signatures.iter().position(|s| s == signature).unwrap() as u32;
+        let mut func_index = HashMap::new();
+        for import in &self.imports {
+            let index = func_index.len() as u32;
+            func_index.insert(import.name.as_str(), index);
+        }
+        for func in &self.funcs {
+            let index = func_index.len() as u32;
+            func_index.insert(func.name.as_str(), index);
+        }
+        let mut global_index = HashMap::new();
+        for (i, (name, _)) in self.globals.iter().enumerate() {
+            global_index.insert(name.as_str(), i as u32);
+        }
+        global_index.insert(self.heap.as_str(), self.globals.len() as u32);
+
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+
+        let mut types = vec![];
+        vector(&mut types, &signatures, |out, signature| {
+            out.push(0x60);
+            vector(out, &signature.params, |out, ty| out.push(ty.code()));
+            vector(
+                out,
+                &signature.result.into_iter().collect::<Vec<_>>(),
+                |out, ty| out.push(ty.code()),
+            );
+        });
+        section(&mut wasm, 1, &types);
+
+        let mut imports = vec![];
+        vector(&mut imports, &self.imports, |out, import| {
+            name(out, import.module);
+            name(out, import.field);
+            out.push(0x00);
+            unsigned(out, type_index(&import.signature));
+        });
+        section(&mut wasm, 2, &imports);
+
+        let mut functions = vec![];
+        vector(&mut functions, &self.funcs, |out, func| {
+            unsigned(out, type_index(&func.signature()))
+        });
+        section(&mut wasm, 3, &functions);
+
+        if !self.table.is_empty() {
+            let mut table = vec![];
+            unsigned(&mut table, 1);
+            table.extend([0x70, 0x00]);
+            unsigned(&mut table, self.table.len() as u32);
+            section(&mut wasm, 4, &table);
+        }
+
+        let mut memory = vec![];
+        unsigned(&mut memory, 1);
+        memory.push(0x00);
+        unsigned(&mut memory, self.pages());
+        section(&mut wasm, 5, &memory);
+
+        let mut globals = vec![];
+        let mut all_globals = self.globals.clone();
+        all_globals.push((self.heap.clone(), ValType::I32));
+        vector(&mut globals, &all_globals, |out, (global, ty)| {
+            out.extend([ty.code(), 0x01]);
+            match ty {
+                ValType::I32 if *global == self.heap => {
+                    out.push(0x41);
+                    signed(out, self.heap_start() as i64);
+                }
+                ValType::I32 => out.extend([0x41, 0x00]),
+                ValType::I64 => out.extend([0x42, 0x00]),
+                ValType::F64 => {
+                    out.push(0x44);
+                    out.extend(0f64.to_le_bytes());
+                }
+            }
+            out.push(0x0B);
+        });
+        section(&mut wasm, 6, &globals);
+
+        let mut exports = vec![];
+        unsigned(&mut exports, self.exports.len() as u32 + 1);
+        name(&mut exports, "memory");
+        exports.extend([0x02, 0x00]);
+        for export in &self.exports {
+            name(&mut exports, export);
+            exports.push(0x00);
+            unsigned(&mut exports, func_index[export.as_str()]);
+        }
+        section(&mut wasm, 7, &exports);
+
+        if !self.table.is_empty() {
+            let mut elements = vec![];
+            unsigned(&mut elements, 1);
+            elements.extend([0x00, 0x41, 0x00, 0x0B]);
+            vector(&mut elements, &self.table, |out, func| {
+                unsigned(out, func_index[func.as_str()])
+            });
+            section(&mut wasm, 9, &elements);
+        }
+
+        let mut code = vec![];
+        vector(&mut code, &self.funcs, |out, func| {
+            let mut body = vec![];
+            let mut local_index = HashMap::new();
+            for (name, _) in func.params.iter().chain(&func.locals) {
+                let index = local_index.len() as u32;
+                local_index.insert(name.as_str(), index);
+            }
+            vector(&mut body, &func.locals, |out, (_, ty)| {
+                unsigned(out, 1);
+                out.push(ty.code());
+            });
+            let mut labels: Vec<Option<&str>> = vec![];
+            for instr in &func.body {
+                encode(
+                    &mut body,
+                    instr,
+                    &mut labels,
+                    &local_index,
+                    &global_index,
+                    &func_index,
+                    &type_index,
+                );
+            }
+            body.push(0x0B);
+            unsigned(out, body.len() as u32);
+            out.extend(body);
+        });
+        section(&mut wasm, 10, &code);
+
+        if !self.data.is_empty() {
+            let mut data = vec![];
+            unsigned(&mut data, 1);
+            data.extend([0x00, 0x41]);
+            signed(&mut data, self.data_start as i64);
+            data.push(0x0B);
+            unsigned(&mut data, self.data.len() as u32);
+            data.extend(&self.data);
+            section(&mut wasm, 11, &data);
+        }
+        wasm
+    }
+}
+
+fn encode<'a>(
+    out: &mut Vec<u8>,
+    instr: &'a Instr,
+    labels: &mut Vec<Option<&'a str>>,
+    local_index: &HashMap<&str, u32>,
+    global_index: &HashMap<&str, u32>,
+    func_index: &HashMap<&str, u32>,
+    type_index: &dyn Fn(&Signature) -> u32,
+) {
+    match instr {
+        Instr::Plain(op) => {
+            out.push(opcode(op).unwrap().1);
+            if op.starts_with("memory.") {
+                out.push(0x00);
+            }
+        }
+        Instr::I32Const(n) => {
+            out.push(0x41);
+            signed(out, *n as i64);
+        }
+        Instr::I64Const(n) => {
+            out.push(0x42);
+            signed(out, *n);
+        }
+        Instr::F64Const(x) => {
+            out.push(0x44);
+            out.extend(x.to_le_bytes());
+        }
+        Instr::Local(op, name) => {
+            out.push(match *op {
+                "local.get" => 0x20,
+                "local.set" => 0x21,
+                _ => 0x22,
+            });
+            unsigned(out, local_index[name.as_str()]);
+        }
+        Instr::Global(op, name) => {
+            out.push(if *op == "global.get" { 0x23 } else { 0x24 });
+            unsigned(out, global_index[name.as_str()]);
+        }
+        Instr::Call(name) => {
+            out.push(0x10);
+            unsigned(out, func_index[name.as_str()]);
+        }
+        Instr::CallIndirect(signature) => {
+            out.push(0x11);
+            unsigned(out, type_index(signature));
+            out.push(0x00);
+        }
+        Instr::Block(kind, label, result) => {
+            out.push(match *kind {
+                "block" => 0x02,
+                "loop" => 0x03,
+                _ => 0x04,
+            });
+            out.push(result.map(ValType::code).unwrap_or(0x40));
+            labels.push(label.as_deref());
+        }
+        Instr::Else => out.push(0x05),
+        Instr::End => {
+            labels.pop();
+            out.push(0x0B);
+        }
+        // branches count the blocks they jump out of
+        Instr::Br(op, label) => {
+            out.push(if *op == "br" { 0x0C } else { 0x0D });
+            let depth = labels
+                .iter()
+                .rev()
+                .position(|l| *l == Some(label.as_str()))
+                .unwrap();
+            unsigned(out, depth as u32);
+        }
+        Instr::Memory(op, offset) => {
+            out.push(opcode(op).unwrap().1);
+            unsigned(out, alignment(op));
+            unsigned(out, *offset);
+        }
+    }
+}
+
+fn section(wasm: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    wasm.push(id);
+    unsigned(wasm, contents.len() as u32);
+    wasm.extend(contents);
+}
+
+fn vector<T>(out: &mut Vec<u8>, items: &[T], mut item: impl FnMut(&mut Vec<u8>, &T)) {
+    unsigned(out, items.len() as u32);
+    for i in items {
+        item(out, i);
+    }
+}
+
+fn name(out: &mut Vec<u8>, name: &str) {
+    unsigned(out, name.len() as u32);
+    out.extend(name.as_bytes());
+}
+
+// LEB128
+fn unsigned(out: &mut Vec<u8>, mut n: u32) {
+    loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn signed(out: &mut Vec<u8>, mut n: i64) {
+    loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        let done = (n == 0 && byte & 0x40 == 0) || (n == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn leb(write: impl Fn(&mut Vec<u8>)) -> Vec<u8> {
+        let mut out = vec![];
+        write(&mut out);
+        out
+    }
+
+    #[test]
+    fn leb128() {
+        assert_eq!(leb(|out| unsigned(out, 0)), [0x00]);
+        assert_eq!(leb(|out| unsigned(out, 624485)), [0xE5, 0x8E, 0x26]);
+        assert_eq!(leb(|out| signed(out, -1)), [0x7F]);
+        assert_eq!(leb(|out| signed(out, 63)), [0x3F]);
+        assert_eq!(leb(|out| signed(out, 64)), [0xC0, 0x00]);
+        assert_eq!(leb(|out| signed(out, -123456)), [0xC0, 0xBB, 0x78]);
+        assert_eq!(leb(|out| signed(out, i64::MIN)).len(), 10);
+    }
+
+    #[test]
+    fn assemble() {
+        assert_eq!(
+            asm("block $end local.get $a i64.load offset=8 br_if $end end"),
+            vec![
+                Instr::Block("block", Some(String::from("end")), None),
+                Instr::Local("local.get", String::from("a")),
+                Instr::Memory("i64.load", 8),
+                Instr::Br("br_if", String::from("end")),
+                Instr::End,
+            ]
+        );
+        assert_eq!(alignment("i32.load8_u"), 0);
+        assert_eq!(alignment("i32.store"), 2);
+        assert_eq!(alignment("f64.load"), 3);
+    }
+
+    #[test]
+    fn text_and_binary() {
+        let wasm = Wasm {
+            funcs: vec![Func {
+                name: String::from("main"),
+                params: vec![],
+                result: Some(ValType::I32),
+                locals: vec![(String::from("x"), ValType::I32)],
+                body: asm("block $out i32.const 1 local.set $x br $out end local.get $x"),
+                comment: Some(String::from("just main")),
+            }],
+            heap: String::from("heap"),
+            data: b"hi\"".to_vec(),
+            data_start: 8,
+            exports: vec![String::from("main")],
+            ..Wasm::default()
+        };
+        let text = wasm.to_text();
+        assert!(text.contains("  (data (i32.const 8) \"hi\\22\")\n"));
+        assert!(text.contains("  (global $heap (mut i32) (i32.const 16))\n"));
+        assert!(text.contains(
+            "  ;; just main\n  (func $main (result i32)\n    (local $x i32)\n    block $out\n      i32.const 1\n"
+        ));
+        assert!(text.contains("  (export \"main\" (func $main))\n"));
+
+        let binary = wasm.to_binary();
+        assert_eq!(&binary[..8], b"\0asm\x01\0\0\0");
+        // the type section holds the one () -> i32 signature
+        assert_eq!(&binary[8..14], [0x01, 0x05, 0x01, 0x60, 0x00, 0x01]);
+        // br $out from directly inside the block is a branch of depth 0
+        assert!(binary.windows(2).any(|pair| pair == [0x0C, 0x00]));
+    }
+}
@@ -0,0 +1,873 @@
+mod environment;
+mod error;
+mod value;
+
+pub use environment::Environment;
+pub use error::RuntimeError;
+pub use value::{Builtin, BuiltinValue, Closure, Value};
+
+use crate::ast::*;
+use num_bigint::BigInt;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+
+type Env = Rc<RefCell<Environment>>;
+
+// how deep Pipes calls can nest before we give up, the same as the vm
+const MAX_CALL_DEPTH: usize = 4096;
+
+// each call costs a few eval/exec frames, which would overflow a 2MB thread
+// stack in debug builds long before MAX_CALL_DEPTH. when a call starts with
+// less than STACK_RED_ZONE left, its body runs on a fresh STACK_SEGMENT
+const STACK_RED_ZONE: usize = 64 * 1024;
+const STACK_SEGMENT: usize = 1024 * 1024;
+
+// what running a statement did: carry on, or return out of the function
+enum Flow {
+    Normal,
+    Return(Value),
+}
+
+pub struct Interpreter<W: Write> {
+    globals: Env,
+    // the field names of each declared struct, in the order they're declared
+    structs: HashMap<String, Vec<String>>,
+    output: W,
+    depth: usize,
+}
+
+impl Interpreter<std::io::Stdout> {
+    pub fn new() -> Interpreter<std::io::Stdout> {
+        Interpreter::with_output(std::io::stdout())
+    }
+}
+
+impl Default for Interpreter<std::io::Stdout> {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+impl<W: Write> Interpreter<W> {
+    // print writes to output instead of stdout
+    pub fn with_output(output: W) -> Interpreter<W> {
+        let globals = Environment::new();
+        for builtin in Builtin::ALL.iter() {
+            globals
+                .borrow_mut()
+                .define(builtin.name(), Value::Builtin(*builtin));
+        }
+        Interpreter {
+            globals,
+            structs: HashMap::new(),
+            output,
+            depth: 0,
+        }
+    }
+
+    pub fn into_output(self) -> W {
+        self.output
+    }
+
+    // runs a whole program, returning the value of its last top level
+    // expression (or what a top level return gave back)
+    pub fn run(&mut self, program: &Program) -> Result<Value, RuntimeError> {
+        let env = Rc::clone(&self.globals);
+        // top level functions can be called before they're defined
+        for function in program.functions() {
+            define_function(function, &env);
+        }
+        for def in program.structs() {
+            let fields = def.fields.iter().map(|field| field.name.clone()).collect();
+            self.structs.insert(def.name.clone(), fields);
+        }
+
+        let mut last = Value::Void;
+        for item in &program.items {
+            last = Value::Void;
+            match item {
+                Item::Function(_) | Item::Struct(_) => {}
+                Item::Global(var, exp) => {
+                    let value = self.eval(exp, &env)?;
+                    env.borrow_mut().define(&var.name, value);
+                }
+                Item::Statement(Statement::Exp(exp, _)) => last = self.eval(exp, &env)?,
+                Item::Statement(stmt) => {
+                    if let Flow::Return(value) = self.exec(stmt, &env)? {
+                        return Ok(value);
+                    }
+                }
+            }
+        }
+        Ok(last)
+    }
+
+    // ----------- statements ---------- \\
+    fn exec(&mut self, stmt: &Statement, env: &Env) -> Result<Flow, RuntimeError> {
+        match stmt {
+            Statement::Assignment(var, exp) => {
+                let value = self.eval(exp, env)?;
+                // a typed assignment is a let, an untyped one updates a variable
+                if var.var_type.is_some() {
+                    env.borrow_mut().define(&var.name, value);
+                } else if !env.borrow_mut().set(&var.name, value) {
+                    return Err(RuntimeError::UndefinedVariable(var.name.clone()));
+                }
+            }
+            Statement::Function(function) => define_function(function, env),
+            Statement::Return(exp, _) => return Ok(Flow::Return(self.eval(exp, env)?)),
+            Statement::If(if_stmt) => {
+                if self.eval_bool(&if_stmt.condition, env)? {
+                    return self.exec_block(&if_stmt.true_branch, env);
+                } else if let Some(false_branch) = &if_stmt.false_branch {
+                    return self.exec_block(false_branch, env);
+                }
+            }
+            Statement::For(for_stmt) => {
+                let items = match self.eval(&for_stmt.iterable, env)? {
+                    Value::List(items) => items,
+                    other => return Err(RuntimeError::TypeMismatch("list", other.type_name())),
+                };
+                for item in items {
+                    let scope = Environment::child(env);
+                    scope.borrow_mut().define(&for_stmt.iter.name, item);
+                    if let Flow::Return(value) = self.exec_all(&for_stmt.block, &scope)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            Statement::While(while_stmt) => {
+                while self.eval_bool(&while_stmt.condition, env)? {
+                    if let Flow::Return(value) = self.exec_block(&while_stmt.block, env)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            Statement::Exp(exp, _) => {
+                self.eval(exp, env)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    // runs a block in its own scope
+    fn exec_block(&mut self, block: &[Statement], env: &Env) -> Result<Flow, RuntimeError> {
+        self.exec_all(block, &Environment::child(env))
+    }
+
+    fn exec_all(&mut self, block: &[Statement], env: &Env) -> Result<Flow, RuntimeError> {
+        for stmt in block {
+            if let Flow::Return(value) = self.exec(stmt, env)? {
+                return Ok(Flow::Return(value));
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    // ----------- expressions ---------- \\
+    fn eval(&mut self, exp: &Exp, env: &Env) -> Result<Value, RuntimeError> {
+        match exp {
+            Exp::Var(var) => lookup(&var.name, env),
+            Exp::Int(i) => Ok(Value::Int(*i)),
+            Exp::BigInt(i) => Ok(Value::BigInt(i.clone())),
+            Exp::Float(x) => Ok(Value::Float(*x)),
+            Exp::Str(s) => Ok(Value::Str(s.clone())),
+            Exp::Bool(b) => Ok(Value::Bool(*b)),
+            Exp::Void => Ok(Value::Void),
+
+            Exp::Call(callee, args) => {
+                let function = self.eval(callee, env)?;
+                let args = self.eval_list(args, env)?;
+                self.call(&callee.callee_name(), function, args)
+            }
+            Exp::Unary(UnaryOp::Neg, exp) => match self.eval(exp, env)? {
+                Value::Int(i) => i
+                    .checked_neg()
+                    .map(Value::Int)
+                    .ok_or(RuntimeError::IntegerOverflow),
+                Value::BigInt(i) => Ok(Value::BigInt(-i)),
+                Value::Float(x) => Ok(Value::Float(-x)),
+                other => Err(RuntimeError::TypeMismatch("number", other.type_name())),
+            },
+            Exp::Unary(UnaryOp::Not, exp) => match self.eval(exp, env)? {
+                Value::Bool(b) => Ok(Value::Bool(!b)),
+                other => Err(RuntimeError::TypeMismatch("bool", other.type_name())),
+            },
+            Exp::Op(Operation::And, left, right) => Ok(Value::Bool(
+                self.eval_bool(left, env)? && self.eval_bool(right, env)?,
+            )),
+            Exp::Op(Operation::Or, left, right) => Ok(Value::Bool(
+                self.eval_bool(left, env)? || self.eval_bool(right, env)?,
+            )),
+            Exp::Op(op, left, right) => {
+                let left = self.eval(left, env)?;
+                let right = self.eval(right, env)?;
+                eval_op(*op, left, right)
+            }
+            Exp::Pipe(left, right) => {
+                let left = self.eval(left, env)?;
+                match &**right {
+                    Exp::Call(callee, args) => {
+                        let function = self.eval(callee, env)?;
+                        let mut all_args = vec![left];
+                        all_args.extend(self.eval_list(args, env)?);
+                        self.call(&callee.callee_name(), function, all_args)
+                    }
+                    other => {
+                        let function = self.eval(other, env)?;
+                        self.call(&other.callee_name(), function, vec![left])
+                    }
+                }
+            }
+
+            Exp::Struct(name, fields) => {
+                let mut values = vec![];
+                for field in fields {
+                    let value = match &field.value {
+                        Some(exp) => self.eval(exp, env)?,
+                        None => Value::Void,
+                    };
+                    values.push((field.name.clone(), value));
+                }
+                // literals can list fields in any order, but a struct value
+                // keeps them in the order they're declared, like the backends
+                if let Some(declared) = self.structs.get(name) {
+                    values.sort_by_key(|(field, _)| declared.iter().position(|d| d == field));
+                }
+                Ok(Value::Struct(name.clone(), values))
+            }
+            Exp::Field(exp, field) => match self.eval(exp, env)? {
+                Value::Struct(name, fields) => fields
+                    .into_iter()
+                    .find(|(field_name, _)| field_name == field)
+                    .map(|(_, value)| value)
+                    .ok_or_else(|| RuntimeError::UnknownField(name, field.clone())),
+                other => Err(RuntimeError::TypeMismatch("struct", other.type_name())),
+            },
+            Exp::List(_, items) => Ok(Value::List(self.eval_list(items, env)?)),
+            Exp::HOF(params, block) => Ok(Value::Function(Rc::new(Closure {
+                name: String::from("<lambda>"),
+                params: params.iter().map(|p| p.name.clone()).collect(),
+                block: block.clone(),
+                env: Rc::clone(env),
+            }))),
+        }
+    }
+
+    fn eval_bool(&mut self, exp: &Exp, env: &Env) -> Result<bool, RuntimeError> {
+        match self.eval(exp, env)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(RuntimeError::TypeMismatch("bool", other.type_name())),
+        }
+    }
+
+    fn eval_list(&mut self, items: &[Exp], env: &Env) -> Result<Vec<Value>, RuntimeError> {
+        items.iter().map(|item| self.eval(item, env)).collect()
+    }
+
+    fn call(
+        &mut self,
+        name: &str,
+        function: Value,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let closure = match function {
+            Value::Function(closure) => closure,
+            Value::Builtin(builtin) => return builtin.call(args, &mut self.output),
+            _ => return Err(RuntimeError::NotCallable(String::from(name))),
+        };
+        if closure.params.len() != args.len() {
+            return Err(RuntimeError::WrongArgCount {
+                name: closure.name.clone(),
+                expected: closure.params.len(),
+                found: args.len(),
+            });
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow);
+        }
+
+        let scope = Environment::child(&closure.env);
+        for (param, arg) in closure.params.iter().zip(args) {
+            scope.borrow_mut().define(param, arg);
+        }
+
+        self.depth += 1;
+        let flow = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+            self.exec_all(&closure.block, &scope)
+        });
+        self.depth -= 1;
+
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            Flow::Normal => Ok(Value::Void),
+        }
+    }
+}
+
+fn define_function(function: &Function, env: &Env) {
+    let closure = Closure {
+        name: function.name.clone(),
+        params: function.param.iter().map(|p| p.name.clone()).collect(),
+        block: function.block.clone(),
+        env: Rc::clone(env),
+    };
+    env.borrow_mut()
+        .define(&function.name, Value::Function(Rc::new(closure)));
+}
+
+fn lookup(name: &str, env: &Env) -> Result<Value, RuntimeError> {
+    env.borrow()
+        .get(name)
+        .ok_or_else(|| RuntimeError::UndefinedVariable(String::from(name)))
+}
+
+// ----------- operations ---------- \\
+// both sides of a numeric operation, promoted to a common type the same way
+// Operation::result_type does
+enum Numbers {
+    Int(i64, i64),
+    BigInt(BigInt, BigInt),
+    Float(f64, f64),
+}
+
+fn numbers(left: &Value, right: &Value) -> Option<Numbers> {
+    match (left, right) {
+        (Value::Int(a), Value::Int(b)) => Some(Numbers::Int(*a, *b)),
+        (Value::BigInt(a), Value::BigInt(b)) => Some(Numbers::BigInt(a.clone(), b.clone())),
+        (Value::Int(a), Value::BigInt(b)) => Some(Numbers::BigInt(BigInt::from(*a), b.clone())),
+        (Value::BigInt(a), Value::Int(b)) => Some(Numbers::BigInt(a.clone(), BigInt::from(*b))),
+        (Value::Float(a), Value::Float(b)) => Some(Numbers::Float(*a, *b)),
+        (Value::Int(a), Value::Float(b)) => Some(Numbers::Float(*a as f64, *b)),
+        (Value::Float(a), Value::Int(b)) => Some(Numbers::Float(*a, *b as f64)),
+        _ => None,
+    }
+}
+
+pub(crate) fn eval_op(op: Operation, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    use Operation::*;
+
+    let nums = numbers(&left, &right);
+    match op {
+        Equal | NotEqual => {
+            let equal = match nums {
+                Some(nums) => compare(nums) == Some(Ordering::Equal),
+                None => left == right,
+            };
+            Ok(Value::Bool(equal == (op == Equal)))
+        }
+        GreaterThan | LessThan | GreaterEqual | LessEqual => {
+            let nums = nums.ok_or_else(|| mismatch(&left, &right))?;
+            let result = match compare(nums) {
+                Some(ordering) => match op {
+                    GreaterThan => ordering == Ordering::Greater,
+                    LessThan => ordering == Ordering::Less,
+                    GreaterEqual => ordering != Ordering::Less,
+                    _ => ordering != Ordering::Greater,
+                },
+                // NaN compares false with everything
+                None => false,
+            };
+            Ok(Value::Bool(result))
+        }
+        Add | Subtract | Multiply | Divide | Modulo => {
+            match nums.ok_or_else(|| mismatch(&left, &right))? {
+                Numbers::Int(a, b) => int_op(op, a, b),
+                Numbers::BigInt(a, b) => bigint_op(op, a, b),
+                Numbers::Float(a, b) => Ok(Value::Float(match op {
+                    Add => a + b,
+                    Subtract => a - b,
+                    Multiply => a * b,
+                    Divide => a / b,
+                    _ => a % b,
+                })),
+            }
+        }
+        And | Or => Err(mismatch(&left, &right)),
+    }
+}
+
+pub(crate) fn int_op(op: Operation, a: i64, b: i64) -> Result<Value, RuntimeError> {
+    if b == 0 && (op == Operation::Divide || op == Operation::Modulo) {
+        return Err(RuntimeError::DivideByZero);
+    }
+    let result = match op {
+        Operation::Add => a.checked_add(b),
+        Operation::Subtract => a.checked_sub(b),
+        Operation::Multiply => a.checked_mul(b),
+        Operation::Divide => a.checked_div(b),
+        _ => a.checked_rem(b),
+    };
+    result.map(Value::Int).ok_or(RuntimeError::IntegerOverflow)
+}
+
+fn bigint_op(op: Operation, a: BigInt, b: BigInt) -> Result<Value, RuntimeError> {
+    if b == BigInt::from(0) && (op == Operation::Divide || op == Operation::Modulo) {
+        return Err(RuntimeError::DivideByZero);
+    }
+    Ok(Value::BigInt(match op {
+        Operation::Add => a + b,
+        Operation::Subtract => a - b,
+        Operation::Multiply => a * b,
+        Operation::Divide => a / b,
+        _ => a % b,
+    }))
+}
+
+fn compare(nums: Numbers) -> Option<Ordering> {
+    match nums {
+        Numbers::Int(a, b) => Some(a.cmp(&b)),
+        Numbers::BigInt(a, b) => Some(a.cmp(&b)),
+        Numbers::Float(a, b) => a.partial_cmp(&b),
+    }
+}
+
+fn mismatch(left: &Value, right: &Value) -> RuntimeError {
+    let found = match left {
+        Value::Int(_) | Value::BigInt(_) | Value::Float(_) => right.type_name(),
+        _ => left.type_name(),
+    };
+    RuntimeError::TypeMismatch("number", found)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::tokenizer::tokenizer::tokenize;
+
+    fn run(source: &str) -> Result<Value, RuntimeError> {
+        let ast = parse(tokenize(source).unwrap()).unwrap();
+        Interpreter::with_output(vec![]).run(&ast)
+    }
+
+    fn run_output(source: &str) -> String {
+        let ast = parse(tokenize(source).unwrap()).unwrap();
+        let mut interpreter = Interpreter::with_output(vec![]);
+        interpreter.run(&ast).unwrap();
+        String::from_utf8(interpreter.into_output()).unwrap()
+    }
+
+    // ----------- expression tests ---------- \\
+    #[test]
+    fn run_nothing() {
+        assert_eq!(run(""), Ok(Value::Void))
+    }
+
+    #[test]
+    fn run_arithmetic() {
+        assert_eq!(run("2 * 3 + 8 / 2 - 7 % 4"), Ok(Value::Int(7)))
+    }
+
+    #[test]
+    fn run_mixed_arithmetic() {
+        assert_eq!(run("1 + 0.5"), Ok(Value::Float(1.5)));
+        assert_eq!(
+            run("10000000000000000000n * 10 + 1"),
+            Ok(Value::BigInt("100000000000000000001".parse().unwrap()))
+        )
+    }
+
+    #[test]
+    fn run_comparisons() {
+        assert_eq!(
+            run("1 < 2 && 2 <= 2 && 3 > 2.5 && 1 == 1.0 && 1 != 2"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(run("\"a\" == \"a\""), Ok(Value::Bool(true)))
+    }
+
+    #[test]
+    fn run_negate() {
+        assert_eq!(run("let x: int = 4; -x"), Ok(Value::Int(-4)));
+        assert_eq!(run("!(1 > 2)"), Ok(Value::Bool(true)));
+        assert_eq!(run("let a: int = 4; a-1"), Ok(Value::Int(3)));
+        assert_eq!(run("let a: int = 4; -a-1"), Ok(Value::Int(-5)));
+        assert_eq!(run("let a: int = 4; a - -a"), Ok(Value::Int(8)))
+    }
+
+    #[test]
+    fn run_unary_type_mismatch() {
+        // neg and not no longer stand in for each other
+        assert_eq!(
+            run("-true"),
+            Err(RuntimeError::TypeMismatch("number", "bool"))
+        );
+        assert_eq!(run("!1"), Err(RuntimeError::TypeMismatch("bool", "int")))
+    }
+
+    #[test]
+    fn run_short_circuit() {
+        // the right side would be an undefined variable error
+        assert_eq!(run("false && nope"), Ok(Value::Bool(false)));
+        assert_eq!(run("true || nope"), Ok(Value::Bool(true)))
+    }
+
+    #[test]
+    fn run_divide_by_zero() {
+        assert_eq!(run("1 / 0"), Err(RuntimeError::DivideByZero));
+        assert_eq!(run("1n % 0"), Err(RuntimeError::DivideByZero));
+        assert_eq!(run("1.0 / 0"), Ok(Value::Float(f64::INFINITY)))
+    }
+
+    #[test]
+    fn run_overflow() {
+        assert_eq!(
+            run("9223372036854775807 + 1"),
+            Err(RuntimeError::IntegerOverflow)
+        )
+    }
+
+    #[test]
+    fn run_type_mismatch() {
+        assert_eq!(
+            run("1 + true"),
+            Err(RuntimeError::TypeMismatch("number", "bool"))
+        );
+        assert_eq!(
+            run("if (1) { }"),
+            Err(RuntimeError::TypeMismatch("bool", "int"))
+        )
+    }
+
+    #[test]
+    fn run_list_and_struct() {
+        assert_eq!(
+            run("let x: [int] = [1, 2 + 1]; x"),
+            Ok(Value::List(vec![Value::Int(1), Value::Int(3)]))
+        );
+        assert_eq!(
+            run("let p: Point = {x: 1, y: 2.5,}; p"),
+            Ok(Value::Struct(
+                String::from("Point"),
+                vec![
+                    (String::from("x"), Value::Int(1)),
+                    (String::from("y"), Value::Float(2.5)),
+                ]
+            ))
+        )
+    }
+
+    #[test]
+    fn run_out_of_order_struct_literals() {
+        let program = "struct P { x: int, y: int, }
+            let a: P = P{y: 2, x: 1,};
+            let b: P = P{x: 1, y: 2,};";
+        assert_eq!(run(&format!("{} a == b", program)), Ok(Value::Bool(true)));
+        assert_eq!(
+            run_output(&format!("{} print(a);", program)),
+            "P { x: 1, y: 2 }\n"
+        )
+    }
+
+    #[test]
+    fn run_field_access() {
+        let program = "struct Point { x: int, y: float, }
+            let p: Point = {x: 1, y: 2.5,};
+            let line: [Point] = [p];";
+        assert_eq!(
+            run(&format!("{} p.y + p.x", program)),
+            Ok(Value::Float(3.5))
+        );
+        assert_eq!(
+            run(&format!("{} p.z", program)),
+            Err(RuntimeError::UnknownField(
+                String::from("Point"),
+                String::from("z")
+            ))
+        );
+        assert_eq!(
+            run(&format!("{} line.x", program)),
+            Err(RuntimeError::TypeMismatch("struct", "list"))
+        )
+    }
+
+    #[test]
+    fn run_undefined_variable() {
+        assert_eq!(
+            run("x"),
+            Err(RuntimeError::UndefinedVariable(String::from("x")))
+        );
+        assert_eq!(
+            run("x = 1;"),
+            Err(RuntimeError::UndefinedVariable(String::from("x")))
+        )
+    }
+
+    // ----------- statement tests ---------- \\
+    #[test]
+    fn run_function() {
+        assert_eq!(
+            run("func bad_adder(a: int, b: int,) -> int {
+                    let x: int = a;
+                    let y: int = b;
+                    let result: int = x + y;
+                    return result;
+                }
+                let a: int = 2;
+                let b: int = 3;
+                bad_adder(a, b)"),
+            Ok(Value::Int(5))
+        )
+    }
+
+    #[test]
+    fn run_recursion() {
+        assert_eq!(
+            run("func fib(n: int) -> int {
+                    if (n < 2) { return n; }
+                    let a: int = n - 1;
+                    let b: int = n - 2;
+                    return fib(a) + fib(b);
+                }
+                let n: int = 15;
+                fib(n)"),
+            Ok(Value::Int(610))
+        )
+    }
+
+    #[test]
+    fn run_functions_before_definition() {
+        assert_eq!(
+            run("let n: int = 7;
+                let result: bool = is_even(n);
+                func is_even(n: int) -> bool {
+                    if (n == 0) { return true; }
+                    let m: int = n - 1;
+                    return is_odd(m);
+                }
+                func is_odd(n: int) -> bool {
+                    if (n == 0) { return false; }
+                    let m: int = n - 1;
+                    return is_even(m);
+                }
+                result"),
+            Ok(Value::Bool(false))
+        )
+    }
+
+    #[test]
+    fn run_deep_recursion() {
+        assert_eq!(
+            run("func sum(n: int) -> int { if (n == 0) { return 0; } return n + sum(n - 1); } let n: int = 4000; sum(n)"),
+            Ok(Value::Int(8002000))
+        )
+    }
+
+    #[test]
+    fn run_runaway_recursion() {
+        assert_eq!(
+            run("func forever(n: int) -> int { return forever(n); } let n: int = 1; forever(n)"),
+            Err(RuntimeError::StackOverflow)
+        )
+    }
+
+    #[test]
+    fn run_wrong_arg_count() {
+        assert_eq!(
+            run("func one(a: int) -> int { return a; } let a: int = 1; one(a, a)"),
+            Err(RuntimeError::WrongArgCount {
+                name: String::from("one"),
+                expected: 1,
+                found: 2,
+            })
+        )
+    }
+
+    #[test]
+    fn run_not_callable() {
+        assert_eq!(
+            run("let x: int = 1; x(x)"),
+            Err(RuntimeError::NotCallable(String::from("x")))
+        )
+    }
+
+    #[test]
+    fn run_while() {
+        assert_eq!(
+            run("let x: int = 0; let total: int = 0;
+                while (x < 5) { total = total + x; x = x + 1; }
+                total"),
+            Ok(Value::Int(10))
+        )
+    }
+
+    #[test]
+    fn run_for() {
+        assert_eq!(
+            run("let total: int = 0; for x in [1, 2, 3] { total = total + x; } total"),
+            Ok(Value::Int(6))
+        )
+    }
+
+    #[test]
+    fn run_for_over_expressions() {
+        assert_eq!(
+            run("func evens(n: int) -> [int] { return [0, 2, 4]; }
+                let rows: [[int]] = [[1, 2], [3]];
+                let total: int = 0;
+                for row in rows { for x in row { total = total + x; } }
+                for e in evens(3) { total = total + e; }
+                total"),
+            Ok(Value::Int(12))
+        );
+        assert_eq!(
+            run("let n: int = 3; for x in n { }"),
+            Err(RuntimeError::TypeMismatch("list", "int"))
+        )
+    }
+
+    #[test]
+    fn run_if_else() {
+        let program = "func sign(x: int) -> int {
+                if (x < 0) { return -1; } else if (x == 0) { return 0; } else { return 1; }
+            }
+            let a: int = -5; let b: int = 0; let c: int = 9;
+            [sign(a), sign(b), sign(c)]";
+        assert_eq!(
+            run(program),
+            Ok(Value::List(vec![
+                Value::Int(-1),
+                Value::Int(0),
+                Value::Int(1)
+            ]))
+        )
+    }
+
+    #[test]
+    fn run_elif() {
+        let program = "func grade(score: int) -> str {
+                if (score >= 90) { return \"A\"; }
+                elif (score >= 80) { return \"B\"; }
+                elif (score >= 70) { return \"C\"; }
+                else { return \"F\"; }
+            }
+            let a: int = 95; let b: int = 85; let c: int = 70; let f: int = 3;
+            [grade(a), grade(b), grade(c), grade(f)]";
+        let grades = ["A", "B", "C", "F"]
+            .iter()
+            .map(|g| Value::Str(String::from(*g)))
+            .collect();
+        assert_eq!(run(program), Ok(Value::List(grades)))
+    }
+
+    #[test]
+    fn run_block_scopes() {
+        assert_eq!(
+            run("let x: int = 1; if (true) { let x: int = 2; x = 3; } x"),
+            Ok(Value::Int(1))
+        );
+        assert_eq!(
+            run("let x: int = 1; if (true) { x = 3; } x"),
+            Ok(Value::Int(3))
+        )
+    }
+
+    #[test]
+    fn run_return_from_loop() {
+        assert_eq!(
+            run("func first_big(xs: [int]) -> int {
+                    for x in [1, 20, 300] { if (x > 10) { return x; } }
+                    return 0;
+                }
+                let xs: [int] = [];
+                first_big(xs)"),
+            Ok(Value::Int(20))
+        )
+    }
+
+    #[test]
+    fn run_top_level_return() {
+        assert_eq!(run("return 1; 2"), Ok(Value::Int(1)))
+    }
+
+    // ----------- closure tests ---------- \\
+    #[test]
+    fn run_higher_order_function() {
+        assert_eq!(
+            run("let x: (int -> int) = (a) { return 1 + a; }; let y: int = 41; x(y)"),
+            Ok(Value::Int(42))
+        )
+    }
+
+    #[test]
+    fn run_closure_captures_scope() {
+        assert_eq!(
+            run("func make_adder(n: int) -> (int -> int) {
+                    return (a: int) { return a + n; };
+                }
+                let ten: int = 10;
+                let add_ten: (int -> int) = make_adder(ten);
+                let five: int = 5;
+                add_ten(five)"),
+            Ok(Value::Int(15))
+        )
+    }
+
+    #[test]
+    fn run_functions_as_values() {
+        assert_eq!(
+            run(
+                "func apply(f: (int, int) -> int, a: int, b: int) -> int { return f(a, b); }
+                func bad_adder(a: int, b: int,) -> int { return a + b; }
+                let times: (int, int) -> int = (a, b) { return a * b; };
+                let x: int = 6;
+                let y: int = 7;
+                [apply(bad_adder, x, y), apply(times, x, y)]"
+            ),
+            Ok(Value::List(vec![Value::Int(13), Value::Int(42)]))
+        )
+    }
+
+    #[test]
+    fn run_expression_args_and_callees() {
+        assert_eq!(
+            run("func add(a: int, b: int) -> int { return a + b; }
+                func make_adder(n: int) -> (int) -> int {
+                    return (a: int) { return a + n; };
+                }
+                struct Ops { double: (int) -> int, }
+                let ops: Ops = {double: (a) { return a * 2; },};
+                [add(1 + 2, add(3, 4)), make_adder(10)(5), ops.double(21), (a) { return -a; }(1)]"),
+            Ok(Value::List(vec![
+                Value::Int(10),
+                Value::Int(15),
+                Value::Int(42),
+                Value::Int(-1)
+            ]))
+        );
+        assert_eq!(
+            run("1(2)"),
+            Err(RuntimeError::NotCallable(String::from("expression")))
+        )
+    }
+
+    #[test]
+    fn run_pipe() {
+        assert_eq!(
+            run("func add(a: int, b: int) -> int { return a + b; }
+                let double: (int -> int) = (a) { return a * 2; };
+                let one: int = 1;
+                3 |> double |> add(one) |> (x) { return x * 10; }"),
+            Ok(Value::Int(70))
+        )
+    }
+
+    // ----------- builtin tests ---------- \\
+    #[test]
+    fn run_print() {
+        assert_eq!(
+            run_output("let x: [float] = [1.0, 2.5]; print(x); let s: str = \"hi\"; print(s);"),
+            "[1.0, 2.5]\nhi\n"
+        )
+    }
+
+    #[test]
+    fn run_len() {
+        assert_eq!(run("let s: str = \"héllo\"; len(s)"), Ok(Value::Int(5)));
+        assert_eq!(run("let l: [int] = [1, 2]; l |> len"), Ok(Value::Int(2)))
+    }
+}
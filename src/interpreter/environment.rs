@@ -0,0 +1,50 @@
+use super::value::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// one scope of variables, chained to the scope it was opened in. closures
+// keep a handle on the scope they were made in
+#[derive(Debug, Default)]
+pub struct Environment {
+    vars: HashMap<String, Value>,
+    parent: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment::default()))
+    }
+
+    pub fn child(parent: &Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment {
+            vars: HashMap::new(),
+            parent: Some(Rc::clone(parent)),
+        }))
+    }
+
+    // let: always creates the variable in this scope, shadowing any outer one
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.vars.insert(String::from(name), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.vars.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.borrow().get(name),
+        }
+    }
+
+    // x = ...: updates the nearest scope that has the variable, returning
+    // false if none do
+    pub fn set(&mut self, name: &str, value: Value) -> bool {
+        if let Some(slot) = self.vars.get_mut(name) {
+            *slot = value;
+            return true;
+        }
+        match &self.parent {
+            Some(parent) => parent.borrow_mut().set(name, value),
+            None => false,
+        }
+    }
+}
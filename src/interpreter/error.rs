@@ -0,0 +1,46 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeError {
+    UndefinedVariable(String),
+    NotCallable(String),
+    WrongArgCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    // what was expected and the type of value found instead
+    TypeMismatch(&'static str, &'static str),
+    // the struct and the field
+    UnknownField(String, String),
+    DivideByZero,
+    IntegerOverflow,
+    StackOverflow,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::UndefinedVariable(name) => write!(f, "undefined variable {}", name),
+            RuntimeError::NotCallable(name) => write!(f, "{} is not a function", name),
+            RuntimeError::WrongArgCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} takes {} argument(s) but was given {}",
+                name, expected, found
+            ),
+            RuntimeError::TypeMismatch(expected, found) => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            RuntimeError::UnknownField(name, field) => {
+                write!(f, "{} has no field named {}", name, field)
+            }
+            RuntimeError::DivideByZero => write!(f, "divide by zero"),
+            RuntimeError::IntegerOverflow => write!(f, "integer overflow"),
+            RuntimeError::StackOverflow => write!(f, "too many nested function calls"),
+        }
+    }
+}
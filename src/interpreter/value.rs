@@ -0,0 +1,187 @@
+use super::environment::Environment;
+use super::error::RuntimeError;
+use crate::ast::Statement;
+use num_bigint::BigInt;
+use std::cell::RefCell;
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Void,
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    List(Vec<Value>),
+    Struct(String, Vec<(String, Value)>),
+    Function(Rc<Closure>),
+    Builtin(Builtin),
+}
+
+// a named function or a HOF along with the scope it was created in
+pub struct Closure {
+    pub name: String,
+    pub params: Vec<String>,
+    pub block: Vec<Statement>,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+// the scope is left out, it can contain the closure itself
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Builtin {
+    Print, // print(x)
+    Len,   // len(list) or len(str)
+}
+
+impl Builtin {
+    pub const ALL: [Builtin; 2] = [Builtin::Print, Builtin::Len];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Print => "print",
+            Builtin::Len => "len",
+        }
+    }
+
+    // the interpreter and the vm both call builtins through here, each with
+    // its own values
+    pub fn call<V: BuiltinValue>(
+        self,
+        args: Vec<V>,
+        output: &mut impl Write,
+    ) -> Result<V, RuntimeError> {
+        if args.len() != 1 {
+            return Err(RuntimeError::WrongArgCount {
+                name: String::from(self.name()),
+                expected: 1,
+                found: args.len(),
+            });
+        }
+        match (self, &args[0]) {
+            (Builtin::Print, value) => {
+                // a closed stdout shouldn't take the program down with it
+                writeln!(output, "{}", value).ok();
+                Ok(V::void())
+            }
+            (Builtin::Len, value) => match value.length() {
+                Some(len) => Ok(V::int(len as i64)),
+                None => Err(RuntimeError::TypeMismatch("list or str", value.type_name())),
+            },
+        }
+    }
+}
+
+// what the builtins need from a value
+pub trait BuiltinValue: fmt::Display {
+    fn void() -> Self;
+    fn int(i: i64) -> Self;
+    // the items in a list or the chars in a str
+    fn length(&self) -> Option<usize>;
+    fn type_name(&self) -> &'static str;
+}
+
+impl BuiltinValue for Value {
+    fn void() -> Value {
+        Value::Void
+    }
+
+    fn int(i: i64) -> Value {
+        Value::Int(i)
+    }
+
+    fn length(&self) -> Option<usize> {
+        match self {
+            Value::List(items) => Some(items.len()),
+            Value::Str(s) => Some(s.chars().count()),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        Value::type_name(self)
+    }
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Void => "void",
+            Value::Int(_) => "int",
+            Value::BigInt(_) => "bigint",
+            Value::Float(_) => "float",
+            Value::Str(_) => "str",
+            Value::Bool(_) => "bool",
+            Value::List(_) => "list",
+            Value::Struct(..) => "struct",
+            Value::Function(_) | Value::Builtin(_) => "function",
+        }
+    }
+}
+
+// functions are only equal to themselves
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Void, Value::Void) => true,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::BigInt(a), Value::BigInt(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Struct(a, a_fields), Value::Struct(b, b_fields)) => {
+                a == b && a_fields == b_fields
+            }
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Builtin(a), Value::Builtin(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Void => write!(f, "()"),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::BigInt(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Struct(name, fields) => {
+                write!(f, "{} {{", name)?;
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {}: {}", field, value)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Function(closure) => write!(f, "<func {}>", closure.name),
+            Value::Builtin(builtin) => write!(f, "<func {}>", builtin.name()),
+        }
+    }
+}
@@ -1,4 +1,9 @@
-mod parser;
-mod tokenizer;
-mod ast;
-mod types;
+pub mod ast;
+pub mod bytecode;
+pub mod codegen;
+pub mod interpreter;
+pub mod parser;
+pub mod span;
+pub mod tokenizer;
+pub mod typeck;
+pub mod types;
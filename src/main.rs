@@ -1,3 +1,326 @@
+use pipes::ast::Program;
+use pipes::bytecode::{self, Vm};
+use pipes::codegen;
+use pipes::interpreter::Interpreter;
+use pipes::parser::parse_spanned;
+use pipes::tokenizer::tokenizer::{tokenize_spanned, Comments};
+use pipes::typeck::check;
+use pipes::types::StructRegistry;
+use std::env;
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::process;
+
+const USAGE: &str = "usage: pipes <command> [options] [file]
+
+commands:
+    run [--interpret] [file]     run a program, on the ast interpreter with --interpret
+    check [file]                 type check a program
+    tokens [file]                print a program's tokens and where they start
+    ast [file]                   print a program's parse tree
+    build --target <target> [-o out] [file]
+                                 compile a program to out, or stdout
+    build --target <target> --runtime [-o out]
+                                 write what a target's output needs to run
+
+targets:
+    c       a standalone C99 file
+    llvm    LLVM IR, which links against the C runtime
+    wasm    a WebAssembly binary, which runs in the node or browser host
+    wat     the same module as WebAssembly text
+
+with no file, or -, the program is read from stdin. exits with 1 when the
+program has an error and with 2 when the command line does";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Target {
+    C,
+    Llvm,
+    Wasm,
+    Wat,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help,
+    Run {
+        interpret: bool,
+    },
+    Check,
+    Tokens,
+    Ast,
+    Build {
+        target: Target,
+        output: Option<String>,
+        runtime: bool,
+    },
+}
+
+// a parsed command line. no input means stdin
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: Command,
+    input: Option<String>,
+}
+
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("pipes: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(message) = drive(&args) {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+// ----------- command line ---------- \\
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(String::from("no command given")),
+    };
+    let mut interpret = false;
+    let mut target = None;
+    let mut output = None;
+    let mut runtime = false;
+    let mut input = None;
+
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--interpret" if command == "run" => interpret = true,
+            "--runtime" if command == "build" => runtime = true,
+            "--target" | "-o" if command == "build" => {
+                let value = rest
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", arg))?;
+                if arg == "-o" {
+                    output = Some(value.clone());
+                } else {
+                    target = Some(parse_target(value)?);
+                }
+            }
+            "-" if input.is_none() => input = Some(None),
+            flag if flag.starts_with('-') => {
+                return Err(format!("unknown option {} for {}", flag, command))
+            }
+            file if input.is_none() => input = Some(Some(String::from(file))),
+            file => return Err(format!("more than one file given: {}", file)),
+        }
+    }
+
+    let command = match command {
+        "help" | "-h" | "--help" => Command::Help,
+        "run" => Command::Run { interpret },
+        "check" => Command::Check,
+        "tokens" => Command::Tokens,
+        "ast" => Command::Ast,
+        "build" => Command::Build {
+            target: target.ok_or("build needs a --target")?,
+            output,
+            runtime,
+        },
+        other => return Err(format!("unknown command {}", other)),
+    };
+    if runtime && input.is_some() {
+        return Err(String::from("--runtime doesn't take a file"));
+    }
+    Ok(Args {
+        command,
+        input: input.flatten(),
+    })
+}
+
+fn parse_target(target: &str) -> Result<Target, String> {
+    match target {
+        "c" => Ok(Target::C),
+        "llvm" => Ok(Target::Llvm),
+        "wasm" => Ok(Target::Wasm),
+        "wat" => Ok(Target::Wat),
+        other => Err(format!(
+            "unknown target {}, expected c, llvm, wasm or wat",
+            other
+        )),
+    }
+}
+
+// ----------- commands ---------- \\
+// runs a command, giving back what went wrong already worded for stderr
+fn drive(args: &Args) -> Result<(), String> {
+    let name = args.input.as_deref().unwrap_or("<stdin>");
+    match &args.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Command::Build {
+            target,
+            output,
+            runtime: true,
+        } => {
+            let runtime = match target {
+                Target::Llvm => codegen::RUNTIME,
+                Target::Wasm | Target::Wat => codegen::wasm::HOST,
+                Target::C => return Err(String::from("pipes: c output needs no runtime")),
+            };
+            return write_output(output.as_deref(), runtime.as_bytes());
+        }
+        _ => {}
+    }
+
+    let source = read_input(args.input.as_deref())?;
+    let tokens = tokenize_spanned(&source, 0, Comments::Keep).map_err(|errors| {
+        let errors: Vec<String> = errors
+            .iter()
+            .map(|error| format!("{}:{}", name, error))
+            .collect();
+        errors.join("\n")
+    })?;
+    if args.command == Command::Tokens {
+        let mut out = String::new();
+        for spanned in &tokens {
+            let at = format!("{}:{}", spanned.span.line, spanned.span.column);
+            out.push_str(&format!("{:<8} {:?}\n", at, spanned.token));
+        }
+        return write_output(None, out.as_bytes());
+    }
+
+    let mut program = parse_spanned(tokens).map_err(|error| format!("{}:{}", name, error))?;
+    if args.command == Command::Ast {
+        return write_output(None, format!("{:#?}\n", program).as_bytes());
+    }
+
+    let structs = type_check(name, &mut program)?;
+    match &args.command {
+        Command::Run { interpret } => run(name, &program, &structs, *interpret),
+        Command::Build { target, output, .. } => {
+            let compiled = match target {
+                Target::C => codegen::c::compile(&program, &structs).map(String::into_bytes),
+                Target::Llvm => codegen::llvm::compile(&program, &structs).map(String::into_bytes),
+                Target::Wat => codegen::wasm::compile(&program, &structs).map(String::into_bytes),
+                Target::Wasm => codegen::wasm::compile_binary(&program, &structs),
+            };
+            let compiled = compiled.map_err(|error| format!("{}: {}", name, error))?;
+            write_output(output.as_deref(), &compiled)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn type_check(name: &str, program: &mut Program) -> Result<StructRegistry, String> {
+    check(program).map_err(|errors| {
+        let errors: Vec<String> = errors
+            .iter()
+            .map(|error| format!("{}:{}", name, error))
+            .collect();
+        errors.join("\n")
+    })
+}
+
+// whatever the program printed before an error still comes out
+fn run(
+    name: &str,
+    program: &Program,
+    structs: &StructRegistry,
+    interpret: bool,
+) -> Result<(), String> {
+    let stdout = BufWriter::new(io::stdout());
+    let (result, mut stdout) = if interpret {
+        let mut interpreter = Interpreter::with_output(stdout);
+        let result = interpreter.run(program);
+        (result.map(|_| ()), interpreter.into_output())
+    } else {
+        let mut vm = Vm::with_output(stdout);
+        let result = vm.run(&bytecode::compile(program, structs));
+        (result.map(|_| ()), vm.into_output())
+    };
+    stdout.flush().ok();
+    result.map_err(|error| format!("{}: runtime error: {}", name, error))
+}
+
+// ----------- files ---------- \\
+fn read_input(path: Option<&str>) -> Result<String, String> {
+    match path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|error| format!("pipes: can't read {}: {}", path, error)),
+        None => {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|error| format!("pipes: can't read stdin: {}", error))?;
+            Ok(source)
+        }
+    }
+}
+
+// no path means stdout
+fn write_output(path: Option<&str>, bytes: &[u8]) -> Result<(), String> {
+    match path {
+        Some(path) => fs::write(path, bytes)
+            .map_err(|error| format!("pipes: can't write {}: {}", path, error)),
+        None => {
+            let mut stdout = io::stdout();
+            stdout
+                .write_all(bytes)
+                .and_then(|_| stdout.flush())
+                .map_err(|error| format!("pipes: can't write to stdout: {}", error))
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Result<Args, String> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            args("run --interpret main.pipes"),
+            Ok(Args {
+                command: Command::Run { interpret: true },
+                input: Some(String::from("main.pipes")),
+            })
+        );
+        assert_eq!(
+            args("check -"),
+            Ok(Args {
+                command: Command::Check,
+                input: None,
+            })
+        );
+        assert_eq!(
+            args("build main.pipes --target wasm -o main.wasm"),
+            Ok(Args {
+                command: Command::Build {
+                    target: Target::Wasm,
+                    output: Some(String::from("main.wasm")),
+                    runtime: false,
+                },
+                input: Some(String::from("main.pipes")),
+            })
+        );
+        assert_eq!(args("--help").map(|args| args.command), Ok(Command::Help))
+    }
+
+    #[test]
+    fn parse_bad_commands() {
+        assert!(args("").is_err());
+        assert!(args("compile main.pipes").is_err());
+        assert!(args("run a.pipes b.pipes").is_err());
+        assert!(args("check --interpret").is_err());
+        assert!(args("build main.pipes").is_err());
+        assert!(args("build --target js main.pipes").is_err());
+        assert!(args("build --target c -o").is_err());
+        assert!(args("build --target llvm --runtime main.pipes").is_err())
+    }
 }
@@ -1,327 +1,2090 @@
 use crate::ast::*;
-use crate::tokenizer::Token;
+use crate::span::Span;
+use crate::tokenizer::{SpannedToken, Token};
+use crate::types::Type;
+use std::cell::Cell;
+use std::fmt;
 
-pub fn parse(input: Vec<Token>) -> Result<AST, &'static str> {
-	Err("Parser not written")
+// every parse function takes the tokens left to parse and returns what it
+// parsed along with the remainder, the same way the tokenizer works on &str
+type ParseResult<'a, T> = Result<(T, &'a [Token]), ParseError>;
+
+// how deeply expressions, blocks and types can nest. every pass over the tree
+// recurses as deep as it goes, so past this a program is an error rather than
+// a stack overflow
+const MAX_NESTING: usize = 500;
+
+// a level of brackets costs the parser itself several frames, so like calls in
+// the interpreter, parsing a level deeper with less than STACK_RED_ZONE left
+// carries on in a fresh STACK_SEGMENT
+const STACK_RED_ZONE: usize = 64 * 1024;
+const STACK_SEGMENT: usize = 1024 * 1024;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub message: &'static str,
+    pub span: Span,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+// the span of every token being parsed, plus one more for running out of
+// tokens. they line up with the end of the input, so any remainder finds its
+// first token's span from its length alone. depth is how deeply nested the
+// parser is where it's up to
+struct Spans<'s> {
+    spans: &'s [Span],
+    depth: Cell<usize>,
+}
+
+impl Spans<'_> {
+    fn new(spans: &[Span]) -> Spans<'_> {
+        Spans {
+            spans,
+            depth: Cell::new(0),
+        }
+    }
+
+    fn at(&self, input: &[Token]) -> Span {
+        self.spans[self.spans.len() - 1 - input.len()]
+    }
+
+    fn error(&self, input: &[Token], message: &'static str) -> ParseError {
+        ParseError {
+            message,
+            span: self.at(input),
+        }
+    }
+
+    // the spans for parsing only the input that comes before rest
+    fn before(&self, rest: &[Token]) -> Spans<'_> {
+        Spans {
+            spans: &self.spans[..self.spans.len() - rest.len()],
+            depth: self.depth.clone(),
+        }
+    }
+
+    // goes a level deeper, failing at input past MAX_NESTING. loops that
+    // build on what they've parsed so far go a level deeper each time round,
+    // since each time nests the tree they're building one level further
+    fn nest(&self, input: &[Token]) -> Result<(), ParseError> {
+        if self.depth.get() == MAX_NESTING {
+            return Err(self.error(input, "Nested too deeply"));
+        }
+        self.depth.set(self.depth.get() + 1);
+        Ok(())
+    }
+
+    // parses a level deeper than where the parser is
+    fn nested<'a, T>(
+        &self,
+        input: &'a [Token],
+        parse: impl FnOnce(&'a [Token]) -> ParseResult<'a, T>,
+    ) -> ParseResult<'a, T> {
+        let depth = self.depth.get();
+        self.nest(input)?;
+        let parsed = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || parse(input));
+        self.depth.set(depth);
+        parsed
+    }
+}
+
+// for tokens without spans, every node and error gets the default span
+pub fn parse(input: Vec<Token>) -> Result<Program, ParseError> {
+    let input = input
+        .into_iter()
+        .map(|token| SpannedToken {
+            token,
+            span: Span::default(),
+        })
+        .collect();
+    parse_spanned(input)
+}
+
+pub fn parse_spanned(mut input: Vec<SpannedToken>) -> Result<Program, ParseError> {
+    // comments only matter to tools that asked the tokenizer to keep them
+    input.retain(|spanned| !matches!(spanned.token, Token::Comment(_)));
+
+    // running out of tokens is reported at the last one
+    let end = input.last().map(|spanned| spanned.span).unwrap_or_default();
+    let (tokens, mut spans): (Vec<Token>, Vec<Span>) = input
+        .into_iter()
+        .map(|spanned| (spanned.token, spanned.span))
+        .unzip();
+    spans.push(end);
+    let spans = Spans::new(&spans);
+
+    let mut items = vec![];
+    let mut input = &tokens[..];
+    while !input.is_empty() {
+        let (item, remainder) = parse_item(input, &spans)?;
+        items.push(item);
+        input = remainder;
+    }
+    Ok(Program { items })
+}
+
+fn parse_item<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Item> {
+    if let [Token::Struct, ..] = input {
+        let (def, remainder) = parse_struct_def(input, spans)?;
+        return Ok((Item::Struct(def), remainder));
+    }
+    if let Some((stmt, remainder)) = try_parse_stmt(input, spans)? {
+        return Ok((Item::from(stmt), remainder));
+    }
+
+    let (exp, remainder) = try_parse_exp(input, spans)?;
+    // a trailing expression doesn't need its semicolon
+    let remainder = match remainder.split_first() {
+        Some((Token::Semicolon, remainder)) => remainder,
+        Some(_) => return Err(spans.error(remainder, "Expected ';' after expression")),
+        None => remainder,
+    };
+    let stmt = Statement::Exp(exp, spans.at(input));
+    Ok((Item::Statement(stmt), remainder))
+}
+
+// returns None if the input doesn't start with a statement, so the caller can
+// try an expression instead
+fn try_parse_stmt<'a>(
+    input: &'a [Token],
+    spans: &Spans,
+) -> Result<Option<(Statement, &'a [Token])>, ParseError> {
+    let parsed = match input {
+        [Token::Let, ..] => parse_let(input, spans)?,
+        [Token::Var(_), Token::Assign, ..] => parse_reassign(input, spans)?,
+        [Token::Function, ..] => parse_function(input, spans)?,
+        [Token::Return, ..] => parse_return(input, spans)?,
+        [Token::If, ..] => parse_if(input, spans)?,
+        [Token::For, ..] => parse_for(input, spans)?,
+        [Token::While, ..] => parse_while(input, spans)?,
+        [Token::Struct, ..] => {
+            return Err(spans.error(input, "Structs can only be declared at the top level"))
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(parsed))
+}
+
+fn try_parse_exp<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    spans.nested(input, |input| parse_pipe(input, spans))
+}
+
+// ----------- statements ---------- \\
+// let name: type = exp;
+fn parse_let<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Statement> {
+    let input = expect(input, spans, Token::Let, "Expected 'let'")?;
+    let span = spans.at(input);
+    let (name, input) = expect_name(input, spans, "Expected a variable name after 'let'")?;
+    let input = expect(
+        input,
+        spans,
+        Token::Colon,
+        "Expected ':' after variable name",
+    )?;
+    let (var_type, input) = parse_type(input, spans)?;
+    let input = expect(
+        input,
+        spans,
+        Token::Assign,
+        "Expected '=' in variable declaration",
+    )?;
+    let (mut exp, input) = try_parse_exp(input, spans)?;
+    let input = expect(
+        input,
+        spans,
+        Token::Semicolon,
+        "Expected ';' after variable declaration",
+    )?;
+
+    exp.apply_declared_type(&var_type);
+    Ok((
+        Statement::Assignment(Var::spanned(&name, Some(var_type), span), exp),
+        input,
+    ))
+}
+
+// name = exp;
+fn parse_reassign<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Statement> {
+    let span = spans.at(input);
+    let (name, input) = expect_name(input, spans, "Expected a variable name")?;
+    let input = expect(input, spans, Token::Assign, "Expected '='")?;
+    let (exp, input) = try_parse_exp(input, spans)?;
+    let input = expect(
+        input,
+        spans,
+        Token::Semicolon,
+        "Expected ';' after assignment",
+    )?;
+
+    Ok((
+        Statement::Assignment(Var::spanned(&name, None, span), exp),
+        input,
+    ))
+}
+
+// func name(param: type,) -> type { block }
+fn parse_function<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Statement> {
+    let span = spans.at(input);
+    let input = expect(input, spans, Token::Function, "Expected 'func'")?;
+    let name_span = spans.at(input);
+    let (name, input) = expect_name(input, spans, "Expected a function name after 'func'")?;
+    let mut input = expect(
+        input,
+        spans,
+        Token::LeftParen,
+        "Expected '(' after function name",
+    )?;
+
+    let mut param = vec![];
+    while let Some((Token::Var(param_name), remainder)) = input.split_first() {
+        let param_span = spans.at(input);
+        let remainder = expect(
+            remainder,
+            spans,
+            Token::Colon,
+            "Expected ':' after parameter name",
+        )?;
+        let (param_type, remainder) = parse_type(remainder, spans)?;
+        param.push(Var::spanned(param_name, Some(param_type), param_span));
+
+        input = match remainder.split_first() {
+            Some((Token::Comma, remainder)) => remainder,
+            Some((Token::RightParen, _)) => remainder,
+            _ => return Err(spans.error(remainder, "Expected ',' or ')' after parameter")),
+        };
+    }
+    let input = expect(
+        input,
+        spans,
+        Token::RightParen,
+        "Expected ')' after parameters",
+    )?;
+
+    let (output_type, input) = match input.split_first() {
+        Some((Token::Output, remainder)) => parse_type(remainder, spans)?,
+        _ => (Type::Void, input),
+    };
+    let (block, input) = parse_block(input, spans)?;
+
+    let function = Function {
+        output: Var::spanned(&name, Some(output_type), name_span),
+        name,
+        param,
+        block,
+        span,
+    };
+    Ok((Statement::Function(function), input))
+}
+
+// return exp;
+fn parse_return<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Statement> {
+    let span = spans.at(input);
+    let input = expect(input, spans, Token::Return, "Expected 'return'")?;
+    if let Some((Token::Semicolon, remainder)) = input.split_first() {
+        return Ok((Statement::Return(Exp::Void, span), remainder));
+    }
+
+    let (exp, input) = try_parse_exp(input, spans)?;
+    let input = expect(input, spans, Token::Semicolon, "Expected ';' after return")?;
+    Ok((Statement::Return(exp, span), input))
+}
+
+// if (exp) { block } (elif (exp) { block })* else { block }
+fn parse_if<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Statement> {
+    let span = spans.at(input);
+    let input = expect(input, spans, Token::If, "Expected 'if'")?;
+    parse_if_branches(input, spans, span)
+}
+
+// everything after an 'if' or 'elif', which is at span. elif and else if both
+// become an if nested in the false branch
+fn parse_if_branches<'a>(
+    input: &'a [Token],
+    spans: &Spans,
+    span: Span,
+) -> ParseResult<'a, Statement> {
+    let (condition, input) = parse_condition(input, spans)?;
+    let (true_branch, input) = parse_block(input, spans)?;
+
+    let (false_branch, input) = match input {
+        [Token::Elif, remainder @ ..] | [Token::Else, Token::If, remainder @ ..] => {
+            let (stmt, remainder) = parse_if_branches(remainder, spans, spans.at(input))?;
+            (Some(vec![stmt]), remainder)
+        }
+        [Token::Else, ..] => {
+            let (block, remainder) = parse_block(&input[1..], spans)?;
+            (Some(block), remainder)
+        }
+        _ => (None, input),
+    };
+
+    let if_stmt = IfStatement {
+        condition,
+        true_branch,
+        false_branch,
+        span,
+    };
+    Ok((Statement::If(if_stmt), input))
+}
+
+// for name in exp { block }
+fn parse_for<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Statement> {
+    let span = spans.at(input);
+    let input = expect(input, spans, Token::For, "Expected 'for'")?;
+    let name_span = spans.at(input);
+    let (name, input) = expect_name(input, spans, "Expected a variable name after 'for'")?;
+    let input = expect(input, spans, Token::In, "Expected 'in' after loop variable")?;
+
+    // `xs {` would parse as a struct literal, so the iterable is everything
+    // up to the first '{' that isn't inside brackets
+    let (iterable, input) = input.split_at(loop_block_start(input));
+    let iterable_spans = spans.before(input);
+    let iterable = match try_parse_exp(iterable, &iterable_spans)? {
+        (iterable, []) => iterable,
+        (_, remainder) => {
+            return Err(iterable_spans.error(remainder, "Expected '{' after the loop's iterable"))
+        }
+    };
+    let (block, input) = parse_block(input, spans)?;
+
+    // the loop variable's type is only known here for list literals,
+    // the type checker works it out for everything else
+    let item_type = match &iterable {
+        Exp::List(list_type, _) => list_type.as_ref().and_then(Type::element_type),
+        _ => None,
+    };
+    let for_stmt = ForStatement {
+        iter: Var::spanned(&name, item_type, name_span),
+        iterable,
+        block,
+        span,
+    };
+    Ok((Statement::For(for_stmt), input))
+}
+
+// the index of the '{' that opens a loop's block, or the end of the input
+fn loop_block_start(input: &[Token]) -> usize {
+    let mut depth = 0usize;
+    for (i, token) in input.iter().enumerate() {
+        match token {
+            Token::LeftParen | Token::LeftBrace => depth += 1,
+            Token::RightParen | Token::RightBrace => depth = depth.saturating_sub(1),
+            Token::LeftCurly if depth == 0 => return i,
+            _ => {}
+        }
+    }
+    input.len()
+}
+
+// struct name { field: type, }
+fn parse_struct_def<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, StructDef> {
+    let span = spans.at(input);
+    let input = expect(input, spans, Token::Struct, "Expected 'struct'")?;
+    let (name, input) = expect_name(input, spans, "Expected a struct name after 'struct'")?;
+    let mut input = expect(
+        input,
+        spans,
+        Token::LeftCurly,
+        "Expected '{' after struct name",
+    )?;
+
+    let mut fields = vec![];
+    while let Some((Token::Var(field), remainder)) = input.split_first() {
+        let field_span = spans.at(input);
+        let remainder = expect(
+            remainder,
+            spans,
+            Token::Colon,
+            "Expected ':' after field name",
+        )?;
+        let (field_type, remainder) = parse_type(remainder, spans)?;
+        fields.push(Var::spanned(field, Some(field_type), field_span));
+
+        input = match remainder.split_first() {
+            Some((Token::Comma, remainder)) => remainder,
+            Some((Token::RightCurly, _)) => remainder,
+            _ => return Err(spans.error(remainder, "Expected ',' or '}' after field")),
+        };
+    }
+    let input = expect(
+        input,
+        spans,
+        Token::RightCurly,
+        "Expected '}' after struct fields",
+    )?;
+
+    Ok((StructDef { name, fields, span }, input))
+}
+
+// while (exp) { block }
+fn parse_while<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Statement> {
+    let span = spans.at(input);
+    let input = expect(input, spans, Token::While, "Expected 'while'")?;
+    let (condition, input) = parse_condition(input, spans)?;
+    let (block, input) = parse_block(input, spans)?;
+
+    let while_stmt = WhileStatement {
+        condition,
+        block,
+        span,
+    };
+    Ok((Statement::While(while_stmt), input))
+}
+
+// (exp)
+fn parse_condition<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    let input = expect(
+        input,
+        spans,
+        Token::LeftParen,
+        "Expected '(' before condition",
+    )?;
+    let (condition, input) = try_parse_exp(input, spans)?;
+    let input = expect(
+        input,
+        spans,
+        Token::RightParen,
+        "Expected ')' after condition",
+    )?;
+    Ok((condition, input))
+}
+
+// { stmt* }
+fn parse_block<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Vec<Statement>> {
+    spans.nested(input, |input| parse_stmts(input, spans))
+}
+
+fn parse_stmts<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Vec<Statement>> {
+    let mut input = expect(
+        input,
+        spans,
+        Token::LeftCurly,
+        "Expected '{' to start a block",
+    )?;
+    let mut block = vec![];
+
+    loop {
+        match input.split_first() {
+            Some((Token::RightCurly, remainder)) => return Ok((block, remainder)),
+            None => return Err(spans.error(input, "Expected '}' to close the block")),
+            _ => {}
+        }
+
+        if let Some((stmt, remainder)) = try_parse_stmt(input, spans)? {
+            block.push(stmt);
+            input = remainder;
+        } else {
+            let span = spans.at(input);
+            let (exp, remainder) = try_parse_exp(input, spans)?;
+            input = expect(
+                remainder,
+                spans,
+                Token::Semicolon,
+                "Expected ';' after expression",
+            )?;
+            block.push(Statement::Exp(exp, span));
+        }
+    }
+}
+
+// ----------- expressions ---------- \\
+// exp |> function, binds looser than every operator and chains to the left
+fn parse_pipe<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    let (mut left, mut input) = parse_binary(input, spans, 0)?;
+
+    let depth = spans.depth.get();
+    while let Some((Token::Pipe, remainder)) = input.split_first() {
+        spans.nest(input)?;
+        let (right, after) = parse_binary(remainder, spans, 0)?;
+        match right {
+            Exp::Var(_) | Exp::Call(..) | Exp::HOF(..) | Exp::Field(..) => {}
+            _ => {
+                return Err(
+                    spans.error(remainder, "Expected a function or function call after '|>'")
+                )
+            }
+        }
+        left = Exp::Pipe(Box::new(left), Box::new(right));
+        input = after;
+    }
+    spans.depth.set(depth);
+
+    Ok((left, input))
+}
+
+// precedence climbing: parses operators that bind at least as tight as min_prec
+fn parse_binary<'a>(input: &'a [Token], spans: &Spans, min_prec: u8) -> ParseResult<'a, Exp> {
+    let (mut left, mut input) = parse_unary(input, spans)?;
+
+    let depth = spans.depth.get();
+    while let Some((op, prec)) = input.first().and_then(binary_op) {
+        if prec < min_prec {
+            break;
+        }
+        spans.nest(input)?;
+        // prec + 1 makes every operator left associative
+        let (right, remainder) = parse_binary(&input[1..], spans, prec + 1)?;
+        left = Exp::Op(op, Box::new(left), Box::new(right));
+        input = remainder;
+    }
+    spans.depth.set(depth);
+
+    Ok((left, input))
+}
+
+fn binary_op(token: &Token) -> Option<(Operation, u8)> {
+    match token {
+        Token::Or => Some((Operation::Or, 1)),
+        Token::And => Some((Operation::And, 2)),
+
+        Token::Equal => Some((Operation::Equal, 3)),
+        Token::NotEqual => Some((Operation::NotEqual, 3)),
+
+        Token::GreaterThan => Some((Operation::GreaterThan, 4)),
+        Token::LessThan => Some((Operation::LessThan, 4)),
+        Token::GreaterEqual => Some((Operation::GreaterEqual, 4)),
+        Token::LessEqual => Some((Operation::LessEqual, 4)),
+
+        Token::Plus => Some((Operation::Add, 5)),
+        Token::Minus => Some((Operation::Subtract, 5)),
+
+        Token::Multiply => Some((Operation::Multiply, 6)),
+        Token::Divide => Some((Operation::Divide, 6)),
+        Token::Modulo => Some((Operation::Modulo, 6)),
+
+        _ => None,
+    }
+}
+
+// -exp | !exp
+fn parse_unary<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    match input {
+        // the tokenizer leaves negative ints to us, and gives 2^63 as
+        // i64::MIN, which stays i64::MIN once negated
+        [Token::Minus, Token::Int(i), remainder @ ..] => {
+            Ok((Exp::Int(i.wrapping_neg()), remainder))
+        }
+        [Token::Minus, Token::Float(f), remainder @ ..] => Ok((Exp::Float(-f), remainder)),
+        [Token::Minus, Token::BigInt(i), remainder @ ..] => Ok((Exp::BigInt(-i), remainder)),
+        [Token::Minus, remainder @ ..] => {
+            let (exp, remainder) = spans.nested(remainder, |input| parse_unary(input, spans))?;
+            Ok((Exp::Unary(UnaryOp::Neg, Box::new(exp)), remainder))
+        }
+        [Token::Not, remainder @ ..] => {
+            let (exp, remainder) = spans.nested(remainder, |input| parse_unary(input, spans))?;
+            Ok((Exp::Unary(UnaryOp::Not, Box::new(exp)), remainder))
+        }
+        _ => parse_postfix(input, spans),
+    }
+}
+
+// exp.field and exp(args), which bind tighter than any prefix operator
+fn parse_postfix<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    let (mut exp, mut input) = parse_primary(input, spans)?;
+    let depth = spans.depth.get();
+    loop {
+        if let [Token::Dot, ..] | [Token::LeftParen, ..] = input {
+            spans.nest(input)?;
+        }
+        match input {
+            [Token::Dot, Token::Var(field), remainder @ ..] => {
+                exp = Exp::Field(Box::new(exp), field.clone());
+                input = remainder;
+            }
+            [Token::LeftParen, remainder @ ..] => {
+                let (args, remainder) = parse_args(remainder, spans)?;
+                exp = Exp::Call(Box::new(exp), args);
+                input = remainder;
+            }
+            _ => {
+                spans.depth.set(depth);
+                return Ok((exp, input));
+            }
+        }
+    }
+}
+
+fn parse_primary<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    match input {
+        [Token::Int(i64::MIN), ..] => Err(spans.error(
+            input,
+            "Integer literal does not fit in an int, use the n suffix for a bigint",
+        )),
+        [Token::Int(i), remainder @ ..] => Ok((Exp::Int(*i), remainder)),
+        [Token::Float(f), remainder @ ..] => Ok((Exp::Float(*f), remainder)),
+        [Token::BigInt(i), remainder @ ..] => Ok((Exp::BigInt(i.clone()), remainder)),
+        [Token::Str(s), remainder @ ..] => Ok((Exp::Str(s.clone()), remainder)),
+        [Token::Bool(b), remainder @ ..] => Ok((Exp::Bool(*b), remainder)),
+
+        [Token::Var(name), Token::LeftCurly, remainder @ ..] => {
+            parse_struct(name, remainder, spans)
+        }
+        [Token::Var(name), remainder @ ..] => {
+            let var = Var::spanned(name, None, spans.at(input));
+            Ok((Exp::Var(Box::new(var)), remainder))
+        }
+
+        [Token::LeftCurly, remainder @ ..] => parse_struct("", remainder, spans),
+        [Token::LeftBrace, remainder @ ..] => parse_list(remainder, spans),
+
+        [Token::LeftParen, Token::RightParen, Token::LeftCurly, ..]
+        | [Token::LeftParen, Token::Var(_), Token::RightParen, Token::LeftCurly, ..]
+        | [Token::LeftParen, Token::Var(_), Token::Colon, ..]
+        | [Token::LeftParen, Token::Var(_), Token::Comma, ..] => parse_hof(&input[1..], spans),
+        [Token::LeftParen, Token::RightParen, remainder @ ..] => Ok((Exp::Void, remainder)),
+        [Token::LeftParen, remainder @ ..] => {
+            let (exp, remainder) = try_parse_exp(remainder, spans)?;
+            let remainder = expect(remainder, spans, Token::RightParen, "Expected ')'")?;
+            Ok((exp, remainder))
+        }
+
+        [] => Err(spans.error(input, "Expected an expression but found the end of input")),
+        _ => Err(spans.error(input, "Expected an expression")),
+    }
+}
+
+// (exp, exp,) with the '(' already consumed
+fn parse_args<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Vec<Exp>> {
+    let mut input = input;
+    let mut args = vec![];
+
+    loop {
+        if let [Token::RightParen, remainder @ ..] = input {
+            return Ok((args, remainder));
+        }
+        if input.is_empty() {
+            return Err(spans.error(input, "Expected ')' to close the function call"));
+        }
+        let (arg, remainder) = try_parse_exp(input, spans)?;
+        args.push(arg);
+        input = match remainder.split_first() {
+            Some((Token::Comma, remainder)) => remainder,
+            Some((Token::RightParen, _)) => remainder,
+            _ => return Err(spans.error(remainder, "Expected ',' or ')' in function call")),
+        };
+    }
+}
+
+// name { field: exp, } with the name and '{' already consumed
+fn parse_struct<'a>(name: &str, input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    let mut input = input;
+    let mut fields = vec![];
+
+    while let Some((Token::Var(field), remainder)) = input.split_first() {
+        let span = spans.at(input);
+        let remainder = expect(
+            remainder,
+            spans,
+            Token::Colon,
+            "Expected ':' after field name",
+        )?;
+        let (exp, remainder) = try_parse_exp(remainder, spans)?;
+        fields.push(Var {
+            name: field.clone(),
+            var_type: None,
+            value: Some(exp),
+            span,
+        });
+
+        input = match remainder.split_first() {
+            Some((Token::Comma, remainder)) => remainder,
+            Some((Token::RightCurly, _)) => remainder,
+            _ => return Err(spans.error(remainder, "Expected ',' or '}' after field")),
+        };
+    }
+    let input = expect(
+        input,
+        spans,
+        Token::RightCurly,
+        "Expected '}' to close the struct",
+    )?;
+
+    Ok((Exp::Struct(String::from(name), fields), input))
 }
 
-fn try_parse_stmt(input: Vec<Token>) -> Option<Statement> {
-	// try all of em, all of our statements will also require parsing exp
-	None
+// [exp,] with the '[' already consumed
+fn parse_list<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    let mut input = input;
+    let mut items = vec![];
+
+    loop {
+        if let Some((Token::RightBrace, remainder)) = input.split_first() {
+            let list_type = items.first().and_then(infer_list_type);
+            return Ok((Exp::List(list_type, items), remainder));
+        }
+
+        let (item, remainder) = try_parse_exp(input, spans)?;
+        items.push(item);
+        input = match remainder.split_first() {
+            Some((Token::Comma, remainder)) => remainder,
+            Some((Token::RightBrace, _)) => remainder,
+            _ => return Err(spans.error(remainder, "Expected ',' or ']' in list")),
+        };
+    }
+}
+
+// (param, param: type,) { block } with the '(' already consumed
+fn parse_hof<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Exp> {
+    let mut input = input;
+    let mut params = vec![];
+
+    while let Some((Token::Var(name), remainder)) = input.split_first() {
+        let span = spans.at(input);
+        let (param_type, remainder) = match remainder.split_first() {
+            Some((Token::Colon, remainder)) => {
+                let (param_type, remainder) = parse_type(remainder, spans)?;
+                (Some(param_type), remainder)
+            }
+            _ => (None, remainder),
+        };
+        params.push(Var::spanned(name, param_type, span));
+
+        input = match remainder.split_first() {
+            Some((Token::Comma, remainder)) => remainder,
+            Some((Token::RightParen, _)) => remainder,
+            _ => return Err(spans.error(remainder, "Expected ',' or ')' after parameter")),
+        };
+    }
+    let input = expect(
+        input,
+        spans,
+        Token::RightParen,
+        "Expected ')' after parameters",
+    )?;
+    let (block, input) = parse_block(input, spans)?;
+
+    Ok((Exp::HOF(params, block), input))
+}
+
+// ----------- types ---------- \\
+// int | str | bool | void | struct_name | [type] | (type, type) -> type
+fn parse_type<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Type> {
+    spans.nested(input, |input| parse_type_at(input, spans))
+}
+
+fn parse_type_at<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Type> {
+    match input {
+        [Token::TypeName(t), remainder @ ..] => Ok((t.clone(), remainder)),
+        [Token::Var(name), remainder @ ..] => Ok((Type::Struct(name.clone()), remainder)),
+        [Token::LeftBrace, remainder @ ..] => {
+            let (inner, remainder) = parse_type(remainder, spans)?;
+            let remainder = expect(
+                remainder,
+                spans,
+                Token::RightBrace,
+                "Expected ']' after list type",
+            )?;
+            Ok((Type::List(Box::new(inner)), remainder))
+        }
+        [Token::LeftParen, remainder @ ..] => parse_function_type(remainder, spans),
+        _ => Err(spans.error(input, "Expected a type")),
+    }
+}
+
+// (type, type) -> type, or the older (type -> type) with the arrow inside the
+// parentheses, with the '(' already consumed. a single type in parentheses
+// without an arrow is just grouped
+fn parse_function_type<'a>(input: &'a [Token], spans: &Spans) -> ParseResult<'a, Type> {
+    let mut input = input;
+    let mut params = vec![];
+
+    loop {
+        match input {
+            [Token::RightParen, Token::Output, remainder @ ..] => {
+                let (output, remainder) = parse_type(remainder, spans)?;
+                let hof = Type::HigherOrderFunction(params, Box::new(output));
+                return Ok((hof, remainder));
+            }
+            [Token::Output, remainder @ ..] => {
+                let (output, remainder) = parse_type(remainder, spans)?;
+                let remainder = expect(
+                    remainder,
+                    spans,
+                    Token::RightParen,
+                    "Expected ')' after function type",
+                )?;
+                let hof = Type::HigherOrderFunction(params, Box::new(output));
+                return Ok((hof, remainder));
+            }
+            [Token::RightParen, remainder @ ..] if params.len() == 1 => {
+                return Ok((params.remove(0), remainder));
+            }
+            [Token::RightParen, ..] => {
+                return Err(spans.error(input, "Expected '->' in function type"))
+            }
+            _ => {
+                let (param, remainder) = parse_type(input, spans)?;
+                params.push(param);
+                input = match remainder.split_first() {
+                    Some((Token::Comma, remainder)) => remainder,
+                    Some((Token::RightParen, _)) | Some((Token::Output, _)) => remainder,
+                    _ => {
+                        let message = "Expected ',', ')' or '->' in function type";
+                        return Err(spans.error(remainder, message));
+                    }
+                };
+            }
+        }
+    }
+}
+
+fn infer_list_type(item: &Exp) -> Option<Type> {
+    let element = match item {
+        Exp::Int(_) => Type::Int,
+        Exp::BigInt(_) => Type::BigInt,
+        Exp::Float(_) => Type::Float,
+        Exp::Str(_) => Type::Str,
+        Exp::Bool(_) => Type::Bool,
+        Exp::Struct(name, _) if !name.is_empty() => Type::Struct(name.clone()),
+        Exp::List(Some(list_type), _) => list_type.clone(),
+        _ => return None,
+    };
+    Some(Type::List(Box::new(element)))
 }
 
-fn try_parse_exp(input: Vec<Token>) -> Option<Exp> {
-	// try primary (var, int, str, bool)
-	// try operation
-	// try data structure
-	None
+// ----------- helpers ---------- \\
+fn expect<'a>(
+    input: &'a [Token],
+    spans: &Spans,
+    token: Token,
+    err: &'static str,
+) -> Result<&'a [Token], ParseError> {
+    match input.split_first() {
+        Some((first, remainder)) if *first == token => Ok(remainder),
+        _ => Err(spans.error(input, err)),
+    }
+}
+
+fn expect_name<'a>(
+    input: &'a [Token],
+    spans: &Spans,
+    err: &'static str,
+) -> ParseResult<'a, String> {
+    match input.split_first() {
+        Some((Token::Var(name), remainder)) => Ok((name.clone(), remainder)),
+        _ => Err(spans.error(input, err)),
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::tokenizer::token::Token;
+    use crate::tokenizer::tokenizer::{tokenize, tokenize_spanned, Comments};
+
+    fn ast(exp: Exp) -> Result<Program, ParseError> {
+        ast_stmt(Statement::Exp(exp, Span::default()))
+    }
+
+    fn ast_stmt(stmt: Statement) -> Result<Program, ParseError> {
+        Ok(Program {
+            items: vec![Item::from(stmt)],
+        })
+    }
+
+    fn op(operation: Operation, left: Exp, right: Exp) -> Exp {
+        Exp::Op(operation, Box::new(left), Box::new(right))
+    }
+
+    fn var(name: &str) -> Exp {
+        Exp::Var(Box::new(Var::new(name, None)))
+    }
+
+    fn assign(name: &str, var_type: Type, exp: Exp) -> Statement {
+        Statement::Assignment(Var::new(name, Some(var_type)), exp)
+    }
+
+    fn list_of(element: Type) -> Type {
+        Type::List(Box::new(element))
+    }
 
     //Operation tests
     #[test]
     fn parser_add_two_ints() {
         // 1+2
-        let tokens = tokenize("1+2");
+        let tokens = tokenize("1+2").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(Operation::Add, left(Exp::Int(1)), right(Exp::Int(2))))
+            ast(op(Operation::Add, Exp::Int(1), Exp::Int(2)))
         )
     }
 
     #[test]
     fn parser_substract_two_ints() {
-        let tokens = tokenize("4-2");
+        let tokens = tokenize("4-2").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(
-                Operation::Subtract,
-                left(Exp::Int(4)),
-                right(Exp::Int(2))
-            ))
+            ast(op(Operation::Subtract, Exp::Int(4), Exp::Int(2)))
         )
     }
 
     #[test]
     fn parser_multiply_two_ints() {
-        let tokens = tokenize("2*5");
+        let tokens = tokenize("2*5").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(
-                Operation::Multiply,
-                left(Exp::Int(2)),
-                right(Exp::Int(5))
-            ))
+            ast(op(Operation::Multiply, Exp::Int(2), Exp::Int(5)))
         )
     }
 
     #[test]
     fn parser_divide_two_ints() {
-        let tokens = tokenize("2/2");
+        let tokens = tokenize("2/2").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(Operation::Divide, left(Exp::Int(2)), right(Exp::Int(2))))
+            ast(op(Operation::Divide, Exp::Int(2), Exp::Int(2)))
         )
     }
 
     #[test]
     fn parser_modulo_two_ints() {
-        let tokens = tokenize("4 % 2");
+        let tokens = tokenize("4 % 2").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(Operation::Modulo, left(Exp::Int(4)), right(Exp::Int(2))))
+            ast(op(Operation::Modulo, Exp::Int(4), Exp::Int(2)))
         )
     }
 
     #[test]
     fn parser_great_than_two_ints() {
-        let tokens = tokenize("3 > 2");
+        let tokens = tokenize("3 > 2").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(
-                Operation::GreaterThan,
-                left(Exp::Int(3)),
-                right(Exp::Int(2))
-            ))
+            ast(op(Operation::GreaterThan, Exp::Int(3), Exp::Int(2)))
         )
     }
 
     #[test]
     fn parser_less_than_two_ints() {
-        let tokens = tokenize("3 < 5");
+        let tokens = tokenize("3 < 5").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(
-                Operation::LessThan,
-                left(Exp::Int(3)),
-                right(Exp::Int(5))
-            ))
+            ast(op(Operation::LessThan, Exp::Int(3), Exp::Int(5)))
         )
     }
 
     #[test]
     fn parser_great_equal_two_ints() {
-        let tokens = tokenize("3 >= 2");
+        let tokens = tokenize("3 >= 2").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(
-                Operation::GreaterEqual,
-                left(Exp::Int(3)),
-                right(Exp::Int(2))
-            ))
+            ast(op(Operation::GreaterEqual, Exp::Int(3), Exp::Int(2)))
         )
     }
+
     #[test]
     fn parser_less_equal_two_ints() {
-        let tokens = tokenize("3 <= 4");
+        let tokens = tokenize("3 <= 4").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(
-                Operation::LessEqual,
-                left(Exp::Int(3)),
-                right(Exp::Int(4))
-            ))
+            ast(op(Operation::LessEqual, Exp::Int(3), Exp::Int(4)))
         )
     }
 
     #[test]
     fn parser_equal_two_ints() {
-        let tokens = tokenize("3 == 3");
+        let tokens = tokenize("3 == 3").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(Operation::Equal, left(Exp::Int(3)), right(Exp::Int(3))))
+            ast(op(Operation::Equal, Exp::Int(3), Exp::Int(3)))
         )
     }
 
     #[test]
     fn parser_not_equal_two_ints() {
-        let tokens = tokenize("3 != 2");
+        let tokens = tokenize("3 != 2").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(OP(
-                Operation::NotEqual,
-                left(Exp::Int(3)),
-                right(Exp::Int(2))
-            ))
+            ast(op(Operation::NotEqual, Exp::Int(3), Exp::Int(2)))
         )
     }
 
     #[test]
-    // let x: Int = 1;
-    fn parser_int_variable_assignment() {
-        let tokens = tokenize("let x: Int = 1;");
-        assert_eq!(parse(tokens),
-			ast(Statement::Assignment(Exp::Var(name::String::from("x"), type: Type::Int, value::None), 
-			Exp::Int(1))))
+    fn parser_and_binds_tighter_than_or() {
+        let tokens = tokenize("a || b && c").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast(op(
+                Operation::Or,
+                var("a"),
+                op(Operation::And, var("b"), var("c"))
+            ))
+        )
     }
 
-    //Integer assignment with order of operations
     #[test]
-    fn parse_int_assign_1() {
-        let tokens = tokenize("let x: int = 1 + 2;");
+    fn parser_subtract_is_left_associative() {
+        let tokens = tokenize("5 - 2 - 1").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(Assignment(
-                Exp::Var(name::String::from("x"), Type::Int, value::None),
-                Op(Operation::Add, left(Exp::Int(1)), right(Exp::Int(2)))
+            ast(op(
+                Operation::Subtract,
+                op(Operation::Subtract, Exp::Int(5), Exp::Int(2)),
+                Exp::Int(1)
             ))
-        );
+        )
     }
 
     #[test]
-    fn parse_int_assign_2() {
-        let tokens = tokenize("let x: int = 1 - 2;");
+    fn parser_parens_override_precedence() {
+        let tokens = tokenize("(1 + 2) * 3").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(Assignment(
-                Exp::Var(name::String::from("x"), Type::Int, value::None),
-                Op(Operation::Minus, left(Exp::Int(1)), right(Exp::Int(2)))
+            ast(op(
+                Operation::Multiply,
+                op(Operation::Add, Exp::Int(1), Exp::Int(2)),
+                Exp::Int(3)
             ))
-        );
+        )
     }
 
     #[test]
-    fn parse_int_assign_3() {
-        let tokens = tokenize("let x: int = 4 * 5;");
+    fn parser_pipe() {
+        let tokens = tokenize("data |> filter(is_valid) |> map(scale)").unwrap();
+        let call = |name: &str, arg: &str| Exp::Call(Box::new(var(name)), vec![var(arg)]);
         assert_eq!(
             parse(tokens),
-            ast(Assignment(
-                Exp::Var(name::String::from("x"), Type::Int, value::None),
-                Op(Operation::Multiply, left(Exp::Int(4)), right(Exp::Int(5)))
+            ast(Exp::Pipe(
+                Box::new(Exp::Pipe(
+                    Box::new(var("data")),
+                    Box::new(call("filter", "is_valid"))
+                )),
+                Box::new(call("map", "scale"))
             ))
-        );
+        )
     }
 
     #[test]
-    fn parse_int_assign_4() {
-        let tokens = tokenize("let x: int = 9 / 3;");
+    fn parser_pipe_binds_loosest() {
+        let tokens = tokenize("a + 1 |> double").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(Assignment(
-                Exp::Var(name::String::from("x"), Type::Int, value::None),
-                Op(Operation::Divide, left(Exp::Int(9)), right(Exp::Int(3)))
+            ast(Exp::Pipe(
+                Box::new(op(Operation::Add, var("a"), Exp::Int(1))),
+                Box::new(var("double"))
             ))
-        );
+        )
     }
 
     #[test]
-    fn parse_int_assign_5() {
-        let tokens = tokenize("let x: int = 1 + 2 * 3;");
+    fn parser_pipe_into_lambda() {
+        let tokens = tokenize("let y: int = x |> (a) { return a; };").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(Assignment(
-                Exp::Var(name::String::from("x"), Type::Int, value::None),
-                Op(
-                    Operation::Add,
-                    left(Exp::Int(1)),
-                    right(OP(
-                        Operation::Multiply,
-                        left(Exp::Int(2)),
-                        right(Exp::Int(3))
+            ast_stmt(assign(
+                "y",
+                Type::Int,
+                Exp::Pipe(
+                    Box::new(var("x")),
+                    Box::new(Exp::HOF(
+                        vec![Var::new("a", None)],
+                        vec![Statement::Return(var("a"), Span::default())]
                     ))
                 )
             ))
-        );
+        )
     }
 
     #[test]
-    fn parse_int_assign_6() {
-        let tokens = tokenize("let x: int = 2 * 3 + 8 / 2;");
-        assert_eq!(
-            parse(tokens),
-            ast(Assignment(
-                Exp::Var(name::String::from("x"), Type::Int, value::None),
-                Op(
-                    Operation::Operation::Add,
-                    left(Op(
-                        Operation::Multiply,
-                        left(Exp::Int(2)),
-                        right(Exp::Int(3))
-                    )),
-                    right(Op(Operation::Divide, left(Exp::Int(8)), right(Exp::Int(2))))
-                )
+    fn parser_pipe_into_non_function() {
+        assert!(parse(tokenize("x |> 1 + 2").unwrap()).is_err());
+        assert!(parse(tokenize("x |>").unwrap()).is_err());
+    }
+
+    #[test]
+    fn parser_negative_int() {
+        assert_eq!(parse(tokenize("-5").unwrap()), ast(Exp::Int(-5)))
+    }
+
+    #[test]
+    fn parser_not() {
+        assert_eq!(
+            parse(tokenize("!done").unwrap()),
+            ast(Exp::Unary(UnaryOp::Not, Box::new(var("done"))))
+        );
+        assert_eq!(
+            parse(tokenize("!!done").unwrap()),
+            ast(Exp::Unary(
+                UnaryOp::Not,
+                Box::new(Exp::Unary(UnaryOp::Not, Box::new(var("done"))))
             ))
+        )
+    }
+
+    #[test]
+    fn parser_neg() {
+        assert_eq!(
+            parse(tokenize("-x").unwrap()),
+            ast(Exp::Unary(UnaryOp::Neg, Box::new(var("x"))))
         );
+        assert_eq!(
+            parse(tokenize("-(1 + 2)").unwrap()),
+            ast(Exp::Unary(
+                UnaryOp::Neg,
+                Box::new(op(Operation::Add, Exp::Int(1), Exp::Int(2)))
+            ))
+        )
     }
 
-    //String assignments
     #[test]
-    fn parse_str_assign() {
-        let tokens = tokenize("let x: str = \"Hello World!\";");
+    fn parser_minus_is_binary_after_an_operand() {
+        let sub = |left: Exp, right: Exp| op(Operation::Subtract, left, right);
+        let neg = |exp: Exp| Exp::Unary(UnaryOp::Neg, Box::new(exp));
         assert_eq!(
-            parse(tokens),
-            ast(Assignment(
-                Exp::Var(name::String::from("x"), Type::Str, value::None),
-                Str("Hello World!")
+            parse(tokenize("a-1").unwrap()),
+            ast(sub(var("a"), Exp::Int(1)))
+        );
+        assert_eq!(
+            parse(tokenize("a--1").unwrap()),
+            ast(sub(var("a"), Exp::Int(-1)))
+        );
+        assert_eq!(
+            parse(tokenize("-a-1").unwrap()),
+            ast(sub(neg(var("a")), Exp::Int(1)))
+        );
+        assert_eq!(
+            parse(tokenize("a - -b").unwrap()),
+            ast(sub(var("a"), neg(var("b"))))
+        );
+        assert_eq!(
+            parse(tokenize("f(a)-1").unwrap()),
+            ast(sub(
+                Exp::Call(Box::new(var("f")), vec![var("a")]),
+                Exp::Int(1)
             ))
         );
     }
 
-    //While loop tests
     #[test]
-    fn parse_while() {
-        let tokens = tokenize("while (x <= 9) { return true; }");
+    fn parser_prefix_binds_tighter_than_binary() {
+        let not = |exp: Exp| Exp::Unary(UnaryOp::Not, Box::new(exp));
         assert_eq!(
-            parse(tokens),
-            ast(
-                While(Op(
-                    Operation::LessEqual,
-                    left(Exp::Var(x)),
-                    right(Exp::Int(9))
-                )),
-                Return(Exp::Bool(true))
-            )
+            parse(tokenize("!a && b").unwrap()),
+            ast(op(Operation::And, not(var("a")), var("b")))
+        );
+        assert_eq!(
+            parse(tokenize("-a * b").unwrap()),
+            ast(op(
+                Operation::Multiply,
+                Exp::Unary(UnaryOp::Neg, Box::new(var("a"))),
+                var("b")
+            ))
         );
     }
 
-    //For loop tests
     #[test]
-    fn parse_for_loop() {
-        let tokens = tokenize("for x in(9)");
+    // let x: Int = 1;
+    fn parser_int_variable_assignment() {
+        let tokens = tokenize("let x: int = 1;").unwrap();
+        assert_eq!(parse(tokens), ast_stmt(assign("x", Type::Int, Exp::Int(1))))
+    }
+
+    //Integer assignment with order of operations
+    #[test]
+    fn parse_int_assign_1() {
+        let tokens = tokenize("let x: int = 1 + 2;").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(For(
-                iter(Var::Var(name::String::from("x"), Type::Int, value::Int(0))),
-                list(ListDef::Type(Type::Int))
+            ast_stmt(assign(
+                "x",
+                Type::Int,
+                op(Operation::Add, Exp::Int(1), Exp::Int(2))
             ))
         );
     }
 
-    //If Else tests
     #[test]
-    fn parse_if() {
-        let tokens = tokenize("if (x < 5) { return true; }");
+    fn parse_int_assign_2() {
+        let tokens = tokenize("let x: int = 1 - 2;").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(If(
-                condition(Exp::Op(
-                    Operation::LessThan,
-                    left(Exp::Var(x)),
-                    right(Exp::Int(5))
-                )),
-                true_branch(Return(Exp::Bool(true)))
+            ast_stmt(assign(
+                "x",
+                Type::Int,
+                op(Operation::Subtract, Exp::Int(1), Exp::Int(2))
             ))
         );
     }
 
     #[test]
-    fn parse_if_else() {
-        let tokens = tokenize("if (x < 5) { return true; } else { return false; }");
+    fn parse_int_assign_3() {
+        let tokens = tokenize("let x: int = 4 * 5;").unwrap();
         assert_eq!(
             parse(tokens),
-            ast(If(
-                condition(Exp::Op(
-                    Operation::LessThan,
-                    left(Exp::Var(x)),
-                    right(Exp::Int(5))
-                )),
-                true_branch(Return(Exp::Bool(true))),
-                flase_branch(Return(Exp::Bool(false)))
+            ast_stmt(assign(
+                "x",
+                Type::Int,
+                op(Operation::Multiply, Exp::Int(4), Exp::Int(5))
             ))
         );
     }
+
+    #[test]
+    fn parse_int_assign_4() {
+        let tokens = tokenize("let x: int = 9 / 3;").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                Type::Int,
+                op(Operation::Divide, Exp::Int(9), Exp::Int(3))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_int_assign_5() {
+        let tokens = tokenize("let x: int = 1 + 2 * 3;").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                Type::Int,
+                op(
+                    Operation::Add,
+                    Exp::Int(1),
+                    op(Operation::Multiply, Exp::Int(2), Exp::Int(3))
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_int_assign_6() {
+        let tokens = tokenize("let x: int = 2 * 3 + 8 / 2;").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                Type::Int,
+                op(
+                    Operation::Add,
+                    op(Operation::Multiply, Exp::Int(2), Exp::Int(3)),
+                    op(Operation::Divide, Exp::Int(8), Exp::Int(2))
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_reassign() {
+        let tokens = tokenize("x = x + 1;").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(Statement::Assignment(
+                Var::new("x", None),
+                op(Operation::Add, var("x"), Exp::Int(1))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_float_assign() {
+        let tokens = tokenize("let x: float = -1.5 * 2e3;").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                Type::Float,
+                op(Operation::Multiply, Exp::Float(-1.5), Exp::Float(2e3))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_bigint_assign() {
+        let tokens = tokenize("let x: bigint = -99999999999999999999n + 1;").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                Type::BigInt,
+                op(
+                    Operation::Add,
+                    Exp::BigInt("-99999999999999999999".parse().unwrap()),
+                    Exp::Int(1)
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_64_bit_int() {
+        assert_eq!(
+            parse(tokenize("-3000000000").unwrap()),
+            ast(Exp::Int(-3_000_000_000))
+        );
+    }
+
+    #[test]
+    fn parse_float_list_assign() {
+        let tokens = tokenize("let x: [float] = [0.5, 1.0];").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                list_of(Type::Float),
+                Exp::List(
+                    Some(list_of(Type::Float)),
+                    vec![Exp::Float(0.5), Exp::Float(1.0)]
+                )
+            ))
+        );
+    }
+
+    //String assignments
+    #[test]
+    fn parse_str_assign() {
+        let tokens = tokenize("let x: str = \"Hello World!\";").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                Type::Str,
+                Exp::Str(String::from("Hello World!"))
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_void_assign() {
+        let tokens = tokenize("let x: void = ();").unwrap();
+        assert_eq!(parse(tokens), ast_stmt(assign("x", Type::Void, Exp::Void)));
+    }
+
+    //Data structure assignments
+    #[test]
+    fn parse_list_assign() {
+        let tokens = tokenize("let x: [int] = [32, 17, -5,];").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                list_of(Type::Int),
+                Exp::List(
+                    Some(list_of(Type::Int)),
+                    vec![Exp::Int(32), Exp::Int(17), Exp::Int(-5)]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_nested_list_assign() {
+        let tokens = tokenize("let x: [[int]] = [[1, 2], []];").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                list_of(list_of(Type::Int)),
+                Exp::List(
+                    Some(list_of(list_of(Type::Int))),
+                    vec![
+                        Exp::List(Some(list_of(Type::Int)), vec![Exp::Int(1), Exp::Int(2)]),
+                        Exp::List(Some(list_of(Type::Int)), vec![]),
+                    ]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_nested_list_types() {
+        let tokens = tokenize("let x: [[[Point]]] = []; let f: [(int -> [str])] = [];").unwrap();
+        let points = list_of(list_of(list_of(Type::Struct(String::from("Point")))));
+        let funcs = list_of(Type::HigherOrderFunction(
+            vec![Type::Int],
+            Box::new(list_of(Type::Str)),
+        ));
+        assert_eq!(
+            parse(tokens),
+            Ok(Program {
+                items: vec![
+                    Item::Global(
+                        Var::new("x", Some(points.clone())),
+                        Exp::List(Some(points), vec![])
+                    ),
+                    Item::Global(
+                        Var::new("f", Some(funcs.clone())),
+                        Exp::List(Some(funcs), vec![])
+                    ),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_list_of_structs_assign() {
+        // anonymous structs in a list take their name from the declared type
+        let tokens = tokenize("let x: [Point] = [{x: 1,}];").unwrap();
+        let point = Type::Struct(String::from("Point"));
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                list_of(point.clone()),
+                Exp::List(
+                    Some(list_of(point)),
+                    vec![Exp::Struct(
+                        String::from("Point"),
+                        vec![Var {
+                            name: String::from("x"),
+                            var_type: None,
+                            value: Some(Exp::Int(1)),
+                            span: Span::default(),
+                        }]
+                    )]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_struct_assign() {
+        let tokens = tokenize("let x: ex = {bar: 32, baz: \"Hi\",};").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                Type::Struct(String::from("ex")),
+                Exp::Struct(
+                    String::from("ex"),
+                    vec![
+                        Var {
+                            name: String::from("bar"),
+                            var_type: None,
+                            value: Some(Exp::Int(32)),
+                            span: Span::default(),
+                        },
+                        Var {
+                            name: String::from("baz"),
+                            var_type: None,
+                            value: Some(Exp::Str(String::from("Hi"))),
+                            span: Span::default(),
+                        },
+                    ]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_higher_order_func_assign() {
+        let tokens = tokenize("let x: (int -> int) = (a) { return 1 + a; };").unwrap();
+        let hof_type = Type::HigherOrderFunction(vec![Type::Int], Box::new(Type::Int));
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                hof_type,
+                Exp::HOF(
+                    vec![Var::new("a", Some(Type::Int))],
+                    vec![Statement::Return(
+                        op(Operation::Add, Exp::Int(1), var("a")),
+                        Span::default()
+                    )]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_multi_arg_higher_order_func_assign() {
+        let tokens = tokenize("let x: (int, float) -> float = (a, b) { return a * b; };").unwrap();
+        let hof_type =
+            Type::HigherOrderFunction(vec![Type::Int, Type::Float], Box::new(Type::Float));
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                hof_type,
+                Exp::HOF(
+                    vec![
+                        Var::new("a", Some(Type::Int)),
+                        Var::new("b", Some(Type::Float))
+                    ],
+                    vec![Statement::Return(
+                        op(Operation::Multiply, var("a"), var("b")),
+                        Span::default()
+                    )]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_zero_arg_higher_order_func() {
+        let tokens = tokenize("let x: () -> int = () { return 1; };").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(assign(
+                "x",
+                Type::HigherOrderFunction(vec![], Box::new(Type::Int)),
+                Exp::HOF(
+                    vec![],
+                    vec![Statement::Return(Exp::Int(1), Span::default())]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_function_types() {
+        let parse_only_type = |source: &str| {
+            let tokens = tokenize(source).unwrap();
+            let spans = vec![Span::default(); tokens.len() + 1];
+            parse_type(&tokens, &Spans::new(&spans)).map(|(t, remainder)| {
+                assert!(remainder.is_empty());
+                t
+            })
+        };
+        let hof =
+            |params: Vec<Type>, output: Type| Type::HigherOrderFunction(params, Box::new(output));
+        let int_to_int = hof(vec![Type::Int], Type::Int);
+
+        assert_eq!(parse_only_type("(int -> int)"), Ok(int_to_int.clone()));
+        assert_eq!(parse_only_type("(int) -> int"), Ok(int_to_int.clone()));
+        assert_eq!(
+            parse_only_type("(int, str,) -> bool"),
+            Ok(hof(vec![Type::Int, Type::Str], Type::Bool))
+        );
+        assert_eq!(
+            parse_only_type("((int) -> int, [int]) -> [int]"),
+            Ok(hof(
+                vec![int_to_int.clone(), list_of(Type::Int)],
+                list_of(Type::Int)
+            ))
+        );
+        // the output of a function type can itself be a function
+        assert_eq!(
+            parse_only_type("(int) -> (int) -> int"),
+            Ok(hof(vec![Type::Int], int_to_int.clone()))
+        );
+        assert_eq!(parse_only_type("((int) -> int)"), Ok(int_to_int));
+        assert!(parse_only_type("(int, int)").is_err());
+    }
+
+    //Struct tests
+    #[test]
+    fn parse_struct_definition() {
+        let tokens = tokenize("struct Point { x: int, y: [float], }").unwrap();
+        assert_eq!(
+            parse(tokens),
+            Ok(Program {
+                items: vec![Item::Struct(StructDef {
+                    name: String::from("Point"),
+                    fields: vec![
+                        Var::new("x", Some(Type::Int)),
+                        Var::new("y", Some(list_of(Type::Float))),
+                    ],
+                    span: Span::default(),
+                })]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_struct_definition_only_at_top_level() {
+        let tokens = tokenize("func f() { struct Point { x: int } }").unwrap();
+        assert_eq!(
+            parse(tokens).map_err(|error| error.message),
+            Err("Structs can only be declared at the top level")
+        );
+    }
+
+    fn parse_source(source: &str) -> Result<Program, ParseError> {
+        parse_spanned(tokenize_spanned(source, 0, Comments::Strip).unwrap())
+    }
+
+    #[test]
+    fn parse_errors_have_spans() {
+        let error = parse_source("let x: int = 1;\nlet y: int = x +;").unwrap_err();
+        assert_eq!(error.message, "Expected an expression");
+        assert_eq!((error.span.line, error.span.column), (2, 17));
+        assert_eq!(error.to_string(), "2:17: Expected an expression");
+
+        // running out of input is reported at the last token
+        let error = parse_source("func f() {\n  print(1);").unwrap_err();
+        assert_eq!(error.message, "Expected '}' to close the block");
+        assert_eq!((error.span.line, error.span.column), (2, 11));
+
+        // the iterable of a for loop is parsed on its own
+        let error = parse_source("for x in xs ys {}").unwrap_err();
+        assert_eq!(error.message, "Expected '{' after the loop's iterable");
+        assert_eq!((error.span.line, error.span.column), (1, 13));
+    }
+
+    #[test]
+    fn parse_nesting_limit() {
+        let nested_at = |source: &str| {
+            let error = parse_source(source).unwrap_err();
+            assert_eq!(error.message, "Nested too deeply");
+            (error.span.line, error.span.column)
+        };
+        // long chains of operators nest as deep as brackets do
+        let sum = vec!["1"; 20000].join("+");
+        assert_eq!(nested_at(&sum), (1, 1000));
+        let parens = format!("{}1{}", "(".repeat(3000), ")".repeat(3000));
+        assert_eq!(nested_at(&parens), (1, 501));
+        let negated = format!("{}1", "-".repeat(20000));
+        assert_eq!(nested_at(&negated), (1, 501));
+        let list = format!("let l: {}int{} = [];", "[".repeat(600), "]".repeat(600));
+        assert_eq!(nested_at(&list), (1, 508));
+
+        let sum = vec!["1"; 400].join("+");
+        assert!(parse_source(&sum).is_ok());
+    }
+
+    #[test]
+    fn parse_int_min() {
+        let parsed = |source: &str| parse(tokenize(source).unwrap());
+        for source in ["-9223372036854775808", "-0x8000_0000_0000_0000"] {
+            assert_eq!(parsed(source), ast(Exp::Int(i64::MIN)), "{}", source);
+        }
+        assert_eq!(
+            parsed("- -9223372036854775808"),
+            ast(Exp::Unary(UnaryOp::Neg, Box::new(Exp::Int(i64::MIN))))
+        );
+        // only a unary minus makes 2^63 fit
+        for source in [
+            "9223372036854775808",
+            "1 -9223372036854775808",
+            "-(9223372036854775808)",
+        ] {
+            let error = parsed(source).unwrap_err();
+            assert_eq!(
+                error.message,
+                "Integer literal does not fit in an int, use the n suffix for a bigint"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_nodes_have_spans() {
+        let program = parse_source("let x: int = 1;\nfunc f(a: int) {\n  return a;\n}").unwrap();
+        let at = |span: Span| (span.line, span.column);
+        match &program.items[..] {
+            [Item::Global(var, _), Item::Function(function)] => {
+                assert_eq!(at(var.span), (1, 5));
+                assert_eq!(at(function.span), (2, 1));
+                assert_eq!(at(function.param[0].span), (2, 8));
+                match &function.block[..] {
+                    [Statement::Return(Exp::Var(var), span)] => {
+                        assert_eq!(at(*span), (3, 3));
+                        assert_eq!(at(var.span), (3, 10));
+                    }
+                    block => panic!("unexpected block {:?}", block),
+                }
+            }
+            items => panic!("unexpected items {:?}", items),
+        }
+    }
+
+    #[test]
+    fn parse_field_access() {
+        let tokens = tokenize("-line.start.x + 1").unwrap();
+        let field = |exp: Exp, name: &str| Exp::Field(Box::new(exp), String::from(name));
+        assert_eq!(
+            parse(tokens),
+            ast(op(
+                Operation::Add,
+                Exp::Unary(
+                    UnaryOp::Neg,
+                    Box::new(field(field(var("line"), "start"), "x"))
+                ),
+                Exp::Int(1)
+            ))
+        );
+    }
+
+    //Function tests
+    #[test]
+    fn parse_function_definition() {
+        let tokens = tokenize(
+            "func bad_adder(a: int, b: int,) -> int {
+                let result: int = a + b;
+                return result;
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(Statement::Function(Function {
+                name: String::from("bad_adder"),
+                param: vec![
+                    Var::new("a", Some(Type::Int)),
+                    Var::new("b", Some(Type::Int))
+                ],
+                output: Var::new("bad_adder", Some(Type::Int)),
+                block: vec![
+                    assign("result", Type::Int, op(Operation::Add, var("a"), var("b"))),
+                    Statement::Return(var("result"), Span::default()),
+                ],
+                span: Span::default(),
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_function_call() {
+        let tokens = tokenize("foo(x, y);").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast(Exp::Call(Box::new(var("foo")), vec![var("x"), var("y")]))
+        );
+    }
+
+    #[test]
+    fn parse_call_with_expression_args() {
+        let tokens = tokenize("foo(x + 1, bar(2),)").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast(Exp::Call(
+                Box::new(var("foo")),
+                vec![
+                    op(Operation::Add, var("x"), Exp::Int(1)),
+                    Exp::Call(Box::new(var("bar")), vec![Exp::Int(2)]),
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_call_any_callee() {
+        // a returned closure, a field holding a function, and a lambda
+        let tokens = tokenize("make_adder(1)(2); ops.add(x); (a) { return a; }(3)").unwrap();
+        let items = parse(tokens).unwrap().items;
+        let call = |callee: Exp, arg: Exp| {
+            Item::Statement(Statement::Exp(
+                Exp::Call(Box::new(callee), vec![arg]),
+                Span::default(),
+            ))
+        };
+        assert_eq!(
+            items,
+            vec![
+                call(
+                    Exp::Call(Box::new(var("make_adder")), vec![Exp::Int(1)]),
+                    Exp::Int(2)
+                ),
+                call(
+                    Exp::Field(Box::new(var("ops")), String::from("add")),
+                    var("x")
+                ),
+                call(
+                    Exp::HOF(
+                        vec![Var::new("a", None)],
+                        vec![Statement::Return(var("a"), Span::default())]
+                    ),
+                    Exp::Int(3)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_unclosed_call() {
+        assert!(parse(tokenize("foo(x, y").unwrap()).is_err());
+        assert!(parse(tokenize("foo(x y)").unwrap()).is_err());
+    }
+
+    //While loop tests
+    #[test]
+    fn parse_while() {
+        let tokens = tokenize("while (x <= 9) { return true; }").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(Statement::While(WhileStatement {
+                condition: op(Operation::LessEqual, var("x"), Exp::Int(9)),
+                block: vec![Statement::Return(Exp::Bool(true), Span::default())],
+                span: Span::default(),
+            }))
+        );
+    }
+
+    //For loop tests
+    #[test]
+    fn parse_for_loop() {
+        let tokens = tokenize("for x in [9] { return x; }").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(Statement::For(ForStatement {
+                iter: Var::new("x", Some(Type::Int)),
+                iterable: Exp::List(Some(list_of(Type::Int)), vec![Exp::Int(9)]),
+                block: vec![Statement::Return(var("x"), Span::default())],
+                span: Span::default(),
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_for_over_variable() {
+        let tokens = tokenize("for row in rows { print(row); }").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(Statement::For(ForStatement {
+                iter: Var::new("row", None),
+                iterable: var("rows"),
+                block: vec![Statement::Exp(
+                    Exp::Call(Box::new(var("print")), vec![var("row")]),
+                    Span::default()
+                )],
+                span: Span::default(),
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_for_over_expression() {
+        let tokens = tokenize("for x in (load_data()) { }").unwrap();
+        let load_data = Exp::Call(Box::new(var("load_data")), vec![]);
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(Statement::For(ForStatement {
+                iter: Var::new("x", None),
+                iterable: load_data.clone(),
+                block: vec![],
+                span: Span::default(),
+            }))
+        );
+
+        // braces inside brackets belong to the iterable, not the block
+        let tokens = tokenize("for p in [Point {x: 1,}] { } for y in data.rows |> f { }").unwrap();
+        let point = Exp::Struct(
+            String::from("Point"),
+            vec![Var {
+                name: String::from("x"),
+                var_type: None,
+                value: Some(Exp::Int(1)),
+                span: Span::default(),
+            }],
+        );
+        let pipe = Exp::Pipe(
+            Box::new(Exp::Field(Box::new(var("data")), String::from("rows"))),
+            Box::new(var("f")),
+        );
+        assert_eq!(
+            parse(tokens),
+            Ok(Program {
+                items: vec![
+                    Item::Statement(Statement::For(ForStatement {
+                        iter: Var::new("p", Some(Type::Struct(String::from("Point")))),
+                        iterable: Exp::List(
+                            Some(list_of(Type::Struct(String::from("Point")))),
+                            vec![point]
+                        ),
+                        block: vec![],
+                        span: Span::default(),
+                    })),
+                    Item::Statement(Statement::For(ForStatement {
+                        iter: Var::new("y", None),
+                        iterable: pipe,
+                        block: vec![],
+                        span: Span::default(),
+                    })),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_for_errors() {
+        assert!(parse(tokenize("for x in { }").unwrap()).is_err());
+        assert!(parse(tokenize("for x in xs ys { }").unwrap()).is_err());
+        assert!(parse(tokenize("for x in xs").unwrap()).is_err());
+    }
+
+    //If Else tests
+    #[test]
+    fn parse_if() {
+        let tokens = tokenize("if (x < 5) { return true; }").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(Statement::If(IfStatement {
+                condition: op(Operation::LessThan, var("x"), Exp::Int(5)),
+                true_branch: vec![Statement::Return(Exp::Bool(true), Span::default())],
+                false_branch: None,
+                span: Span::default(),
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_if_else() {
+        let tokens = tokenize("if (x < 5) { return true; } else { return false; }").unwrap();
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(Statement::If(IfStatement {
+                condition: op(Operation::LessThan, var("x"), Exp::Int(5)),
+                true_branch: vec![Statement::Return(Exp::Bool(true), Span::default())],
+                false_branch: Some(vec![Statement::Return(Exp::Bool(false), Span::default())]),
+                span: Span::default(),
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_if_elif() {
+        let tokens = tokenize(
+            "if (x < 5) { return 1; } elif (x < 10) { return 2; } elif (x < 20) { return 3; } else { return 4; }",
+        )
+        .unwrap();
+        let branch = |condition: Exp, value: i64, false_branch: Option<Vec<Statement>>| {
+            Statement::If(IfStatement {
+                condition,
+                true_branch: vec![Statement::Return(Exp::Int(value), Span::default())],
+                false_branch,
+                span: Span::default(),
+            })
+        };
+        let x_below = |n: i64| op(Operation::LessThan, var("x"), Exp::Int(n));
+        assert_eq!(
+            parse(tokens),
+            ast_stmt(branch(
+                x_below(5),
+                1,
+                Some(vec![branch(
+                    x_below(10),
+                    2,
+                    Some(vec![branch(
+                        x_below(20),
+                        3,
+                        Some(vec![Statement::Return(Exp::Int(4), Span::default())])
+                    )])
+                )])
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_elif_same_as_else_if() {
+        let elif = tokenize("if (a) { x = 1; } elif (b) { x = 2; } elif (c) { x = 3; }").unwrap();
+        let else_if =
+            tokenize("if (a) { x = 1; } else if (b) { x = 2; } else if (c) { x = 3; }").unwrap();
+        assert_eq!(parse(elif), parse(else_if));
+    }
+
+    #[test]
+    fn parse_elif_after_else() {
+        assert!(parse(tokenize("if (a) { } else { } elif (b) { }").unwrap()).is_err());
+        assert!(parse(tokenize("elif (b) { }").unwrap()).is_err());
+    }
+
+    //Program tests
+    #[test]
+    fn parse_multiple_statements() {
+        let tokens = tokenize("let x: int = 1; x = 2;").unwrap();
+        assert_eq!(
+            parse(tokens),
+            Ok(Program {
+                items: vec![
+                    Item::Global(Var::new("x", Some(Type::Int)), Exp::Int(1)),
+                    Item::Statement(Statement::Assignment(Var::new("x", None), Exp::Int(2))),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_top_level_items() {
+        let tokens = tokenize(
+            "struct Point { x: int, }
+            func f() { let y: int = 1; }
+            print(x);",
+        )
+        .unwrap();
+        let program = parse(tokens).unwrap();
+        assert!(matches!(program.items[0], Item::Struct(_)));
+        assert!(matches!(program.items[1], Item::Function(_)));
+        assert!(matches!(
+            program.items[2],
+            Item::Statement(Statement::Exp(Exp::Call(..), _))
+        ));
+        // lets inside a function stay statements
+        assert!(matches!(
+            program.function("f").unwrap().block[0],
+            Statement::Assignment(..)
+        ));
+    }
+
+    #[test]
+    fn parse_deep_program() {
+        // long programs used to be a linked list that overflowed the stack on drop
+        let source = "x = x + 1;\n".repeat(100_000);
+        let program = parse(tokenize(&source).unwrap()).unwrap();
+        assert_eq!(program.items.len(), 100_000);
+    }
+
+    #[test]
+    fn parse_skips_comments() {
+        let tokens = vec![
+            Token::Comment(String::from(" one")),
+            Token::Int(1),
+            Token::Comment(String::from(" done")),
+        ];
+        assert_eq!(parse(tokens), ast(Exp::Int(1)));
+    }
+
+    #[test]
+    fn parse_nothing() {
+        assert_eq!(parse(vec![]), Ok(Program { items: vec![] }));
+    }
+
+    //Error tests
+    #[test]
+    fn parse_missing_semicolon() {
+        assert!(parse(tokenize("let x: int = 1").unwrap()).is_err());
+    }
+
+    #[test]
+    fn parse_unclosed_block() {
+        assert!(parse(tokenize("while (true) { x = 1;").unwrap()).is_err());
+    }
+
+    #[test]
+    fn parse_dangling_operator() {
+        assert!(parse(tokenize("1 +").unwrap()).is_err());
+    }
+
+    #[test]
+    fn parse_missing_commas() {
+        let cases = [
+            (
+                "func f(a: int b: int) {}",
+                "Expected ',' or ')' after parameter",
+            ),
+            (
+                "struct P { x: int y: int }",
+                "Expected ',' or '}' after field",
+            ),
+            (
+                "let p: P = P {x: 1 y: 2};",
+                "Expected ',' or '}' after field",
+            ),
+            (
+                "let f: (int, int) -> int = (a, b c) { return a; };",
+                "Expected ',' or ')' after parameter",
+            ),
+            (
+                "let f: (int) -> int = (a: int b) { return a; };",
+                "Expected ',' or ')' after parameter",
+            ),
+            (
+                "let f: (int int) -> int = (a) { return a; };",
+                "Expected ',', ')' or '->' in function type",
+            ),
+        ];
+        for (source, message) in cases {
+            let error = parse(tokenize(source).unwrap()).unwrap_err();
+            assert_eq!(error.message, message, "{}", source);
+        }
+    }
 }
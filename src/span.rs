@@ -0,0 +1,14 @@
+// identifies which source file a span points into, for when we read more than one
+pub type FileId = usize;
+
+// a location in the source: the byte range [start, end) plus the line and
+// column (both starting at 1) of its first character. the default span, on
+// line 0, is for nodes that didn't come from any source
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
@@ -1,4 +1,7 @@
+mod error;
 mod token;
+#[allow(clippy::module_inception)]
 pub mod tokenizer;
 
-pub use token::Token;
+pub use error::{LexError, LexErrorKind};
+pub use token::{SpannedToken, Token};
@@ -0,0 +1,63 @@
+use crate::span::Span;
+use std::fmt;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum LexErrorKind {
+    UnterminatedString,        // "abc
+    UnterminatedComment,       // #[ abc
+    InvalidEscape(String),     // "\q"
+    UnknownCharacter(char),    // ?
+    IntegerOverflow(String),   // 9223372036854775809
+    FloatOverflow(String),     // 1e999
+    InvalidDigit(char, u32),   // 0b102
+    MissingDigits(String),     // 0x
+    InvalidSuffix(String),     // 10i7
+    UnsupportedSuffix(String), // 1.0f32
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: ", self.span.line, self.span.column)?;
+        match &self.kind {
+            LexErrorKind::UnterminatedString => write!(f, "no closing double-quote for string"),
+            LexErrorKind::UnterminatedComment => write!(f, "no closing ]# for block comment"),
+            LexErrorKind::InvalidEscape(escape) => write!(f, "invalid escape sequence {}", escape),
+            LexErrorKind::UnknownCharacter(c) => write!(f, "unknown character '{}'", c),
+            LexErrorKind::IntegerOverflow(literal) => {
+                write!(
+                    f,
+                    "integer literal {} does not fit in an int, use the n suffix for a bigint",
+                    literal
+                )
+            }
+            LexErrorKind::FloatOverflow(literal) => {
+                write!(f, "float literal {} does not fit in a float", literal)
+            }
+            LexErrorKind::InvalidDigit(digit, radix) => {
+                write!(f, "invalid digit '{}' in base {} literal", digit, radix)
+            }
+            LexErrorKind::MissingDigits(literal) => {
+                write!(f, "number literal {} has no digits", literal)
+            }
+            LexErrorKind::InvalidSuffix(suffix) => write!(
+                f,
+                "unknown number suffix {}, expected i64, f64 or n",
+                suffix
+            ),
+            LexErrorKind::UnsupportedSuffix(suffix) => {
+                let instead = if suffix.starts_with('f') {
+                    "floats are always f64"
+                } else {
+                    "ints are always i64"
+                };
+                write!(f, "unsupported number suffix {}, {}", suffix, instead)
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
+use crate::span::Span;
 use crate::types::Type;
+use num_bigint::BigInt;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     If,   // if
     Elif, // elif
@@ -16,13 +18,15 @@ pub enum Token {
     Assign, // =
 
     TypeName(Type),
-    Struct,      // struct
-    Function,    // func
-    Int(i32),    // int
-    Bool(bool),  // bool
-    Str(String), // str
+    Struct,         // struct
+    Function,       // func
+    Int(i64),       // int
+    BigInt(BigInt), // bigint
+    Float(f64),     // float
+    Bool(bool),     // bool
+    Str(String),    // str
     Var(String),
-    Comment(String), // #
+    Comment(String), // # or #[ ]#
 
     LeftCurly,  // {
     LeftBrace,  // [
@@ -42,9 +46,10 @@ pub enum Token {
     Multiply, // *
     Modulo,   // %
 
-    And, // &&
-    Or,  // ||
-    Not, // !
+    And,  // &&
+    Or,   // ||
+    Pipe, // |>
+    Not,  // !
 
     GreaterThan,  // >
     LessThan,     // <
@@ -53,3 +58,9 @@ pub enum Token {
     Equal,        // ==
     NotEqual,     // !=
 }
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
@@ -1,67 +1,238 @@
-use super::token::Token;
+use super::error::{LexError, LexErrorKind};
+use super::token::{SpannedToken, Token};
+use crate::span::{FileId, Span};
 use crate::types::Type;
+use num_bigint::BigInt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+use unicode_xid::UnicodeXID;
+
+// whether comments are kept as Token::Comment (for formatters and doc tools)
+// or dropped before they reach the parser
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Comments {
+    Keep,
+    Strip,
+}
 
-pub fn tokenize(input: &str) -> Vec<Token> {
-    let mut tokens: Vec<Token> = vec![];
-    let mut input = input;
+// convenience for when locations don't matter, e.g. the tests
+pub fn tokenize(input: &str) -> Result<Vec<Token>, Vec<LexError>> {
+    let tokens = tokenize_spanned(input, 0, Comments::Strip)?;
+    Ok(tokens.into_iter().map(|spanned| spanned.token).collect())
+}
 
-    while input != "" {
+// keeps going after an error so every problem in the input is reported at once
+pub fn tokenize_spanned(
+    source: &str,
+    file: FileId,
+    comments: Comments,
+) -> Result<Vec<SpannedToken>, Vec<LexError>> {
+    let mut tokens: Vec<SpannedToken> = vec![];
+    let mut errors: Vec<LexError> = vec![];
+    let mut lines = LineTracker::new(source);
+    let mut input = source;
+
+    while !input.is_empty() {
         input = skip_whitespace(input);
-        if input == "" {
-            return tokens;
+        if input.is_empty() {
+            break;
         }
-        let cursor = char::from(input.as_bytes()[0]);
-
-        if cursor == '\"' {
-            if let Some((token, remainder)) = tokenize_str(&input[1..]) {
-                tokens.push(token);
-                input = remainder;
-            } else {
-                panic!("No closing double-quote: {}", input)
+        let start = source.len() - input.len();
+        let cursor = match input.chars().next() {
+            Some(c) => c,
+            None => break,
+        };
+
+        let (result, remainder) = if cursor == '\"' {
+            match tokenize_str(&input[1..]) {
+                Ok((token, remainder)) => (Ok(token), remainder),
+                Err((kind, remainder)) => (Err(kind), remainder),
             }
-        } else if cursor.is_alphanumeric() || cursor == '_' {
+        } else if let Some(hashes) = raw_str_hashes(input) {
+            match tokenize_raw_str(&input[hashes + 2..], hashes) {
+                Some((token, remainder)) => (Ok(token), remainder),
+                // the rest of the input is swallowed by the string
+                None => (Err(LexErrorKind::UnterminatedString), ""),
+            }
+        } else if cursor == '#' {
+            match tokenize_comment(input) {
+                Some((token, remainder)) => (Ok(token), remainder),
+                None => (Err(LexErrorKind::UnterminatedComment), ""),
+            }
+        } else if let Some((literal, suffix, remainder)) = split_float(input) {
+            (tokenize_float(literal, suffix), remainder)
+        } else if is_word_start(cursor) {
             let (candidate, remainder) = split_first_word(input);
-            if let Some(token) = tokenize_int(candidate) {
-                tokens.push(token);
-                input = remainder;
-            } else {
-                tokens.push(tokenize_word(candidate));
-                input = remainder;
+            match tokenize_number(candidate) {
+                Some(result) => (result, remainder),
+                None => (Ok(tokenize_word(candidate)), remainder),
             }
+        } else if let Some(token) = input.get(..2).and_then(tokenize_symbol) {
+            (Ok(token), &input[2..])
+        } else if let Some(token) = input.get(..1).and_then(tokenize_symbol) {
+            (Ok(token), &input[1..])
         } else {
-            if input.len() >= 2 {
-                if let Some(token) = tokenize_symbol(&input[..2]) {
-                    tokens.push(token);
-                    input = &input[2..];
-                    continue;
-                }
-            }
+            (
+                Err(LexErrorKind::UnknownCharacter(cursor)),
+                &input[cursor.len_utf8()..],
+            )
+        };
+
+        let end = source.len() - remainder.len();
+        let (line, column) = lines.locate(start);
+        let span = Span {
+            file,
+            start,
+            end,
+            line,
+            column,
+        };
+        match result {
+            Ok(Token::Comment(_)) if comments == Comments::Strip => {}
+            Ok(token) => tokens.push(SpannedToken { token, span }),
+            Err(kind) => errors.push(LexError { kind, span }),
+        }
+        input = remainder;
+    }
 
-            if input.len() >= 1 {
-                if let Some(token) = tokenize_symbol(&input[..1]) {
-                    tokens.push(token);
-                    input = &input[1..];
-                    continue;
-                }
-            }
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(errors)
+    }
+}
 
-            if input != "" {
-                panic!("Failed to parse: {}", input);
-            }
+// turns byte offsets into line and column numbers, assuming it is asked
+// about offsets in increasing order like the tokenizer does
+struct LineTracker<'a> {
+    source: &'a str,
+    scanned: usize,
+    line: usize,
+    line_start: usize,
+}
+
+impl<'a> LineTracker<'a> {
+    fn new(source: &'a str) -> LineTracker<'a> {
+        LineTracker {
+            source,
+            scanned: 0,
+            line: 1,
+            line_start: 0,
         }
     }
 
-    tokens
+    fn locate(&mut self, offset: usize) -> (usize, usize) {
+        for (i, c) in self.source[self.scanned..offset].char_indices() {
+            if c == '\n' {
+                self.line += 1;
+                self.line_start = self.scanned + i + 1;
+            }
+        }
+        self.scanned = offset;
+
+        let column = self.source[self.line_start..offset].chars().count() + 1;
+        (self.line, column)
+    }
 }
 
 fn tokenize_int(word: &str) -> Option<Token> {
     let word = word.replace("_", "");
-    match word.parse::<i32>() {
+    match word.parse::<i64>() {
         Ok(ok) => Some(Token::Int(ok)),
         Err(_) => None,
     }
 }
 
+// 2^63 only fits in an int once it's negated. it's kept as i64::MIN, which
+// no other literal can be, and the parser reports it anywhere but right
+// after a minus
+fn tokenize_int_min(digits: &str, radix: u32) -> Option<Token> {
+    match u64::from_str_radix(digits, radix) {
+        Ok(value) if value == i64::MIN.unsigned_abs() => Some(Token::Int(i64::MIN)),
+        _ => None,
+    }
+}
+
+// takes a word starting with a digit and returns its number token (or what's
+// wrong with it), or none if the word is really a var name like 1num.
+// numbers may have a 0x, 0o or 0b prefix and an i64, f64 or n (bigint) suffix
+fn tokenize_number(word: &str) -> Option<Result<Token, LexErrorKind>> {
+    if !word.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let (radix, body) = match word.get(..2) {
+        Some("0x") => (16, &word[2..]),
+        Some("0o") => (8, &word[2..]),
+        Some("0b") => (2, &word[2..]),
+        _ => (10, word),
+    };
+
+    // binary and octal take every decimal digit so a stray 9 gets reported
+    let split = body
+        .find(|c: char| c != '_' && !c.is_digit(radix.max(10)))
+        .unwrap_or(body.len());
+    let (digits, suffix) = body.split_at(split);
+
+    let is_suffix = suffix.is_empty() || suffix == "n" || is_suffix_like(suffix);
+    if radix == 10 && !is_suffix {
+        return None;
+    }
+    if let Some(c) = digits.chars().find(|c| *c != '_' && !c.is_digit(radix)) {
+        return Some(Err(LexErrorKind::InvalidDigit(c, radix)));
+    }
+    if !is_suffix {
+        let c = suffix.chars().next().unwrap_or('_');
+        return Some(Err(LexErrorKind::InvalidDigit(c, radix)));
+    }
+
+    let digits = digits.replace("_", "");
+    if digits.is_empty() {
+        return Some(Err(LexErrorKind::MissingDigits(String::from(word))));
+    }
+    let overflow = || LexErrorKind::IntegerOverflow(String::from(word));
+
+    Some(match suffix {
+        "" | "i64" if radix == 10 => tokenize_int(&digits)
+            .or_else(|| tokenize_int_min(&digits, radix))
+            .ok_or_else(overflow),
+        "" | "i64" => i64::from_str_radix(&digits, radix)
+            .ok()
+            .map(Token::Int)
+            .or_else(|| tokenize_int_min(&digits, radix))
+            .ok_or_else(overflow),
+        "n" => BigInt::parse_bytes(digits.as_bytes(), radix)
+            .map(Token::BigInt)
+            .ok_or_else(overflow),
+        "f64" if radix == 10 => tokenize_float(&digits, ""),
+        _ => Err(suffix_error(suffix)),
+    })
+}
+
+// looks like a type suffix such as i64, u8 or f32, whether or not we support it
+fn is_suffix_like(s: &str) -> bool {
+    s.len() > 1 && s.starts_with(['i', 'u', 'f']) && s[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+// the sized number types other languages have are told apart from typos
+fn suffix_error(suffix: &str) -> LexErrorKind {
+    match suffix {
+        "i8" | "i16" | "i32" | "i128" | "u8" | "u16" | "u32" | "u64" | "u128" | "f32" => {
+            LexErrorKind::UnsupportedSuffix(String::from(suffix))
+        }
+        _ => LexErrorKind::InvalidSuffix(String::from(suffix)),
+    }
+}
+
+fn tokenize_float(literal: &str, suffix: &str) -> Result<Token, LexErrorKind> {
+    if !suffix.is_empty() && suffix != "f64" {
+        return Err(suffix_error(suffix));
+    }
+    match literal.replace("_", "").parse::<f64>() {
+        Ok(ok) if ok.is_finite() => Ok(Token::Float(ok)),
+        _ => Err(LexErrorKind::FloatOverflow(String::from(literal))),
+    }
+}
+
 fn tokenize_word(word: &str) -> Token {
     match word {
         "if" => Token::If,
@@ -81,6 +252,8 @@ fn tokenize_word(word: &str) -> Token {
         "false" => Token::Bool(false),
 
         "int" => Token::TypeName(Type::Int),
+        "float" => Token::TypeName(Type::Float),
+        "bigint" => Token::TypeName(Type::BigInt),
         "str" => Token::TypeName(Type::Str),
         "bool" => Token::TypeName(Type::Bool),
         "void" => Token::TypeName(Type::Void),
@@ -117,6 +290,7 @@ fn tokenize_symbol(sym: &str) -> Option<Token> {
         "->" => Some(Token::Output),
         "&&" => Some(Token::And),
         "||" => Some(Token::Or),
+        "|>" => Some(Token::Pipe),
 
         ">=" => Some(Token::GreaterEqual),
         "<=" => Some(Token::LessEqual),
@@ -127,39 +301,207 @@ fn tokenize_symbol(sym: &str) -> Option<Token> {
     }
 }
 
-// takes an input string and returns a string token (or none) and the remainder
-fn tokenize_str(s: &str) -> Option<(Token, &str)> {
-    let bytes = s.as_bytes();
+// takes an input string and returns a string token and the remainder, or
+// the error and where to carry on from. an unterminated string swallows the
+// rest of the input
+fn tokenize_str(s: &str) -> Result<(Token, &str), (LexErrorKind, &str)> {
+    let mut value = String::new();
+    let mut error = None;
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\"' => {
+                let remainder = &s[i + 1..];
+                return match error {
+                    None => Ok((Token::Str(value), remainder)),
+                    Some(kind) => Err((kind, remainder)),
+                };
+            }
+            '\\' => match tokenize_escape(&mut chars) {
+                Ok(c) => value.push(c),
+                // keep going to find the end of the string, but only report
+                // the first bad escape in it
+                Err(escape) => {
+                    error.get_or_insert(LexErrorKind::InvalidEscape(escape));
+                }
+            },
+            _ => value.push(c),
+        }
+    }
+    Err((LexErrorKind::UnterminatedString, ""))
+}
+
+// takes the characters after a backslash and returns the escaped character,
+// or the text of the invalid escape
+fn tokenize_escape(chars: &mut Peekable<CharIndices>) -> Result<char, String> {
+    match chars.next() {
+        Some((_, '"')) => Ok('"'),
+        Some((_, '\\')) => Ok('\\'),
+        Some((_, 'n')) => Ok('\n'),
+        Some((_, 't')) => Ok('\t'),
+        Some((_, 'r')) => Ok('\r'),
+        Some((_, '0')) => Ok('\0'),
+        Some((_, 'u')) => {
+            // \u{1F600}, don't eat a closing quote if the escape is cut short
+            let mut escape = String::from("\\u");
+            if chars.peek().map(|&(_, c)| c) != Some('{') {
+                return Err(escape);
+            }
+            chars.next();
+            escape.push('{');
+
+            let mut digits = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !c.is_ascii_hexdigit() {
+                    break;
+                }
+                digits.push(c);
+                chars.next();
+            }
+            escape.push_str(&digits);
+
+            if chars.peek().map(|&(_, c)| c) != Some('}') {
+                return Err(escape);
+            }
+            chars.next();
+            escape.push('}');
 
-    for (i, &item) in bytes.iter().enumerate() {
-        let c = char::from(item);
+            if digits.is_empty() || digits.len() > 6 {
+                return Err(escape);
+            }
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(std::char::from_u32)
+                .ok_or(escape)
+        }
+        Some((_, c)) => Err(format!("\\{}", c)),
+        None => Err(String::from("\\")),
+    }
+}
+
+// returns how many #s a raw string like r"..." or r#"..."# starts with, or
+// none if the input doesn't start a raw string
+fn raw_str_hashes(s: &str) -> Option<usize> {
+    let rest = s.strip_prefix('r')?;
+    let hashes = rest.len() - rest.trim_start_matches('#').len();
+    if rest[hashes..].starts_with('"') {
+        Some(hashes)
+    } else {
+        None
+    }
+}
 
-        if c == '\"' {
-            return Some((Token::Str(String::from(&s[..i])), &s[i + 1..]));
+// takes the input after the opening quote of a raw string and returns the
+// string token (or none if it is never closed) and the remainder. nothing is
+// escaped; the string ends at the first quote followed by the same number of #s
+fn tokenize_raw_str(s: &str, hashes: usize) -> Option<(Token, &str)> {
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let end = s.find(&closing)?;
+    Some((
+        Token::Str(String::from(&s[..end])),
+        &s[end + closing.len()..],
+    ))
+}
+
+// takes an input starting at '#' and returns a comment token (or none if a
+// block comment is never closed) and the remainder. `# ...` runs to the end of
+// the line and `#[ ... ]#` blocks can be nested
+fn tokenize_comment(s: &str) -> Option<(Token, &str)> {
+    if let Some(body) = s.strip_prefix("#[") {
+        let mut depth = 1;
+        let mut i = 0;
+
+        while i < body.len() {
+            let rest = &body[i..];
+            if rest.starts_with("#[") {
+                depth += 1;
+                i += 2;
+            } else if rest.starts_with("]#") {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((Token::Comment(String::from(&body[..i])), &body[i + 2..]));
+                }
+                i += 2;
+            } else {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
         }
+        None
+    } else {
+        let body = &s[1..];
+        let end = body.find('\n').unwrap_or(body.len());
+        let text = body[..end].trim_end_matches('\r');
+        Some((Token::Comment(String::from(text)), &body[end..]))
     }
-    None
 }
 
-// Takes a string slice and returns a slice containing a word and the remainder
-fn split_first_word(s: &str) -> (&str, &str) {
+// words follow UAX #31 (XID_Start then XID_Continue), except that they may
+// also start with an underscore or a digit so ints and vars like 1num share a path
+fn is_word_start(c: char) -> bool {
+    c.is_xid_start() || c == '_' || c.is_ascii_digit()
+}
+
+// Takes a string slice and returns slices containing a float literal like
+// 3.14, 1e-9 or 2.5E+3, its suffix (if any) and the remainder, or none if it
+// doesn't start with one. plain ints and words like 1num are left to split_first_word
+fn split_float(s: &str) -> Option<(&str, &str, &str)> {
+    if !s.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
     let bytes = s.as_bytes();
+    let is_digit = |i: usize| matches!(bytes.get(i), Some(b) if b.is_ascii_digit());
+    let skip_digits = |mut i: usize| {
+        while is_digit(i) || bytes.get(i) == Some(&b'_') {
+            i += 1;
+        }
+        i
+    };
 
-    for (i, &item) in bytes.iter().enumerate() {
-        let c = char::from(item);
+    let mut end = skip_digits(0);
+    let mut is_float = false;
 
-        if !c.is_alphanumeric() && c != '_' {
+    if bytes.get(end) == Some(&b'.') && is_digit(end + 1) {
+        end = skip_digits(end + 1);
+        is_float = true;
+    }
+    if let Some(b'e') | Some(b'E') = bytes.get(end) {
+        let mut exponent = end + 1;
+        if let Some(b'+') | Some(b'-') = bytes.get(exponent) {
+            exponent += 1;
+        }
+        if is_digit(exponent) {
+            end = skip_digits(exponent);
+            is_float = true;
+        }
+    }
+
+    if !is_float {
+        return None;
+    }
+    let (literal, remainder) = s.split_at(end);
+    let (suffix, remainder) = split_first_word(remainder);
+    if !suffix.is_empty() && !is_suffix_like(suffix) {
+        return None;
+    }
+    Some((literal, suffix, remainder))
+}
+
+// Takes a string slice and returns a slice containing a word and the remainder
+fn split_first_word(s: &str) -> (&str, &str) {
+    for (i, c) in s.char_indices() {
+        if !c.is_xid_continue() {
             return (&s[..i], &s[i..]);
         }
     }
 
-    (&s[..], "")
+    (s, "")
 }
 
 //takes a string slice and returns a slice without leading whitespace
 fn skip_whitespace(s: &str) -> &str {
-    for (i, &item) in s.as_bytes().iter().enumerate() {
-        if !char::from(item).is_whitespace() {
+    for (i, c) in s.char_indices() {
+        if !c.is_whitespace() {
             return &s[i..];
         }
     }
@@ -174,82 +516,87 @@ pub mod tests {
     // ----------- simple input tests ---------- \\
     #[test]
     fn tokenize_nothing() {
-        assert_eq!(tokenize(""), vec![])
+        assert_eq!(tokenize(""), Ok(vec![]))
     }
 
     #[test]
     fn tokenize_keyword_if() {
-        assert_eq!(tokenize("if"), vec![Token::If])
+        assert_eq!(tokenize("if"), Ok(vec![Token::If]))
     }
 
     #[test]
     fn tokenize_keyword_elif() {
-        assert_eq!(tokenize("elif"), vec![Token::Elif])
+        assert_eq!(tokenize("elif"), Ok(vec![Token::Elif]))
     }
 
     #[test]
     fn tokenize_keyword_else() {
-        assert_eq!(tokenize("else"), vec![Token::Else])
+        assert_eq!(tokenize("else"), Ok(vec![Token::Else]))
     }
 
     #[test]
     fn tokenize_keyword_for() {
-        assert_eq!(tokenize("for"), vec![Token::For])
+        assert_eq!(tokenize("for"), Ok(vec![Token::For]))
     }
 
     #[test]
     fn tokenize_keyword_in() {
-        assert_eq!(tokenize("in"), vec![Token::In])
+        assert_eq!(tokenize("in"), Ok(vec![Token::In]))
     }
 
     #[test]
     fn tokenize_keyword_while() {
-        assert_eq!(tokenize("while"), vec![Token::While])
+        assert_eq!(tokenize("while"), Ok(vec![Token::While]))
     }
 
     #[test]
     fn tokenize_keyword_function() {
-        assert_eq!(tokenize("func"), vec![Token::Function])
+        assert_eq!(tokenize("func"), Ok(vec![Token::Function]))
     }
 
     #[test]
     fn tokenize_keyword_output() {
-        assert_eq!(tokenize("->"), vec![Token::Output])
+        assert_eq!(tokenize("->"), Ok(vec![Token::Output]))
     }
 
     #[test]
     fn tokenize_keyword_return() {
-        assert_eq!(tokenize("return"), vec![Token::Return])
+        assert_eq!(tokenize("return"), Ok(vec![Token::Return]))
     }
 
     #[test]
     fn tokenize_keyword_let() {
-        assert_eq!(tokenize("let"), vec![Token::Let])
+        assert_eq!(tokenize("let"), Ok(vec![Token::Let]))
     }
 
     #[test]
     fn tokenize_keyword_assign() {
-        assert_eq!(tokenize("="), vec![Token::Assign])
+        assert_eq!(tokenize("="), Ok(vec![Token::Assign]))
     }
 
     #[test]
     fn tokenize_keyword_type_void() {
-        assert_eq!(tokenize("void"), vec![Token::TypeName(Type::Void)])
+        assert_eq!(tokenize("void"), Ok(vec![Token::TypeName(Type::Void)]))
     }
 
     #[test]
     fn tokenize_keyword_type_int() {
-        assert_eq!(tokenize("int"), vec![Token::TypeName(Type::Int)])
+        assert_eq!(tokenize("int"), Ok(vec![Token::TypeName(Type::Int)]))
+    }
+
+    #[test]
+    fn tokenize_keyword_type_float() {
+        assert_eq!(tokenize("float"), Ok(vec![Token::TypeName(Type::Float)]))
     }
 
     #[test]
     fn tokenize_keyword_type_str() {
-        assert_eq!(tokenize("str"), vec![Token::TypeName(Type::Str)])
+        assert_eq!(tokenize("str"), Ok(vec![Token::TypeName(Type::Str)]))
     }
 
     #[test]
     fn tokenize_keyword_type_bool() {
-        assert_eq!(tokenize("bool"), vec![Token::TypeName(Type::Bool)])
+        assert_eq!(tokenize("bool"), Ok(vec![Token::TypeName(Type::Bool)]))
     }
 
     #[test]
@@ -257,181 +604,419 @@ pub mod tests {
         // not sure about this one
         assert_eq!(
             tokenize("struct foo"),
-            vec![Token::Struct, Token::Var(String::from("foo"))]
+            Ok(vec![Token::Struct, Token::Var(String::from("foo"))])
         )
     }
 
     #[test]
     fn tokenize_keyword_struct() {
-        assert_eq!(tokenize("struct"), vec![Token::Struct])
+        assert_eq!(tokenize("struct"), Ok(vec![Token::Struct]))
     }
 
     #[test]
     fn tokenize_keyword_int() {
-        assert_eq!(tokenize("5"), vec![Token::Int(5)])
+        assert_eq!(tokenize("5"), Ok(vec![Token::Int(5)]))
     }
 
     #[test]
     fn tokenize_keyword_negative_int() {
         // the parser will firgure out that it's a negative int
-        assert_eq!(tokenize("-5"), vec![Token::Minus, Token::Int(5)])
+        assert_eq!(tokenize("-5"), Ok(vec![Token::Minus, Token::Int(5)]))
+    }
+
+    #[test]
+    fn tokenize_subtract_without_spaces() {
+        // the minus is always its own token, so a-1 is never a, -1
+        assert_eq!(
+            tokenize("a-1"),
+            Ok(vec![
+                Token::Var(String::from("a")),
+                Token::Minus,
+                Token::Int(1)
+            ])
+        )
     }
 
     #[test]
     fn tokenize_keyword_true() {
-        assert_eq!(tokenize("true"), vec![Token::Bool(true)])
+        assert_eq!(tokenize("true"), Ok(vec![Token::Bool(true)]))
     }
 
     #[test]
     fn tokenize_keyword_false() {
-        assert_eq!(tokenize("false"), vec![Token::Bool(false)])
+        assert_eq!(tokenize("false"), Ok(vec![Token::Bool(false)]))
     }
 
     #[test]
     fn tokenize_keyword_left_curly() {
-        assert_eq!(tokenize("{"), vec![Token::LeftCurly])
+        assert_eq!(tokenize("{"), Ok(vec![Token::LeftCurly]))
     }
 
     #[test]
     fn tokenize_keyword_right_curly() {
-        assert_eq!(tokenize("}"), vec![Token::RightCurly])
+        assert_eq!(tokenize("}"), Ok(vec![Token::RightCurly]))
     }
 
     #[test]
     fn tokenize_keyword_left_brace() {
-        assert_eq!(tokenize("["), vec![Token::LeftBrace])
+        assert_eq!(tokenize("["), Ok(vec![Token::LeftBrace]))
     }
 
     #[test]
     fn tokenize_keyword_right_brace() {
-        assert_eq!(tokenize("]"), vec![Token::RightBrace])
+        assert_eq!(tokenize("]"), Ok(vec![Token::RightBrace]))
     }
 
     #[test]
     fn tokenize_keyword_left_paren() {
-        assert_eq!(tokenize("("), vec![Token::LeftParen])
+        assert_eq!(tokenize("("), Ok(vec![Token::LeftParen]))
     }
 
     #[test]
     fn tokenize_keyword_right_paren() {
-        assert_eq!(tokenize(")"), vec![Token::RightParen])
+        assert_eq!(tokenize(")"), Ok(vec![Token::RightParen]))
     }
 
     #[test]
     fn tokenize_keyword_dot() {
-        assert_eq!(tokenize("."), vec![Token::Dot])
+        assert_eq!(tokenize("."), Ok(vec![Token::Dot]))
     }
 
     #[test]
     fn tokenize_keyword_comma() {
-        assert_eq!(tokenize(","), vec![Token::Comma])
+        assert_eq!(tokenize(","), Ok(vec![Token::Comma]))
     }
 
     #[test]
     fn tokenize_keyword_colon() {
-        assert_eq!(tokenize(":"), vec![Token::Colon])
+        assert_eq!(tokenize(":"), Ok(vec![Token::Colon]))
     }
 
     #[test]
     fn tokenize_keyword_semicolon() {
-        assert_eq!(tokenize(";"), vec![Token::Semicolon])
+        assert_eq!(tokenize(";"), Ok(vec![Token::Semicolon]))
     }
 
     #[test]
     fn tokenize_keyword_minus() {
-        assert_eq!(tokenize("-"), vec![Token::Minus])
+        assert_eq!(tokenize("-"), Ok(vec![Token::Minus]))
     }
 
     #[test]
     fn tokenize_keyword_plus() {
-        assert_eq!(tokenize("+"), vec![Token::Plus])
+        assert_eq!(tokenize("+"), Ok(vec![Token::Plus]))
     }
 
     #[test]
     fn tokenize_keyword_divide() {
-        assert_eq!(tokenize("/"), vec![Token::Divide])
+        assert_eq!(tokenize("/"), Ok(vec![Token::Divide]))
     }
 
     #[test]
     fn tokenize_keyword_multiply() {
-        assert_eq!(tokenize("*"), vec![Token::Multiply])
+        assert_eq!(tokenize("*"), Ok(vec![Token::Multiply]))
     }
 
     #[test]
     fn tokenize_keyword_modulo() {
-        assert_eq!(tokenize("%"), vec![Token::Modulo])
+        assert_eq!(tokenize("%"), Ok(vec![Token::Modulo]))
     }
 
     #[test]
     fn tokenize_keyword_and() {
-        assert_eq!(tokenize("&&"), vec![Token::And])
+        assert_eq!(tokenize("&&"), Ok(vec![Token::And]))
     }
 
     #[test]
     fn tokenize_keyword_or() {
-        assert_eq!(tokenize("||"), vec![Token::Or])
+        assert_eq!(tokenize("||"), Ok(vec![Token::Or]))
+    }
+
+    #[test]
+    fn tokenize_keyword_pipe() {
+        assert_eq!(tokenize("|>"), Ok(vec![Token::Pipe]))
     }
 
     #[test]
     fn tokenize_keyword_not() {
-        assert_eq!(tokenize("!"), vec![Token::Not])
+        assert_eq!(tokenize("!"), Ok(vec![Token::Not]))
     }
 
     #[test]
     fn tokenize_keyword_greater_than() {
-        assert_eq!(tokenize(">"), vec![Token::GreaterThan])
+        assert_eq!(tokenize(">"), Ok(vec![Token::GreaterThan]))
     }
 
     #[test]
     fn tokenize_keyword_less_than() {
-        assert_eq!(tokenize("<"), vec![Token::LessThan])
+        assert_eq!(tokenize("<"), Ok(vec![Token::LessThan]))
     }
 
     #[test]
     fn tokenize_keyword_greater_equal() {
-        assert_eq!(tokenize(">="), vec![Token::GreaterEqual])
+        assert_eq!(tokenize(">="), Ok(vec![Token::GreaterEqual]))
     }
 
     #[test]
     fn tokenize_keyword_less_equal() {
-        assert_eq!(tokenize("<="), vec![Token::LessEqual])
+        assert_eq!(tokenize("<="), Ok(vec![Token::LessEqual]))
     }
 
     #[test]
     fn tokenize_keyword_equal() {
-        assert_eq!(tokenize("=="), vec![Token::Equal])
+        assert_eq!(tokenize("=="), Ok(vec![Token::Equal]))
     }
 
     #[test]
     fn tokenize_keyword_not_equal() {
-        assert_eq!(tokenize("!="), vec![Token::NotEqual])
+        assert_eq!(tokenize("!="), Ok(vec![Token::NotEqual]))
     }
 
     #[test]
     fn tokenize_weird_int() {
-        assert_eq!(tokenize("1_000_000"), vec![Token::Int(1000000)])
+        assert_eq!(tokenize("1_000_000"), Ok(vec![Token::Int(1000000)]))
+    }
+
+    #[test]
+    fn tokenize_64_bit_ints() {
+        let cases = vec![
+            ("3000000000", 3_000_000_000),
+            ("9_223_372_036_854_775_807", i64::MAX),
+            ("4294967296", 1 << 32),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(tokenize(input), Ok(vec![Token::Int(expected)]), "{}", input)
+        }
+    }
+
+    #[test]
+    fn tokenize_bigints() {
+        let cases = vec![
+            ("0n", "0"),
+            ("42n", "42"),
+            (
+                "123_456_789_012_345_678_901_234_567_890n",
+                "123456789012345678901234567890",
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                tokenize(input),
+                Ok(vec![Token::BigInt(expected.parse().unwrap())]),
+                "{}",
+                input
+            )
+        }
+    }
+
+    #[test]
+    fn tokenize_not_bigints() {
+        assert_eq!(tokenize("n"), Ok(vec![Token::Var(String::from("n"))]));
+        assert_eq!(tokenize("1nn"), Ok(vec![Token::Var(String::from("1nn"))]));
+        assert_eq!(tokenize("x1n"), Ok(vec![Token::Var(String::from("x1n"))]))
+    }
+
+    #[test]
+    fn tokenize_keyword_type_bigint() {
+        assert_eq!(tokenize("bigint"), Ok(vec![Token::TypeName(Type::BigInt)]))
+    }
+
+    #[test]
+    fn tokenize_prefixed_ints() {
+        let cases = vec![
+            ("0x1F", 31),
+            ("0xff_ff", 65535),
+            ("0xDEAD_beef", 0xDEAD_BEEF),
+            ("0b1010", 10),
+            ("0b1111_0000", 240),
+            ("0o17", 15),
+            ("0o7_7_7", 511),
+            ("0x0", 0),
+            ("0x7fff_ffff_ffff_ffff", i64::MAX),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(tokenize(input), Ok(vec![Token::Int(expected)]), "{}", input)
+        }
+    }
+
+    #[test]
+    fn tokenize_suffixed_numbers() {
+        let cases = vec![
+            ("10i64", Token::Int(10)),
+            ("1_000i64", Token::Int(1000)),
+            ("0xFFi64", Token::Int(255)),
+            ("0b101n", Token::BigInt(5.into())),
+            (
+                "0xffff_ffff_ffff_ffff_ffn",
+                Token::BigInt("4722366482869645213695".parse().unwrap()),
+            ),
+            ("0o777n", Token::BigInt(511.into())),
+            ("10f64", Token::Float(10.0)),
+            ("1.5f64", Token::Float(1.5)),
+            ("2e3f64", Token::Float(2000.0)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(tokenize(input), Ok(vec![expected]), "{}", input)
+        }
+    }
+
+    #[test]
+    fn tokenize_unsupported_suffixes() {
+        let message = |source: &str| tokenize(source).unwrap_err()[0].to_string();
+        assert_eq!(
+            message("let x: float = 1.0f32;"),
+            "1:16: unsupported number suffix f32, floats are always f64"
+        );
+        assert_eq!(
+            message("let x: int = 1u8;"),
+            "1:14: unsupported number suffix u8, ints are always i64"
+        );
+    }
+
+    #[test]
+    fn tokenize_hex_f64_is_digits() {
+        // like Rust, f is a hex digit so this is an int, not a suffix
+        assert_eq!(tokenize("0x1f64"), Ok(vec![Token::Int(0x1f64)]))
+    }
+
+    #[test]
+    fn tokenize_bad_number_literals() {
+        let cases = vec![
+            ("0b102", LexErrorKind::InvalidDigit('2', 2)),
+            ("0b12z", LexErrorKind::InvalidDigit('2', 2)),
+            ("0o78", LexErrorKind::InvalidDigit('8', 8)),
+            ("0xG", LexErrorKind::InvalidDigit('G', 16)),
+            ("0x12zz", LexErrorKind::InvalidDigit('z', 16)),
+            ("0b1f64", LexErrorKind::InvalidSuffix(String::from("f64"))),
+            ("0x", LexErrorKind::MissingDigits(String::from("0x"))),
+            ("0b__", LexErrorKind::MissingDigits(String::from("0b__"))),
+            ("0on", LexErrorKind::MissingDigits(String::from("0on"))),
+            ("10i7", LexErrorKind::InvalidSuffix(String::from("i7"))),
+            ("1.0f7", LexErrorKind::InvalidSuffix(String::from("f7"))),
+            (
+                "10i32",
+                LexErrorKind::UnsupportedSuffix(String::from("i32")),
+            ),
+            ("10u8", LexErrorKind::UnsupportedSuffix(String::from("u8"))),
+            (
+                "1.0f32",
+                LexErrorKind::UnsupportedSuffix(String::from("f32")),
+            ),
+            ("1f32", LexErrorKind::UnsupportedSuffix(String::from("f32"))),
+            ("1.5i64", LexErrorKind::InvalidSuffix(String::from("i64"))),
+            (
+                "0x1_0000_0000_0000_0000",
+                LexErrorKind::IntegerOverflow(String::from("0x1_0000_0000_0000_0000")),
+            ),
+            (
+                "9223372036854775809i64",
+                LexErrorKind::IntegerOverflow(String::from("9223372036854775809i64")),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(error_kinds(input), vec![expected], "{}", input)
+        }
+    }
+
+    #[test]
+    fn tokenize_bad_number_keeps_lexing() {
+        assert_eq!(
+            error_kinds("let x: int = 0b2 + 0x; ?"),
+            vec![
+                LexErrorKind::InvalidDigit('2', 2),
+                LexErrorKind::MissingDigits(String::from("0x")),
+                LexErrorKind::UnknownCharacter('?'),
+            ]
+        )
+    }
+
+    #[test]
+    fn tokenize_floats() {
+        let cases = vec![
+            ("3.25", 3.25),
+            ("0.5", 0.5),
+            ("1_000.25", 1000.25),
+            ("1e-9", 1e-9),
+            ("1E9", 1e9),
+            ("2.5e+3", 2500.0),
+            ("6.02e2_3", 6.02e23),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                tokenize(input),
+                Ok(vec![Token::Float(expected)]),
+                "{}",
+                input
+            )
+        }
+    }
+
+    #[test]
+    fn tokenize_not_floats() {
+        let cases = vec![
+            ("1.", vec![Token::Int(1), Token::Dot]),
+            (
+                "1.x",
+                vec![Token::Int(1), Token::Dot, Token::Var(String::from("x"))],
+            ),
+            ("1e", vec![Token::Var(String::from("1e"))]),
+            ("1e+", vec![Token::Var(String::from("1e")), Token::Plus]),
+            (
+                "1.5x",
+                vec![Token::Int(1), Token::Dot, Token::Var(String::from("5x"))],
+            ),
+            ("1e5x", vec![Token::Var(String::from("1e5x"))]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(tokenize(input), Ok(expected), "{}", input)
+        }
+    }
+
+    #[test]
+    fn tokenize_float_arithmetic() {
+        assert_eq!(
+            tokenize("x*2.0-1e-3"),
+            Ok(vec![
+                Token::Var(String::from("x")),
+                Token::Multiply,
+                Token::Float(2.0),
+                Token::Minus,
+                Token::Float(1e-3),
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_float_overflow() {
+        assert_eq!(
+            error_kinds("1e999"),
+            vec![LexErrorKind::FloatOverflow(String::from("1e999"))]
+        )
     }
 
     #[test]
     fn tokenize_weird_var_name_1() {
-        assert_eq!(tokenize("ifelse"), vec![Token::Var(String::from("ifelse"))])
+        assert_eq!(
+            tokenize("ifelse"),
+            Ok(vec![Token::Var(String::from("ifelse"))])
+        )
     }
 
     #[test]
     fn tokenize_weird_var_name_2() {
-        assert_eq!(tokenize("If"), vec![Token::Var(String::from("If"))])
+        assert_eq!(tokenize("If"), Ok(vec![Token::Var(String::from("If"))]))
     }
 
     #[test]
     fn tokenize_weird_var_name_3() {
-        assert_eq!(tokenize("_"), vec![Token::Var(String::from("_"))])
+        assert_eq!(tokenize("_"), Ok(vec![Token::Var(String::from("_"))]))
     }
 
     #[test]
     fn tokenize_weird_var_name_4() {
         assert_eq!(
             tokenize("under_score"),
-            vec![Token::Var(String::from("under_score"))]
+            Ok(vec![Token::Var(String::from("under_score"))])
         )
     }
 
@@ -439,7 +1024,7 @@ pub mod tests {
     fn tokenize_weird_var_name_5() {
         assert_eq!(
             tokenize("_underscore"),
-            vec![Token::Var(String::from("_underscore"))]
+            Ok(vec![Token::Var(String::from("_underscore"))])
         )
     }
 
@@ -447,44 +1032,135 @@ pub mod tests {
     fn tokenize_weird_var_name_6() {
         assert_eq!(
             tokenize("underscore_"),
-            vec![Token::Var(String::from("underscore_"))]
+            Ok(vec![Token::Var(String::from("underscore_"))])
         )
     }
 
     #[test]
     fn tokenize_weird_var_name_7() {
-        assert_eq!(tokenize("num1"), vec![Token::Var(String::from("num1"))])
+        assert_eq!(tokenize("num1"), Ok(vec![Token::Var(String::from("num1"))]))
     }
 
     #[test]
     fn tokenize_weird_var_name_8() {
-        assert_eq!(tokenize("1num"), vec![Token::Var(String::from("1num"))])
+        assert_eq!(tokenize("1num"), Ok(vec![Token::Var(String::from("1num"))]))
     }
 
     #[test]
     fn tokenize_weird_var_name_9() {
         assert_eq!(
             tokenize("123_this_is_a_var"),
-            vec![Token::Var(String::from("123_this_is_a_var"))]
+            Ok(vec![Token::Var(String::from("123_this_is_a_var"))])
         )
     }
 
+    fn error_kinds(input: &str) -> Vec<LexErrorKind> {
+        tokenize(input)
+            .unwrap_err()
+            .into_iter()
+            .map(|err| err.kind)
+            .collect()
+    }
+
     #[test]
-    #[should_panic]
     fn tokenize_illegal_var_name_1() {
-        assert_eq!(tokenize("&"), vec![])
+        assert_eq!(error_kinds("&"), vec![LexErrorKind::UnknownCharacter('&')])
     }
 
     #[test]
-    #[should_panic]
     fn tokenize_illegal_var_name_2() {
-        assert_eq!(tokenize("|||"), vec![])
+        assert_eq!(
+            error_kinds("|||"),
+            vec![LexErrorKind::UnknownCharacter('|')]
+        )
     }
 
     #[test]
-    #[should_panic]
     fn tokenize_illegal_var_name_3() {
-        assert_eq!(tokenize("?"), vec![])
+        assert_eq!(error_kinds("?"), vec![LexErrorKind::UnknownCharacter('?')])
+    }
+
+    // ----------- error tests ---------- \\
+    #[test]
+    fn tokenize_unterminated_string() {
+        assert_eq!(
+            error_kinds("let x: str = \"Hello;"),
+            vec![LexErrorKind::UnterminatedString]
+        )
+    }
+
+    #[test]
+    fn tokenize_int_overflow() {
+        assert_eq!(
+            error_kinds("9_223_372_036_854_775_809"),
+            vec![LexErrorKind::IntegerOverflow(String::from(
+                "9_223_372_036_854_775_809"
+            ))]
+        )
+    }
+
+    #[test]
+    fn tokenize_int_min() {
+        // left for the parser to negate
+        for input in ["9_223_372_036_854_775_808", "0x8000_0000_0000_0000i64"] {
+            assert_eq!(tokenize(input), Ok(vec![Token::Int(i64::MIN)]), "{}", input)
+        }
+    }
+
+    #[test]
+    fn tokenize_reports_every_error() {
+        let errors = tokenize("let x = ? + 99999999999999999999 & \"oops").unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                LexError {
+                    kind: LexErrorKind::UnknownCharacter('?'),
+                    span: Span {
+                        file: 0,
+                        start: 8,
+                        end: 9,
+                        line: 1,
+                        column: 9,
+                    },
+                },
+                LexError {
+                    kind: LexErrorKind::IntegerOverflow(String::from("99999999999999999999")),
+                    span: Span {
+                        file: 0,
+                        start: 12,
+                        end: 32,
+                        line: 1,
+                        column: 13,
+                    },
+                },
+                LexError {
+                    kind: LexErrorKind::UnknownCharacter('&'),
+                    span: Span {
+                        file: 0,
+                        start: 33,
+                        end: 34,
+                        line: 1,
+                        column: 34,
+                    },
+                },
+                LexError {
+                    kind: LexErrorKind::UnterminatedString,
+                    span: Span {
+                        file: 0,
+                        start: 35,
+                        end: 40,
+                        line: 1,
+                        column: 36,
+                    },
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn lex_error_display() {
+        let errors = tokenize("\n  ?").unwrap_err();
+        assert_eq!(errors[0].to_string(), "2:3: unknown character '?'")
     }
 
     // ----------- basic input tests ---------- \\
@@ -492,7 +1168,7 @@ pub mod tests {
     fn tokenize_1_plus_2_no_whitespace() {
         assert_eq!(
             tokenize("1+2"),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -500,7 +1176,7 @@ pub mod tests {
     fn tokenize_1_plus_2_single_space() {
         assert_eq!(
             tokenize("1 + 2"),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -508,7 +1184,7 @@ pub mod tests {
     fn tokenize_1_plus_2_single_space_front() {
         assert_eq!(
             tokenize(" 1+2"),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -516,7 +1192,7 @@ pub mod tests {
     fn tokenize_1_plus_2_single_tab_front() {
         assert_eq!(
             tokenize("	1+2"),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -527,7 +1203,7 @@ pub mod tests {
                 "
 					 1+2"
             ),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -535,7 +1211,7 @@ pub mod tests {
     fn tokenize_1_plus_2_single_space_back() {
         assert_eq!(
             tokenize("1+2 "),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -543,7 +1219,7 @@ pub mod tests {
     fn tokenize_1_plus_2_single_tab_back() {
         assert_eq!(
             tokenize("1+2	"),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -554,7 +1230,7 @@ pub mod tests {
                 "1+2
 					 "
             ),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -562,7 +1238,7 @@ pub mod tests {
     fn tokenize_1_plus_2_single_space_wrapped() {
         assert_eq!(
             tokenize(" 1 + 2 "),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -574,7 +1250,7 @@ pub mod tests {
 					 +
 					 2						 "
             ),
-            vec![Token::Int(1), Token::Plus, Token::Int(2),]
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2),])
         )
     }
 
@@ -583,7 +1259,7 @@ pub mod tests {
     fn tokenize_int_assignment() {
         assert_eq!(
             tokenize("let x: int = 32;"),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -591,7 +1267,7 @@ pub mod tests {
                 Token::Assign,
                 Token::Int(32),
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -599,7 +1275,7 @@ pub mod tests {
     fn tokenize_assignment_whitespace_termination() {
         assert_eq!(
             tokenize("let x:int=32; "),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -607,7 +1283,7 @@ pub mod tests {
                 Token::Assign,
                 Token::Int(32),
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -615,7 +1291,7 @@ pub mod tests {
     fn tokenize_assignment_min_whitespace() {
         assert_eq!(
             tokenize("let x:int=32;"),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -623,7 +1299,7 @@ pub mod tests {
                 Token::Assign,
                 Token::Int(32),
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -631,14 +1307,14 @@ pub mod tests {
     fn tokenize_assignment_no_whitespace() {
         assert_eq!(
             tokenize("letx:int=32;"),
-            vec![
+            Ok(vec![
                 Token::Var(String::from("letx")),
                 Token::Colon,
                 Token::TypeName(Type::Int),
                 Token::Assign,
                 Token::Int(32),
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -646,7 +1322,7 @@ pub mod tests {
     fn tokenize_str_assignment() {
         assert_eq!(
             tokenize("let x: str = \"Hello World!\";"),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -654,7 +1330,7 @@ pub mod tests {
                 Token::Assign,
                 Token::Str(String::from("Hello World!")),
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -662,7 +1338,7 @@ pub mod tests {
     fn tokenize_bool_assignment() {
         assert_eq!(
             tokenize("let x: bool = true;"),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -670,7 +1346,7 @@ pub mod tests {
                 Token::Assign,
                 Token::Bool(true),
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -678,7 +1354,7 @@ pub mod tests {
     fn tokenize_void_assignment() {
         assert_eq!(
             tokenize("let x: void = ();"),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -687,7 +1363,7 @@ pub mod tests {
                 Token::LeftParen,
                 Token::RightParen,
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -700,7 +1376,7 @@ pub mod tests {
 					baz: \"Hello World\",
 				};"
             ),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -717,7 +1393,7 @@ pub mod tests {
                 Token::Comma,
                 Token::RightCurly,
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -725,7 +1401,7 @@ pub mod tests {
     fn tokenize_list_assignment() {
         assert_eq!(
             tokenize("let x: [int] = [32, 17, -5,];"),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -743,7 +1419,7 @@ pub mod tests {
                 Token::Comma,
                 Token::RightBrace,
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -751,7 +1427,7 @@ pub mod tests {
     fn tokenize_higher_order_func_assignment() {
         assert_eq!(
             tokenize("let x: (int -> int) = (a) { return 1 + a; };"),
-            vec![
+            Ok(vec![
                 Token::Let,
                 Token::Var(String::from("x")),
                 Token::Colon,
@@ -772,7 +1448,7 @@ pub mod tests {
                 Token::Semicolon,
                 Token::RightCurly,
                 Token::Semicolon,
-            ]
+            ])
         )
     }
 
@@ -786,7 +1462,7 @@ pub mod tests {
 						qux: bool,
 					}"
             ),
-            vec![
+            Ok(vec![
                 Token::Struct,
                 Token::Var(String::from("Foo")),
                 Token::LeftCurly,
@@ -803,7 +1479,7 @@ pub mod tests {
                 Token::TypeName(Type::Bool),
                 Token::Comma,
                 Token::RightCurly,
-            ]
+            ])
         )
     }
 
@@ -818,7 +1494,7 @@ pub mod tests {
 					return result;
 				}"
             ),
-            vec![
+            Ok(vec![
                 Token::Function,
                 Token::Var(String::from("bad_adder")),
                 Token::LeftParen,
@@ -861,7 +1537,7 @@ pub mod tests {
                 Token::Var(String::from("result")),
                 Token::Semicolon,
                 Token::RightCurly,
-            ]
+            ])
         )
     }
 
@@ -869,7 +1545,7 @@ pub mod tests {
     fn tokenize_function_call() {
         assert_eq!(
             tokenize("foo(x, y);"),
-            vec![
+            Ok(vec![
                 Token::Var(String::from("foo")),
                 Token::LeftParen,
                 Token::Var(String::from("x")),
@@ -877,16 +1553,189 @@ pub mod tests {
                 Token::Var(String::from("y")),
                 Token::RightParen,
                 Token::Semicolon,
+            ])
+        )
+    }
+
+    // ----------- tokenize_spanned() tests ---------- \\
+    fn span(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            file: 0,
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn tokenize_spanned_assignment() {
+        let spans: Vec<Span> = tokenize_spanned("let x: int = 32;", 0, Comments::Strip)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.span)
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                span(0, 3, 1, 1),
+                span(4, 5, 1, 5),
+                span(5, 6, 1, 6),
+                span(7, 10, 1, 8),
+                span(11, 12, 1, 12),
+                span(13, 15, 1, 14),
+                span(15, 16, 1, 16),
+            ]
+        )
+    }
+
+    #[test]
+    fn tokenize_spanned_two_char_symbol() {
+        assert_eq!(
+            tokenize_spanned("a >= b", 0, Comments::Strip).unwrap()[1],
+            SpannedToken {
+                token: Token::GreaterEqual,
+                span: span(2, 4, 1, 3),
+            }
+        )
+    }
+
+    #[test]
+    fn tokenize_spanned_str_includes_quotes() {
+        assert_eq!(
+            tokenize_spanned("x = \"Hi\";", 0, Comments::Strip).unwrap()[2],
+            SpannedToken {
+                token: Token::Str(String::from("Hi")),
+                span: span(4, 8, 1, 5),
+            }
+        )
+    }
+
+    #[test]
+    fn tokenize_spanned_lines_and_columns() {
+        let tokens = tokenize_spanned("while (x) {\n    return x;\n}", 0, Comments::Strip).unwrap();
+        assert_eq!(tokens[4].span, span(10, 11, 1, 11));
+        assert_eq!(tokens[5].span, span(16, 22, 2, 5));
+        assert_eq!(tokens[8].span, span(26, 27, 3, 1));
+    }
+
+    #[test]
+    fn tokenize_spanned_keeps_file_id() {
+        assert!(tokenize_spanned("a b c", 7, Comments::Strip)
+            .unwrap()
+            .iter()
+            .all(|spanned| spanned.span.file == 7))
+    }
+
+    #[test]
+    fn tokenize_matches_tokenize_spanned() {
+        let source = "func f(a: int,) -> int { return a; }";
+        let spanned: Vec<Token> = tokenize_spanned(source, 0, Comments::Strip)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect();
+        assert_eq!(tokenize(source), Ok(spanned))
+    }
+
+    // ----------- comment tests ---------- \\
+    fn tokenize_keep_comments(input: &str) -> Vec<Token> {
+        tokenize_spanned(input, 0, Comments::Keep)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect()
+    }
+
+    #[test]
+    fn tokenize_line_comment_stripped() {
+        assert_eq!(
+            tokenize("let x: int = 1; # one\nx = 2;"),
+            Ok(vec![
+                Token::Let,
+                Token::Var(String::from("x")),
+                Token::Colon,
+                Token::TypeName(Type::Int),
+                Token::Assign,
+                Token::Int(1),
+                Token::Semicolon,
+                Token::Var(String::from("x")),
+                Token::Assign,
+                Token::Int(2),
+                Token::Semicolon,
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_line_comment_kept() {
+        assert_eq!(
+            tokenize_keep_comments("x # the x\r\n# done"),
+            vec![
+                Token::Var(String::from("x")),
+                Token::Comment(String::from(" the x")),
+                Token::Comment(String::from(" done")),
             ]
         )
     }
 
+    #[test]
+    fn tokenize_comment_only() {
+        assert_eq!(tokenize("#"), Ok(vec![]))
+    }
+
+    #[test]
+    fn tokenize_block_comment() {
+        assert_eq!(
+            tokenize_keep_comments("a #[ spans\nlines ]# b"),
+            vec![
+                Token::Var(String::from("a")),
+                Token::Comment(String::from(" spans\nlines ")),
+                Token::Var(String::from("b")),
+            ]
+        )
+    }
+
+    #[test]
+    fn tokenize_nested_block_comment() {
+        assert_eq!(
+            tokenize_keep_comments("#[ outer #[ inner ]# still outer ]#"),
+            vec![Token::Comment(String::from(
+                " outer #[ inner ]# still outer "
+            ))]
+        )
+    }
+
+    #[test]
+    fn tokenize_comment_span() {
+        let tokens = tokenize_spanned("x\n  # hi\ny", 0, Comments::Keep).unwrap();
+        assert_eq!(
+            tokens[1].span,
+            Span {
+                file: 0,
+                start: 4,
+                end: 8,
+                line: 2,
+                column: 3,
+            }
+        );
+        assert_eq!(tokens[2].span.line, 3);
+    }
+
+    #[test]
+    fn tokenize_unterminated_block_comment() {
+        assert_eq!(
+            error_kinds("x #[ #[ ]# y"),
+            vec![LexErrorKind::UnterminatedComment]
+        )
+    }
+
     // ----------- tokenize_str() tests ---------- \\
     #[test]
     fn tokenize_str_one_word() {
         assert_eq!(
             tokenize_str("Hello\""),
-            Some((Token::Str(String::from("Hello")), ""))
+            Ok((Token::Str(String::from("Hello")), ""))
         )
     }
 
@@ -894,7 +1743,7 @@ pub mod tests {
     fn tokenize_str_two_words() {
         assert_eq!(
             tokenize_str("Hello World\""),
-            Some((Token::Str(String::from("Hello World")), ""))
+            Ok((Token::Str(String::from("Hello World")), ""))
         )
     }
 
@@ -902,18 +1751,125 @@ pub mod tests {
     fn tokenize_str_words_and_remainder() {
         assert_eq!(
             tokenize_str("Hello World\"; let x = 5;"),
-            Some((Token::Str(String::from("Hello World")), "; let x = 5;"))
+            Ok((Token::Str(String::from("Hello World")), "; let x = 5;"))
         )
     }
 
     #[test]
     fn tokenize_str_empty() {
-        assert_eq!(tokenize_str(""), None)
+        assert_eq!(
+            tokenize_str(""),
+            Err((LexErrorKind::UnterminatedString, ""))
+        )
     }
 
     #[test]
     fn tokenize_not_a_string() {
-        assert_eq!(tokenize_str("let x;"), None)
+        assert_eq!(
+            tokenize_str("let x;"),
+            Err((LexErrorKind::UnterminatedString, ""))
+        )
+    }
+
+    #[test]
+    fn tokenize_str_escapes() {
+        assert_eq!(
+            tokenize(r#""say \"Hi\"\n\tand \\ \r\0 bye""#),
+            Ok(vec![Token::Str(String::from(
+                "say \"Hi\"\n\tand \\ \r\0 bye"
+            ))])
+        )
+    }
+
+    #[test]
+    fn tokenize_str_unicode_escapes() {
+        assert_eq!(
+            tokenize(r#""\u{48}\u{1F600}\u{e9}""#),
+            Ok(vec![Token::Str(String::from("H\u{1F600}\u{e9}"))])
+        )
+    }
+
+    #[test]
+    fn tokenize_str_escaped_quote_at_end() {
+        assert_eq!(
+            error_kinds(r#""abc\""#),
+            vec![LexErrorKind::UnterminatedString]
+        )
+    }
+
+    #[test]
+    fn tokenize_str_invalid_escapes() {
+        let cases = vec![
+            (r#""\q""#, "\\q"),
+            (r#""\u""#, "\\u"),
+            (r#""\u{}""#, "\\u{}"),
+            (r#""\u{41""#, "\\u{41"),
+            (r#""\u{D800}""#, "\\u{D800}"),
+            (r#""\u{110000}""#, "\\u{110000}"),
+            (r#""\u{0000041}""#, "\\u{0000041}"),
+        ];
+        for (input, escape) in cases {
+            assert_eq!(
+                error_kinds(input),
+                vec![LexErrorKind::InvalidEscape(String::from(escape))],
+                "{}",
+                input
+            )
+        }
+    }
+
+    #[test]
+    fn tokenize_str_invalid_escape_keeps_lexing() {
+        let errors = tokenize(r#"let x: str = "a\qb\z"; ?"#).unwrap_err();
+        assert_eq!(
+            errors.into_iter().map(|err| err.kind).collect::<Vec<_>>(),
+            vec![
+                LexErrorKind::InvalidEscape(String::from("\\q")),
+                LexErrorKind::UnknownCharacter('?'),
+            ]
+        )
+    }
+
+    #[test]
+    fn tokenize_raw_str() {
+        assert_eq!(
+            tokenize(r#"r"C:\new\table" r"""#),
+            Ok(vec![
+                Token::Str(String::from("C:\\new\\table")),
+                Token::Str(String::new()),
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_raw_str_with_hashes() {
+        assert_eq!(
+            tokenize(r###"r#"say "Hi" \d+"# r##"a "# b"##"###),
+            Ok(vec![
+                Token::Str(String::from("say \"Hi\" \\d+")),
+                Token::Str(String::from("a \"# b")),
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_raw_str_unterminated() {
+        assert_eq!(
+            error_kinds(r###"r#"abc" r"###),
+            vec![LexErrorKind::UnterminatedString]
+        )
+    }
+
+    #[test]
+    fn tokenize_r_is_still_a_var() {
+        assert_eq!(
+            tokenize("r rx r # comment"),
+            Ok(vec![
+                Token::Var(String::from("r")),
+                Token::Var(String::from("rx")),
+                Token::Var(String::from("r")),
+            ])
+        )
     }
 
     // ----------- tokenize_int() tests ---------- \\
@@ -1002,6 +1958,105 @@ pub mod tests {
         }
     }
 
+    // ----------------- unicode tests ------------------ \\
+    #[test]
+    fn tokenize_unicode_var_names() {
+        assert_eq!(
+            tokenize("café 名前 Ωmega naïve_2 _ñ"),
+            Ok(vec![
+                Token::Var(String::from("café")),
+                Token::Var(String::from("名前")),
+                Token::Var(String::from("Ωmega")),
+                Token::Var(String::from("naïve_2")),
+                Token::Var(String::from("_ñ")),
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_unicode_assignment() {
+        assert_eq!(
+            tokenize("let straße: str = \"héllo wörld 🎉\";"),
+            Ok(vec![
+                Token::Let,
+                Token::Var(String::from("straße")),
+                Token::Colon,
+                Token::TypeName(Type::Str),
+                Token::Assign,
+                Token::Str(String::from("héllo wörld 🎉")),
+                Token::Semicolon,
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_non_xid_characters() {
+        assert_eq!(
+            error_kinds("x ≠ y 🎉"),
+            vec![
+                LexErrorKind::UnknownCharacter('≠'),
+                LexErrorKind::UnknownCharacter('🎉'),
+            ]
+        )
+    }
+
+    #[test]
+    fn tokenize_unicode_whitespace() {
+        // no-break space and em space
+        assert_eq!(
+            tokenize("1\u{a0}+\u{2003}2"),
+            Ok(vec![Token::Int(1), Token::Plus, Token::Int(2)])
+        )
+    }
+
+    #[test]
+    fn tokenize_unicode_columns_count_chars() {
+        let tokens = tokenize_spanned("ü = \"é\"; z", 0, Comments::Strip).unwrap();
+        assert_eq!(tokens[4].span.start, 11);
+        assert_eq!(tokens[4].span.column, 10);
+    }
+
+    #[test]
+    fn get_unicode_word() {
+        assert_eq!(split_first_word("naïve rest"), ("naïve", " rest"));
+        assert_eq!(split_first_word("日本語"), ("日本語", ""));
+    }
+
+    // ----------------- tokenize pipe tests ------------------ \\
+    #[test]
+    fn tokenize_pipe_chain() {
+        assert_eq!(
+            tokenize("data |> filter(is_valid) |> map(scale)"),
+            Ok(vec![
+                Token::Var(String::from("data")),
+                Token::Pipe,
+                Token::Var(String::from("filter")),
+                Token::LeftParen,
+                Token::Var(String::from("is_valid")),
+                Token::RightParen,
+                Token::Pipe,
+                Token::Var(String::from("map")),
+                Token::LeftParen,
+                Token::Var(String::from("scale")),
+                Token::RightParen,
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_pipe_no_whitespace() {
+        assert_eq!(
+            tokenize("x|>f||y"),
+            Ok(vec![
+                Token::Var(String::from("x")),
+                Token::Pipe,
+                Token::Var(String::from("f")),
+                Token::Or,
+                Token::Var(String::from("y")),
+            ])
+        )
+    }
+
     // ----------------- tokenize while tests ------------------ \\
     #[test]
     fn tokenize_while_with_condition() {
@@ -1010,7 +2065,7 @@ pub mod tests {
                 "while (x != 5){
         return true; }"
             ),
-            vec![
+            Ok(vec![
                 Token::While,
                 Token::LeftParen,
                 Token::Var(String::from("x")),
@@ -1022,7 +2077,7 @@ pub mod tests {
                 Token::Bool(true),
                 Token::Semicolon,
                 Token::RightCurly,
-            ]
+            ])
         );
     }
 
@@ -1031,14 +2086,14 @@ pub mod tests {
     fn tokenize_for_loop() {
         assert_eq!(
             tokenize("for items in list return items"),
-            vec![
+            Ok(vec![
                 Token::For,
                 Token::Var(String::from("items")),
                 Token::In,
                 Token::Var(String::from("list")),
                 Token::Return,
                 Token::Var(String::from("items")),
-            ]
+            ])
         );
     }
 
@@ -1051,7 +2106,7 @@ pub mod tests {
                              y = true;
                          } else y = false;"
             ),
-            vec![
+            Ok(vec![
                 Token::If,
                 Token::LeftParen,
                 Token::Var(String::from("x")),
@@ -1069,7 +2124,7 @@ pub mod tests {
                 Token::Assign,
                 Token::Bool(false),
                 Token::Semicolon
-            ]
+            ])
         );
     }
 }
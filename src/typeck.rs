@@ -0,0 +1,1485 @@
+mod error;
+
+pub use error::{TypeError, TypeErrorKind};
+
+use crate::ast::*;
+use crate::interpreter::Builtin;
+use crate::span::Span;
+use crate::types::{StructRegistry, Type};
+use std::collections::HashMap;
+
+// what a name in scope refers to. a variable's type is None when it couldn't
+// be worked out, which has already been reported
+#[derive(Debug, Clone)]
+enum Symbol {
+    Var(Option<Type>),
+    Function(Vec<Type>, Type),
+    Builtin(Builtin),
+}
+
+// what a return statement has to give back where it is
+enum Returns {
+    TopLevel,
+    Expected(Type),
+    // lambdas take the type of their first return
+    Infer(Option<Type>),
+}
+
+// checks a whole program, collecting every error rather than stopping at the
+// first. a well typed program gives back the structs it declared. along the
+// way, lambda parameters without a type get the one they're expected to have
+pub fn check(program: &mut Program) -> Result<StructRegistry, Vec<TypeError>> {
+    let mut checker = Checker::new();
+
+    // structs and top level functions can be used before they're declared
+    for item in &program.items {
+        match item {
+            Item::Struct(def) => checker.at(def.span, |checker| checker.declare_struct(def)),
+            Item::Function(function) => checker.declare_function(function),
+            Item::Global(..) | Item::Statement(_) => {}
+        }
+    }
+
+    let mut used = vec![];
+    for item in &mut program.items {
+        match item {
+            Item::Struct(def) => checker.at(def.span, |checker| checker.check_struct_def(def)),
+            Item::Function(function) => {
+                checker.at(function.span, |checker| checker.check_function(function))
+            }
+            Item::Global(var, exp) => checker.at(var.span, |checker| checker.check_let(var, exp)),
+            Item::Statement(stmt) => checker.at(stmt.span(), |checker| checker.check_stmt(stmt)),
+        }
+        used.push(std::mem::take(&mut checker.used));
+    }
+    checker.check_global_order(&program.items, &used);
+
+    if checker.errors.is_empty() {
+        Ok(checker.structs)
+    } else {
+        Err(checker.errors)
+    }
+}
+
+struct Checker {
+    structs: StructRegistry,
+    scopes: Vec<HashMap<String, Symbol>>,
+    returns: Returns,
+    // the top level names the item being checked uses, and where it first
+    // does
+    used: Vec<(String, Span)>,
+    // where errors are reported
+    span: Span,
+    errors: Vec<TypeError>,
+}
+
+impl Checker {
+    fn new() -> Checker {
+        let mut globals = HashMap::new();
+        for builtin in Builtin::ALL.iter() {
+            globals.insert(String::from(builtin.name()), Symbol::Builtin(*builtin));
+        }
+        Checker {
+            structs: StructRegistry::new(),
+            scopes: vec![globals],
+            returns: Returns::TopLevel,
+            used: vec![],
+            span: Span::default(),
+            errors: vec![],
+        }
+    }
+
+    fn error(&mut self, kind: TypeErrorKind) {
+        let error = TypeError {
+            kind,
+            span: self.span,
+        };
+        // e.g. an unknown struct in a let is found in both the type and the literal
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    // runs check with errors reported at span
+    fn at<T>(&mut self, span: Span, check: impl FnOnce(&mut Checker) -> T) -> T {
+        let outer = std::mem::replace(&mut self.span, span);
+        let result = check(self);
+        self.span = outer;
+        result
+    }
+
+    // reports a mismatch unless found is unknown or what was expected
+    fn expect(&mut self, expected: &Type, found: Option<Type>) {
+        if let Some(found) = found {
+            if found != *expected {
+                self.error(TypeErrorKind::Mismatch {
+                    expected: expected.clone(),
+                    found,
+                });
+            }
+        }
+    }
+
+    // ----------- structs ---------- \\
+    fn declare_struct(&mut self, def: &StructDef) {
+        let mut fields: Vec<(String, Type)> = vec![];
+        for field in &def.fields {
+            if fields.iter().any(|(name, _)| *name == field.name) {
+                self.at(field.span, |checker| {
+                    checker.error(TypeErrorKind::DuplicateField(
+                        def.name.clone(),
+                        field.name.clone(),
+                    ))
+                });
+            } else {
+                let field_type = field.var_type.clone().unwrap_or(Type::Void);
+                fields.push((field.name.clone(), field_type));
+            }
+        }
+        if !self.structs.declare(&def.name, fields) {
+            self.error(TypeErrorKind::DuplicateStruct(def.name.clone()));
+        }
+    }
+
+    // already declared, but the field types can name other structs
+    fn check_struct_def(&mut self, def: &StructDef) {
+        for field in &def.fields {
+            if let Some(field_type) = &field.var_type {
+                self.check_type(field_type);
+            }
+        }
+    }
+
+    // reports any struct named in a type that was never declared
+    fn check_type(&mut self, checked: &Type) {
+        match checked {
+            Type::Struct(name) if self.structs.fields(name).is_none() => {
+                self.error(TypeErrorKind::UnknownStruct(name.clone()));
+            }
+            Type::List(element) => self.check_type(element),
+            Type::HigherOrderFunction(params, output) => {
+                for param in params {
+                    self.check_type(param);
+                }
+                self.check_type(output);
+            }
+            _ => {}
+        }
+    }
+
+    // anonymous literals have been given the struct type they're expected to
+    // be by now, if there was one
+    fn check_struct_literal(&mut self, name: &str, fields: &mut [Var]) -> Option<Type> {
+        let declared = match self.structs.fields(name) {
+            Some(declared) => declared.to_vec(),
+            None => {
+                if name.is_empty() {
+                    self.error(TypeErrorKind::AnonymousStruct);
+                } else {
+                    self.error(TypeErrorKind::UnknownStruct(String::from(name)));
+                }
+                for field in fields {
+                    if let Some(value) = &mut field.value {
+                        self.check_exp(value);
+                    }
+                }
+                return None;
+            }
+        };
+
+        for i in 0..fields.len() {
+            let expected = declared
+                .iter()
+                .find(|(field_name, _)| *field_name == fields[i].name)
+                .map(|(_, field_type)| field_type);
+            let found = fields[i]
+                .value
+                .as_mut()
+                .and_then(|value| self.check_exp_expecting(value, expected));
+            let field = &fields[i];
+            if fields[..i].iter().any(|earlier| earlier.name == field.name) {
+                self.error(TypeErrorKind::DuplicateField(
+                    String::from(name),
+                    field.name.clone(),
+                ));
+                continue;
+            }
+            match declared
+                .iter()
+                .find(|(field_name, _)| *field_name == field.name)
+            {
+                Some((_, field_type)) => self.expect(field_type, found),
+                None => self.error(TypeErrorKind::UnknownField(
+                    String::from(name),
+                    field.name.clone(),
+                )),
+            }
+        }
+        for (field_name, _) in &declared {
+            if !fields.iter().any(|field| field.name == *field_name) {
+                self.error(TypeErrorKind::MissingField(
+                    String::from(name),
+                    field_name.clone(),
+                ));
+            }
+        }
+        Some(Type::Struct(String::from(name)))
+    }
+
+    // ----------- scopes ---------- \\
+    fn define(&mut self, name: &str, symbol: Symbol) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(String::from(name), symbol);
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<Symbol> {
+        let found = self
+            .scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(depth, scope)| Some((depth, scope.get(name)?.clone())));
+        match found {
+            Some((depth, symbol)) => {
+                if depth == 0 && !self.used.iter().any(|(used, _)| used == name) {
+                    self.used.push((String::from(name), self.span));
+                }
+                Some(symbol)
+            }
+            None => {
+                self.error(TypeErrorKind::UndefinedVariable(String::from(name)));
+                None
+            }
+        }
+    }
+
+    fn check_block(&mut self, block: &mut [Statement]) {
+        self.scopes.push(HashMap::new());
+        self.check_stmts(block);
+        self.scopes.pop();
+    }
+
+    fn check_stmts(&mut self, block: &mut [Statement]) {
+        for stmt in block {
+            let span = stmt.span();
+            self.at(span, |checker| checker.check_stmt(stmt));
+        }
+    }
+
+    // ----------- statements ---------- \\
+    fn check_stmt(&mut self, stmt: &mut Statement) {
+        match stmt {
+            Statement::Assignment(var, exp) if var.var_type.is_some() => self.check_let(var, exp),
+            Statement::Assignment(var, exp) => match self.lookup(&var.name) {
+                Some(Symbol::Var(Some(current))) => {
+                    let found = self.check_exp_expecting(exp, Some(&current));
+                    self.expect(&current, found);
+                }
+                Some(Symbol::Var(None)) | None => {
+                    self.check_exp(exp);
+                }
+                Some(_) => {
+                    self.check_exp(exp);
+                    self.error(TypeErrorKind::NotAVariable(var.name.clone()));
+                }
+            },
+            Statement::Function(function) => self.check_function(function),
+            Statement::Return(exp, _) => {
+                let expected = match &self.returns {
+                    Returns::Expected(expected) | Returns::Infer(Some(expected)) => {
+                        Some(expected.clone())
+                    }
+                    Returns::TopLevel | Returns::Infer(None) => None,
+                };
+                let found = self.check_exp_expecting(exp, expected.as_ref());
+                match &mut self.returns {
+                    Returns::TopLevel => {}
+                    Returns::Expected(expected) => {
+                        let expected = expected.clone();
+                        self.expect(&expected, found);
+                    }
+                    Returns::Infer(inferred @ None) => *inferred = found,
+                    Returns::Infer(Some(inferred)) => {
+                        let inferred = inferred.clone();
+                        self.expect(&inferred, found);
+                    }
+                }
+            }
+            Statement::If(if_stmt) => {
+                self.check_condition(&mut if_stmt.condition);
+                self.check_block(&mut if_stmt.true_branch);
+                if let Some(false_branch) = &mut if_stmt.false_branch {
+                    self.check_block(false_branch);
+                }
+            }
+            Statement::For(for_stmt) => {
+                let item_type = match self.check_exp(&mut for_stmt.iterable) {
+                    Some(Type::List(item_type)) => Some(*item_type),
+                    Some(found) => {
+                        self.error(TypeErrorKind::NotIterable(found));
+                        None
+                    }
+                    None => None,
+                };
+                self.scopes.push(HashMap::new());
+                self.define(&for_stmt.iter.name, Symbol::Var(item_type));
+                self.check_stmts(&mut for_stmt.block);
+                self.scopes.pop();
+            }
+            Statement::While(while_stmt) => {
+                self.check_condition(&mut while_stmt.condition);
+                self.check_block(&mut while_stmt.block);
+            }
+            Statement::Exp(exp, _) => {
+                self.check_exp(exp);
+            }
+        }
+    }
+
+    // let name: type = exp;
+    fn check_let(&mut self, var: &Var, exp: &mut Exp) {
+        let declared = var.var_type.clone().unwrap_or(Type::Void);
+        let found = self.check_exp_expecting(exp, Some(&declared));
+        self.check_type(&declared);
+        self.expect(&declared, found);
+        self.define(&var.name, Symbol::Var(Some(declared)));
+    }
+
+    fn declare_function(&mut self, function: &Function) {
+        let (params, output) = signature(function);
+        self.define(&function.name, Symbol::Function(params, output));
+    }
+
+    fn check_function(&mut self, function: &mut Function) {
+        let (params, output) = signature(function);
+        for param_type in &params {
+            self.check_type(param_type);
+        }
+        self.check_type(&output);
+        // defined first so the body can call itself
+        self.declare_function(function);
+
+        self.scopes.push(HashMap::new());
+        for (param, param_type) in function.param.iter().zip(params) {
+            self.define(&param.name, Symbol::Var(Some(param_type)));
+        }
+        let outer = std::mem::replace(&mut self.returns, Returns::Expected(output.clone()));
+        self.check_stmts(&mut function.block);
+        self.returns = outer;
+        self.scopes.pop();
+
+        if output != Type::Void && !always_returns(&function.block) {
+            self.error(TypeErrorKind::MissingReturn(function.name.clone()));
+        }
+    }
+
+    // top level functions are hoisted but globals aren't, so a function can
+    // be called before a global it reads has been defined. used has the top
+    // level names each item uses, and an item that can reach a global whose
+    // let is that item or a later one is reported
+    fn check_global_order(&mut self, items: &[Item], used: &[Vec<(String, Span)>]) {
+        let mut defined = HashMap::new();
+        let mut functions = HashMap::new();
+        for (i, item) in items.iter().enumerate() {
+            match item {
+                Item::Global(var, _) => {
+                    defined.entry(var.name.as_str()).or_insert(i);
+                }
+                Item::Function(function) => {
+                    functions.insert(function.name.as_str(), i);
+                }
+                Item::Struct(_) | Item::Statement(_) => {}
+            }
+        }
+
+        for (i, item) in items.iter().enumerate() {
+            if let Item::Struct(_) | Item::Function(_) = item {
+                continue;
+            }
+            for (name, span) in &used[i] {
+                if !functions.contains_key(name.as_str()) {
+                    continue;
+                }
+                for global in globals_read(name, &functions, used) {
+                    if defined.get(global).is_some_and(|&let_at| let_at >= i) {
+                        self.at(*span, |checker| {
+                            checker.error(TypeErrorKind::CalledBeforeGlobal(
+                                name.clone(),
+                                String::from(global),
+                            ))
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_condition(&mut self, condition: &mut Exp) {
+        match self.check_exp(condition) {
+            Some(Type::Bool) | None => {}
+            Some(found) => self.error(TypeErrorKind::ConditionNotBool(found)),
+        }
+    }
+
+    // ----------- expressions ---------- \\
+    // checks exp where a value of the expected type is wanted, first filling
+    // in what that tells us about it the same way a let's type does
+    fn check_exp_expecting(&mut self, exp: &mut Exp, expected: Option<&Type>) -> Option<Type> {
+        if let Some(expected) = expected {
+            exp.apply_declared_type(expected);
+        }
+        self.check_exp(exp)
+    }
+
+    fn check_exp(&mut self, exp: &mut Exp) -> Option<Type> {
+        match exp {
+            Exp::Var(var) => match self.at(var.span, |checker| checker.lookup(&var.name))? {
+                Symbol::Var(var_type) => var_type,
+                // named functions can be passed around like lambdas
+                Symbol::Function(params, output) => {
+                    Some(Type::HigherOrderFunction(params, Box::new(output)))
+                }
+                // builtins take arguments of more than one type, so they
+                // have no function type to pass around with
+                Symbol::Builtin(builtin) => {
+                    self.at(var.span, |checker| {
+                        checker.error(TypeErrorKind::BuiltinAsValue(String::from(builtin.name())))
+                    });
+                    None
+                }
+            },
+            Exp::Int(_) => Some(Type::Int),
+            Exp::BigInt(_) => Some(Type::BigInt),
+            Exp::Float(_) => Some(Type::Float),
+            Exp::Str(_) => Some(Type::Str),
+            Exp::Bool(_) => Some(Type::Bool),
+            Exp::Void => Some(Type::Void),
+
+            Exp::Call(callee, args) => self.check_call(callee, None, args),
+            Exp::Unary(op, exp) => {
+                let found = self.check_exp(exp)?;
+                let result = op.result_type(&found);
+                if result.is_none() {
+                    self.error(TypeErrorKind::BadOperand(*op, found));
+                }
+                result
+            }
+            Exp::Op(op, left, right) => {
+                let left = self.check_exp(left);
+                let right = self.check_exp(right);
+                match (left, right) {
+                    (Some(left), Some(right)) => {
+                        let result = op.result_type(&left, &right);
+                        if result.is_none() {
+                            self.error(TypeErrorKind::BadOperands(*op, left, right));
+                        }
+                        result
+                    }
+                    _ => match op {
+                        Operation::Add
+                        | Operation::Subtract
+                        | Operation::Multiply
+                        | Operation::Divide
+                        | Operation::Modulo => None,
+                        // whatever went into them, the rest always give a bool
+                        _ => Some(Type::Bool),
+                    },
+                }
+            }
+            Exp::Pipe(left, right) => match &mut **right {
+                Exp::Call(callee, args) => self.check_call(callee, Some(left), args),
+                callee => self.check_call(callee, Some(left), &mut []),
+            },
+
+            Exp::Struct(name, fields) => self.check_struct_literal(name, fields),
+            Exp::Field(exp, field) => match self.check_exp(exp)? {
+                Type::Struct(name) => {
+                    // an unknown struct has already been reported
+                    self.structs.fields(&name)?;
+                    let field_type = self.structs.field_type(&name, field).cloned();
+                    if field_type.is_none() {
+                        self.error(TypeErrorKind::UnknownField(name, field.clone()));
+                    }
+                    field_type
+                }
+                found => {
+                    self.error(TypeErrorKind::NotAStruct(found));
+                    None
+                }
+            },
+            Exp::List(list_type, items) => self.check_list(list_type.as_ref(), items),
+            Exp::HOF(params, block) => {
+                self.scopes.push(HashMap::new());
+                for param in params.iter() {
+                    if let Some(param_type) = &param.var_type {
+                        self.check_type(param_type);
+                    }
+                    self.define(&param.name, Symbol::Var(param.var_type.clone()));
+                }
+                let outer = std::mem::replace(&mut self.returns, Returns::Infer(None));
+                self.check_stmts(block);
+                let returns = std::mem::replace(&mut self.returns, outer);
+                self.scopes.pop();
+
+                let output = match returns {
+                    Returns::Infer(Some(output)) => {
+                        if output != Type::Void && !always_returns(block) {
+                            self.error(TypeErrorKind::MissingReturn(String::from("lambda")));
+                        }
+                        output
+                    }
+                    Returns::Infer(None) if !always_returns(block) => Type::Void,
+                    // every return gave back something of unknown type
+                    _ => return None,
+                };
+                let param_types = params
+                    .iter()
+                    .map(|param| param.var_type.clone())
+                    .collect::<Option<Vec<Type>>>()?;
+                Some(Type::HigherOrderFunction(param_types, Box::new(output)))
+            }
+        }
+    }
+
+    // the type of a list with the given items, checking them against the
+    // declared type if there is one, otherwise against the first item
+    fn check_list(&mut self, list_type: Option<&Type>, items: &mut [Exp]) -> Option<Type> {
+        let mut item_type = list_type.and_then(Type::element_type);
+        for item in items {
+            let expected = item_type.clone();
+            let found = self.check_exp_expecting(item, expected.as_ref());
+            match &item_type {
+                Some(expected) => {
+                    let expected = expected.clone();
+                    self.expect(&expected, found)
+                }
+                None => item_type = found,
+            }
+        }
+
+        match list_type {
+            Some(list_type) => Some(list_type.clone()),
+            None => Some(Type::List(Box::new(item_type?))),
+        }
+    }
+
+    // named callees are looked up directly, since builtins have no type. the
+    // callee comes first so lambdas passed to it get its parameter types, and
+    // piped is the left side of a pipe, which goes before the other args
+    fn check_call(
+        &mut self,
+        callee: &mut Exp,
+        piped: Option<&mut Exp>,
+        args: &mut [Exp],
+    ) -> Option<Type> {
+        let symbol = match callee {
+            Exp::Var(var) => self.at(var.span, |checker| checker.lookup(&var.name)),
+            _ => self.check_exp(callee).map(|found| Symbol::Var(Some(found))),
+        };
+        let params = match &symbol {
+            Some(Symbol::Function(params, _))
+            | Some(Symbol::Var(Some(Type::HigherOrderFunction(params, _)))) => params.clone(),
+            _ => vec![],
+        };
+        let args = piped
+            .into_iter()
+            .chain(args.iter_mut())
+            .enumerate()
+            .map(|(i, arg)| self.check_exp_expecting(arg, params.get(i)))
+            .collect();
+        self.check_callee(&callee.callee_name(), symbol?, args)
+    }
+
+    fn check_callee(
+        &mut self,
+        name: &str,
+        callee: Symbol,
+        args: Vec<Option<Type>>,
+    ) -> Option<Type> {
+        let (params, output) = match callee {
+            Symbol::Function(params, output) => (params, output),
+            Symbol::Var(Some(Type::HigherOrderFunction(params, output))) => (params, *output),
+            Symbol::Var(Some(found)) => {
+                self.error(TypeErrorKind::NotCallable(String::from(name), found));
+                return None;
+            }
+            Symbol::Var(None) => return None,
+            Symbol::Builtin(builtin) => return self.check_builtin(builtin, args),
+        };
+
+        if params.len() != args.len() {
+            self.error(TypeErrorKind::WrongArgCount {
+                name: String::from(name),
+                expected: params.len(),
+                found: args.len(),
+            });
+        } else {
+            for (param, arg) in params.iter().zip(args) {
+                self.expect(param, arg);
+            }
+        }
+        Some(output)
+    }
+
+    fn check_builtin(&mut self, builtin: Builtin, args: Vec<Option<Type>>) -> Option<Type> {
+        if args.len() != 1 {
+            self.error(TypeErrorKind::WrongArgCount {
+                name: String::from(builtin.name()),
+                expected: 1,
+                found: args.len(),
+            });
+        }
+        match builtin {
+            Builtin::Print => Some(Type::Void),
+            Builtin::Len => {
+                match args.into_iter().next().flatten() {
+                    Some(Type::Str) | None => {}
+                    Some(found) if found.element_type().is_some() => {}
+                    Some(found) => self.error(TypeErrorKind::NoLength(found)),
+                }
+                Some(Type::Int)
+            }
+        }
+    }
+}
+
+// the parameter types and output type of a function
+fn signature(function: &Function) -> (Vec<Type>, Type) {
+    let params = function
+        .param
+        .iter()
+        .map(|p| p.var_type.clone().unwrap_or(Type::Void))
+        .collect();
+    let output = function.output.var_type.clone().unwrap_or(Type::Void);
+    (params, output)
+}
+
+// the top level names a top level function uses, itself or through the top
+// level functions it uses, which takes in every global it can read
+fn globals_read<'a>(
+    function: &'a str,
+    functions: &HashMap<&str, usize>,
+    used: &'a [Vec<(String, Span)>],
+) -> Vec<&'a str> {
+    let mut read = vec![function];
+    let mut i = 0;
+    while i < read.len() {
+        if let Some(&item) = functions.get(read[i]) {
+            for (name, _) in &used[item] {
+                if !read.contains(&name.as_str()) {
+                    read.push(name);
+                }
+            }
+        }
+        i += 1;
+    }
+    read
+}
+
+// whether every path through a block ends in a return
+fn always_returns(block: &[Statement]) -> bool {
+    block.iter().any(|stmt| match stmt {
+        Statement::Return(..) => true,
+        Statement::If(IfStatement {
+            true_branch,
+            false_branch: Some(false_branch),
+            ..
+        }) => always_returns(true_branch) && always_returns(false_branch),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::parser::{parse, parse_spanned};
+    use crate::tokenizer::tokenizer::{tokenize, tokenize_spanned, Comments};
+
+    fn check_source(source: &str) -> Result<(), Vec<TypeError>> {
+        let tokens = tokenize_spanned(source, 0, Comments::Strip).unwrap();
+        check(&mut parse_spanned(tokens).unwrap()).map(|_| ())
+    }
+
+    // the line and column of each error
+    fn error_lines(source: &str) -> Vec<(usize, usize)> {
+        check_source(source)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| (e.span.line, e.span.column))
+            .collect()
+    }
+
+    // just the kinds of the errors, for tests that don't care where they are
+    fn error_kinds(source: &str) -> Vec<TypeErrorKind> {
+        check_source(source)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.kind)
+            .collect()
+    }
+
+    fn mismatch(expected: Type, found: Type) -> TypeErrorKind {
+        TypeErrorKind::Mismatch { expected, found }
+    }
+
+    fn list_of(element: Type) -> Type {
+        Type::List(Box::new(element))
+    }
+
+    // ----------- assignment tests ---------- \\
+    #[test]
+    fn check_readme_snippets() {
+        assert_eq!(
+            check_source(
+                "let x: int = 32;
+                let s: str = \"Hi!\";
+                let v: void = ();
+                struct ex { bar: int, baz: str, }
+                let e: ex = {bar: 32, baz: \"Hi\",};
+                let l: [int] = [32, 17, -5];
+                let f: (int -> int) = (a) { return 1 + a; };
+                func bad_adder(a: int, b: int,) -> int {
+                    let x: int = a;
+                    let y: int = b;
+                    let result: int = x + y;
+                    return result;
+                }
+                let y: int = 1;
+                bad_adder(x, y);"
+            ),
+            Ok(())
+        )
+    }
+
+    #[test]
+    fn check_let_mismatch() {
+        assert_eq!(
+            error_kinds("let x: int = \"Hi\";"),
+            vec![mismatch(Type::Int, Type::Str)]
+        );
+        assert_eq!(error_lines("let x: int = \"Hi\";"), vec![(1, 5)])
+    }
+
+    #[test]
+    fn check_reassign() {
+        assert_eq!(check_source("let x: float = 1.5; x = 2.5;"), Ok(()));
+        assert_eq!(
+            error_kinds("let x: float = 1.5; x = true;"),
+            vec![mismatch(Type::Float, Type::Bool)]
+        );
+        assert_eq!(
+            error_kinds("func f(a: int) {} f = 1;"),
+            vec![TypeErrorKind::NotAVariable(String::from("f"))]
+        )
+    }
+
+    #[test]
+    fn check_undefined_variable() {
+        assert_eq!(
+            error_kinds("let x: int = y + 1;"),
+            vec![TypeErrorKind::UndefinedVariable(String::from("y"))]
+        )
+    }
+
+    #[test]
+    fn check_list_items() {
+        assert_eq!(
+            error_kinds("let x: [int] = [1, \"two\", 3.0];"),
+            vec![
+                mismatch(Type::Int, Type::Str),
+                mismatch(Type::Int, Type::Float)
+            ]
+        );
+        assert_eq!(
+            error_kinds("let x: [str] = [1, 2];"),
+            vec![mismatch(list_of(Type::Str), list_of(Type::Int))]
+        )
+    }
+
+    #[test]
+    fn check_nested_lists() {
+        assert_eq!(
+            check_source(
+                "let grid: [[int]] = [[1, 2], [], [3]];
+                let row: [int] = [4];
+                let more: [[int]] = [row, [5]];
+                for r in [row] { let n: int = len(r); }"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds("let grid: [[int]] = [[1], [\"a\"]];"),
+            vec![mismatch(list_of(Type::Int), list_of(Type::Str))]
+        );
+        assert_eq!(
+            error_kinds("let grid: [[int]] = [[1.5]];"),
+            vec![mismatch(
+                list_of(list_of(Type::Int)),
+                list_of(list_of(Type::Float))
+            )]
+        )
+    }
+
+    #[test]
+    fn check_lists_of_functions_and_structs() {
+        assert_eq!(
+            check_source(
+                "let fs: [(int -> int)] = [(a) { return a; }, (b) { return b * 2; }];
+                struct Point { x: int, }
+                let paths: [[Point]] = [[{x: 1,}], []];"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds("let fs: [(int -> int)] = [(a) { return true; }];"),
+            vec![mismatch(
+                Type::HigherOrderFunction(vec![Type::Int], Box::new(Type::Int)),
+                Type::HigherOrderFunction(vec![Type::Int], Box::new(Type::Bool)),
+            )]
+        )
+    }
+
+    // ----------- struct tests ---------- \\
+    #[test]
+    fn check_struct_registry() {
+        let mut ast = parse(
+            tokenize(
+                "let origin: Point = {x: 0, y: 0.0,};
+                struct Point { x: int, y: float, }
+                struct Line { start: Point, end: Point, }",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let structs = check(&mut ast).unwrap();
+        // declaration order is kept, and structs can be used before they're declared
+        let names: Vec<&String> = structs.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["Point", "Line"]);
+        assert_eq!(
+            structs.field_type("Line", "end"),
+            Some(&Type::Struct(String::from("Point")))
+        )
+    }
+
+    #[test]
+    fn check_struct_literals() {
+        let program = "struct Point { x: int, y: float, }";
+        assert_eq!(
+            error_kinds(&format!("{} let p: Point = {{x: 1, z: 2,}};", program)),
+            vec![
+                TypeErrorKind::UnknownField(String::from("Point"), String::from("z")),
+                TypeErrorKind::MissingField(String::from("Point"), String::from("y")),
+            ]
+        );
+        assert_eq!(
+            error_kinds(&format!("{} Point {{x: 1.5, y: 2.5, x: 1,}}", program)),
+            vec![
+                mismatch(Type::Int, Type::Float),
+                TypeErrorKind::DuplicateField(String::from("Point"), String::from("x")),
+            ]
+        )
+    }
+
+    #[test]
+    fn check_anonymous_struct_literals() {
+        let program = "struct Point { x: int, y: int, }
+            struct Line { start: Point, end: Point, }
+            func flip(p: Point) -> Point { return {x: p.y, y: p.x,}; }";
+        assert_eq!(
+            check_source(&format!(
+                "{} let l: Line = {{start: {{x: 1, y: 2,}}, end: flip({{x: 3, y: 4,}}),}};
+                let q: Point = {{x: 7, y: 8,}} |> flip;
+                let ps: [Point] = [{{x: 5, y: 6,}}];",
+                program
+            )),
+            Ok(())
+        );
+        // they're checked against whatever struct they have to be
+        assert_eq!(
+            error_kinds(&format!(
+                "{} func f() -> Point {{ return {{z: \"bad\",}}; }}",
+                program
+            )),
+            vec![
+                TypeErrorKind::UnknownField(String::from("Point"), String::from("z")),
+                TypeErrorKind::MissingField(String::from("Point"), String::from("x")),
+                TypeErrorKind::MissingField(String::from("Point"), String::from("y")),
+            ]
+        );
+        assert_eq!(
+            error_kinds(&format!(
+                "{} let l: Line = {{start: {{x: 1, y: true,}}, end: {{x: 1,}},}};",
+                program
+            )),
+            vec![
+                mismatch(Type::Int, Type::Bool),
+                TypeErrorKind::MissingField(String::from("Point"), String::from("y")),
+            ]
+        );
+        // and without one there's no telling what they are
+        assert_eq!(
+            error_kinds(&format!(
+                "{} let p: int = {{x: 1,}}; print({{x: 1, y: 2,}});",
+                program
+            )),
+            vec![
+                TypeErrorKind::AnonymousStruct,
+                TypeErrorKind::AnonymousStruct
+            ]
+        );
+
+        // the name is filled in for whatever runs the program next
+        let mut ast =
+            parse(tokenize(&format!("{} print(flip({{x: 1, y: 2,}}));", program)).unwrap())
+                .unwrap();
+        assert_eq!(check(&mut ast).map(|_| ()), Ok(()));
+        match &ast.items[2] {
+            Item::Function(function) => match &function.block[0] {
+                Statement::Return(Exp::Struct(name, _), _) => assert_eq!(name, "Point"),
+                stmt => panic!("unexpected statement {:?}", stmt),
+            },
+            item => panic!("unexpected item {:?}", item),
+        }
+    }
+
+    #[test]
+    fn check_unknown_structs() {
+        assert_eq!(
+            error_kinds("let p: Point = {x: 1,}; func f(l: [Line]) {}"),
+            vec![
+                TypeErrorKind::UnknownStruct(String::from("Point")),
+                TypeErrorKind::UnknownStruct(String::from("Line")),
+            ]
+        );
+        assert_eq!(
+            error_kinds("struct Line { start: Point, }"),
+            vec![TypeErrorKind::UnknownStruct(String::from("Point"))]
+        )
+    }
+
+    #[test]
+    fn check_duplicate_structs() {
+        assert_eq!(
+            error_kinds("struct A { x: int, x: str, } struct A { }"),
+            vec![
+                TypeErrorKind::DuplicateField(String::from("A"), String::from("x")),
+                TypeErrorKind::DuplicateStruct(String::from("A")),
+            ]
+        )
+    }
+
+    #[test]
+    fn check_field_access() {
+        let program = "struct Point { x: int, y: float, }
+            struct Line { start: Point, end: Point, }
+            let a: Point = {x: 1, y: 2.0,};
+            let line: Line = {start: a, end: a,};";
+        assert_eq!(
+            check_source(&format!("{} let y: float = line.end.y * 2;", program)),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds(&format!("{} let y: int = line.end.y;", program)),
+            vec![mismatch(Type::Int, Type::Float)]
+        );
+        assert_eq!(
+            error_kinds(&format!("{} line.middle; a.x.y;", program)),
+            vec![
+                TypeErrorKind::UnknownField(String::from("Line"), String::from("middle")),
+                TypeErrorKind::NotAStruct(Type::Int),
+            ]
+        )
+    }
+
+    // ----------- operator tests ---------- \\
+    #[test]
+    fn check_operands() {
+        assert_eq!(check_source("let x: float = 1 + 2.5 * 2;"), Ok(()));
+        assert_eq!(
+            error_kinds("let x: bool = 1 && true;"),
+            vec![TypeErrorKind::BadOperands(
+                Operation::And,
+                Type::Int,
+                Type::Bool
+            )]
+        );
+        assert_eq!(
+            error_kinds("\"a\" < \"b\""),
+            vec![TypeErrorKind::BadOperands(
+                Operation::LessThan,
+                Type::Str,
+                Type::Str
+            )]
+        )
+    }
+
+    #[test]
+    fn check_unary() {
+        assert_eq!(
+            check_source("let x: float = -(1.5 * 2); let n: int = 1; let y: int = -n-1; !(1 < 2)"),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds("-\"a\"; -true; !1; !\"a\""),
+            vec![
+                TypeErrorKind::BadOperand(UnaryOp::Neg, Type::Str),
+                TypeErrorKind::BadOperand(UnaryOp::Neg, Type::Bool),
+                TypeErrorKind::BadOperand(UnaryOp::Not, Type::Int),
+                TypeErrorKind::BadOperand(UnaryOp::Not, Type::Str),
+            ]
+        )
+    }
+
+    #[test]
+    fn check_unknown_operands_dont_cascade() {
+        // only the undefined variable is reported, not the comparison
+        assert_eq!(
+            error_kinds("let x: bool = y < 1;"),
+            vec![TypeErrorKind::UndefinedVariable(String::from("y"))]
+        )
+    }
+
+    // ----------- function tests ---------- \\
+    #[test]
+    fn check_call_arguments() {
+        let program = "func add(a: int, b: int) -> int { return a + b; }
+            let x: int = 1;
+            let s: str = \"s\";";
+        assert_eq!(
+            error_kinds(&format!("{} add(x, s);", program)),
+            vec![mismatch(Type::Int, Type::Str)]
+        );
+        assert_eq!(
+            error_kinds(&format!("{} let y: str = add(x, x);", program)),
+            vec![mismatch(Type::Str, Type::Int)]
+        );
+        assert_eq!(
+            error_kinds(&format!("{} add(x);", program)),
+            vec![TypeErrorKind::WrongArgCount {
+                name: String::from("add"),
+                expected: 2,
+                found: 1,
+            }]
+        )
+    }
+
+    #[test]
+    fn check_not_callable() {
+        assert_eq!(
+            error_kinds("let x: int = 1; x(x);"),
+            vec![TypeErrorKind::NotCallable(String::from("x"), Type::Int)]
+        )
+    }
+
+    #[test]
+    fn check_return_type() {
+        let source = "func f(a: int) -> str {\n    return a;\n}";
+        assert_eq!(error_kinds(source), vec![mismatch(Type::Str, Type::Int)]);
+        assert_eq!(error_lines(source), vec![(2, 5)]);
+        assert_eq!(
+            check_source(source).unwrap_err()[0].to_string(),
+            "2:5: expected str but found int"
+        )
+    }
+
+    #[test]
+    fn check_missing_return() {
+        assert_eq!(
+            error_kinds("func f(a: int) -> int { if (a > 0) { return a; } }"),
+            vec![TypeErrorKind::MissingReturn(String::from("f"))]
+        );
+        assert_eq!(
+            check_source("func f(a: int) -> int { if (a > 0) { return a; } else { return 0; } }"),
+            Ok(())
+        );
+        // lambdas too, once their first return says they give something back
+        assert_eq!(
+            error_kinds("let f: (int -> int) = (a) { if (a > 0) { return a; } };"),
+            vec![TypeErrorKind::MissingReturn(String::from("lambda"))]
+        );
+        assert_eq!(
+            check_source("let f: (int -> int) = (a) { if (a > 0) { return a; } return 0; };"),
+            Ok(())
+        );
+        assert_eq!(
+            check_source("let f: (int -> void) = (a) { if (a > 0) { print(a); } };"),
+            Ok(())
+        )
+    }
+
+    #[test]
+    fn check_elif() {
+        let sign = "func sign(x: int) -> int {
+                if (x < 0) { return -1; } elif (x == 0) { return 0; }";
+        assert_eq!(
+            check_source(&format!("{} else {{ return 1; }} }}", sign)),
+            Ok(())
+        );
+        // without the else there's a path that doesn't return
+        assert_eq!(
+            error_kinds(&format!("{} }}", sign)),
+            vec![TypeErrorKind::MissingReturn(String::from("sign"))]
+        );
+        assert_eq!(
+            error_kinds("if (true) { } elif (1) { } elif (false) { }"),
+            vec![TypeErrorKind::ConditionNotBool(Type::Int)]
+        )
+    }
+
+    #[test]
+    fn check_recursion() {
+        assert_eq!(
+            check_source(
+                "func fib(n: int) -> int {
+                    if (n < 2) { return n; }
+                    let a: int = n - 1;
+                    let b: int = n - 2;
+                    return fib(a) + fib(b);
+                }"
+            ),
+            Ok(())
+        )
+    }
+
+    #[test]
+    fn check_functions_before_definition() {
+        assert_eq!(
+            check_source(
+                "let n: int = 7;
+                let result: bool = is_even(n);
+                func is_even(n: int) -> bool {
+                    if (n == 0) { return true; }
+                    let m: int = n - 1;
+                    return is_odd(m);
+                }
+                func is_odd(n: int) -> bool {
+                    if (n == 0) { return false; }
+                    let m: int = n - 1;
+                    return is_even(m);
+                }"
+            ),
+            Ok(())
+        );
+        // but functions inside a block still have to come first
+        assert_eq!(
+            error_kinds("func f() { g(); func g() {} }"),
+            vec![TypeErrorKind::UndefinedVariable(String::from("g"))]
+        )
+    }
+
+    #[test]
+    fn check_calls_before_globals() {
+        let called_before = |name: &str, global: &str| {
+            TypeErrorKind::CalledBeforeGlobal(String::from(name), String::from(global))
+        };
+        assert_eq!(
+            error_kinds("print(f()); let g: int = 41; func f() -> int { return g + 1; }"),
+            vec![called_before("f", "g")]
+        );
+        // through other functions, from a let's own value, or passed as a value
+        assert_eq!(
+            error_kinds(
+                "let g: int = h();
+                func h() -> int { return f(); }
+                func f() -> int { return g; }"
+            ),
+            vec![called_before("h", "g")]
+        );
+        assert_eq!(
+            error_kinds(
+                "let k: (int -> int) = f;
+                let g: int = 1;
+                func f(a: int) -> int { return g + a; }"
+            ),
+            vec![called_before("f", "g")]
+        );
+        assert_eq!(
+            error_lines(
+                "if (true) {
+                    print(f());
+                }
+                let g: int = 1;
+                func f() -> int { return g; }"
+            ),
+            vec![(2, 27)]
+        );
+
+        // once the global is defined, or when the function only has a local
+        assert_eq!(
+            check_source(
+                "let g: int = 41;
+                print(h());
+                func h() -> int { return f() * 2; }
+                func f() -> int { return g + 1; }"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check_source(
+                "print(f()); func f() -> int { let g: int = 1; return g; } let g: int = 2;"
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn check_function_scope() {
+        // parameters and locals don't leak out of the function
+        assert_eq!(
+            error_kinds("func f(a: int) { let b: int = a; } let c: int = a + b;"),
+            vec![
+                TypeErrorKind::UndefinedVariable(String::from("a")),
+                TypeErrorKind::UndefinedVariable(String::from("b")),
+            ]
+        )
+    }
+
+    #[test]
+    fn check_lambdas() {
+        assert_eq!(
+            error_kinds("let f: (int -> str) = (a) { return a * 2; };"),
+            vec![mismatch(
+                Type::HigherOrderFunction(vec![Type::Int], Box::new(Type::Str)),
+                Type::HigherOrderFunction(vec![Type::Int], Box::new(Type::Int)),
+            )]
+        );
+        assert_eq!(
+            error_kinds("let f: (int -> int) = (a) { return a; }; let s: str = \"s\"; f(s);"),
+            vec![mismatch(Type::Int, Type::Str)]
+        )
+    }
+
+    #[test]
+    fn check_lambda_params_from_expected_type() {
+        let apply = "func apply(f: (int) -> int, x: int) -> int { return f(x); }";
+        // a is an int because apply says so
+        assert_eq!(
+            error_kinds(&format!(
+                "{} print(apply((a) {{ return a + \"s\"; }}, 1));",
+                apply
+            )),
+            vec![TypeErrorKind::BadOperands(
+                Operation::Add,
+                Type::Int,
+                Type::Str
+            )]
+        );
+        assert_eq!(
+            error_kinds(
+                "func twice(x: int, f: (int) -> int) -> int { return f(f(x)); }
+                1 |> twice((a) { return !a; });"
+            ),
+            vec![TypeErrorKind::BadOperand(UnaryOp::Not, Type::Int)]
+        );
+        assert_eq!(
+            error_kinds(
+                "func make() -> (str) -> int { return (s) { return s * 2; }; }
+                let f: (int) -> int = (a) { return a; };
+                f = (b) { return b == \"b\"; };"
+            ),
+            vec![
+                TypeErrorKind::BadOperands(Operation::Multiply, Type::Str, Type::Int),
+                TypeErrorKind::BadOperands(Operation::Equal, Type::Int, Type::Str),
+            ]
+        );
+
+        // the type is filled in for whatever runs the program next
+        let mut program =
+            parse(tokenize(&format!("{} apply((a) {{ return a; }}, 1);", apply)).unwrap()).unwrap();
+        assert_eq!(check(&mut program).map(|_| ()), Ok(()));
+        match &program.items[1] {
+            Item::Statement(Statement::Exp(Exp::Call(_, args), _)) => match &args[0] {
+                Exp::HOF(params, _) => assert_eq!(params[0].var_type, Some(Type::Int)),
+                arg => panic!("unexpected arg {:?}", arg),
+            },
+            item => panic!("unexpected item {:?}", item),
+        }
+    }
+
+    #[test]
+    fn check_functions_as_values() {
+        let program = "func apply(f: (int, int) -> int, a: int, b: int) -> int {
+                return f(a, b);
+            }
+            func bad_adder(a: int, b: int,) -> int { return a + b; }
+            let times: (int, int) -> int = (a, b) { return a * b; };
+            let x: int = 2;
+            let y: int = 3;";
+        // named functions and lambdas go through the same parameter
+        assert_eq!(
+            check_source(&format!(
+                "{} let sum: int = apply(bad_adder, x, y); let product: int = apply(times, x, y);",
+                program
+            )),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds(&format!(
+                "{} func negate(a: int) -> int {{ return -a; }} apply(negate, x, y);",
+                program
+            )),
+            vec![mismatch(
+                Type::HigherOrderFunction(vec![Type::Int, Type::Int], Box::new(Type::Int)),
+                Type::HigherOrderFunction(vec![Type::Int], Box::new(Type::Int)),
+            )]
+        );
+        assert_eq!(
+            error_kinds(&format!("{} times(x);", program)),
+            vec![TypeErrorKind::WrongArgCount {
+                name: String::from("times"),
+                expected: 2,
+                found: 1,
+            }]
+        )
+    }
+
+    #[test]
+    fn check_expression_args_and_callees() {
+        let program = "func add(a: int, b: int) -> int { return a + b; }
+            func make_adder(n: int) -> (int) -> int {
+                return (a: int) { return a + n; };
+            }
+            struct Ops { double: (int) -> int, }
+            let ops: Ops = {double: (a) { return a * 2; },};";
+        assert_eq!(
+            check_source(&format!(
+                "{} let x: int = add(1 + 2, add(3, 4)) + make_adder(10)(5) + ops.double(21);",
+                program
+            )),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds(&format!("{} add(1.5, make_adder(1)(true));", program)),
+            vec![
+                mismatch(Type::Int, Type::Bool),
+                mismatch(Type::Int, Type::Float)
+            ]
+        );
+        assert_eq!(
+            error_kinds(&format!("{} make_adder(1)(2, 3); add(1, 2)(3);", program)),
+            vec![
+                TypeErrorKind::WrongArgCount {
+                    name: String::from("expression"),
+                    expected: 1,
+                    found: 2,
+                },
+                TypeErrorKind::NotCallable(String::from("expression"), Type::Int),
+            ]
+        )
+    }
+
+    #[test]
+    fn check_pipes() {
+        let program = "func add(a: int, b: int) -> int { return a + b; }
+            func shout(s: str) -> str { return s; }
+            let one: int = 1;";
+        assert_eq!(
+            check_source(&format!(
+                "{} let x: int = 3 |> add(one) |> add(one);",
+                program
+            )),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds(&format!("{} 3 |> shout;", program)),
+            vec![mismatch(Type::Str, Type::Int)]
+        );
+        assert_eq!(
+            error_kinds(&format!("{} let s: str = \"s\"; s |> add(one);", program)),
+            vec![mismatch(Type::Int, Type::Str)]
+        )
+    }
+
+    #[test]
+    fn check_builtins() {
+        assert_eq!(
+            check_source("let l: [int] = [1]; let n: int = l |> len; print(n);"),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds("let b: bool = true; len(b);"),
+            vec![TypeErrorKind::NoLength(Type::Bool)]
+        );
+        // they can only be called
+        assert_eq!(
+            error_kinds("let x: int = print;"),
+            vec![TypeErrorKind::BuiltinAsValue(String::from("print"))]
+        );
+        assert_eq!(
+            error_kinds(
+                "func apply(f: (str -> int), s: str) -> int { return f(s); } apply(len, \"ab\");"
+            ),
+            vec![TypeErrorKind::BuiltinAsValue(String::from("len"))]
+        )
+    }
+
+    // ----------- control flow tests ---------- \\
+    #[test]
+    fn check_conditions() {
+        assert_eq!(
+            error_kinds("let x: int = 1; if (x) { } while (x + 1) { }"),
+            vec![
+                TypeErrorKind::ConditionNotBool(Type::Int),
+                TypeErrorKind::ConditionNotBool(Type::Int),
+            ]
+        )
+    }
+
+    #[test]
+    fn check_for_item_type() {
+        assert_eq!(
+            error_kinds("for x in [1, 2] { let s: str = x; }"),
+            vec![mismatch(Type::Str, Type::Int)]
+        )
+    }
+
+    #[test]
+    fn check_for_over_expressions() {
+        assert_eq!(
+            check_source(
+                "struct Row { cells: [float], }
+                func load_data() -> [Row] { return [{cells: [1.0],}]; }
+                let total: float = 0.0;
+                for row in load_data() { for cell in row.cells { total = total + cell; } }"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            error_kinds(
+                "let rows: [[int]] = [[1]];
+                for row in rows { let s: str = row; }"
+            ),
+            vec![mismatch(Type::Str, list_of(Type::Int))]
+        );
+        assert_eq!(
+            error_kinds("let n: int = 3; for x in n { let y: int = x; } for x in m { }"),
+            vec![
+                TypeErrorKind::NotIterable(Type::Int),
+                TypeErrorKind::UndefinedVariable(String::from("m"))
+            ]
+        )
+    }
+
+    #[test]
+    fn check_collects_every_error() {
+        let errors = check_source(
+            "let x: int = \"a\";
+            func f(a: int) -> bool {
+                while (a) { }
+                return a;
+            }",
+        )
+        .unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "1:5: expected int but found str",
+                "3:17: condition must be a bool but found int",
+                "4:17: expected bool but found int",
+            ]
+        )
+    }
+}
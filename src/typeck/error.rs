@@ -0,0 +1,122 @@
+use crate::ast::{Operation, UnaryOp};
+use crate::span::Span;
+use crate::types::Type;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeErrorKind {
+    Mismatch {
+        expected: Type,
+        found: Type,
+    },
+    UndefinedVariable(String),
+    NotCallable(String, Type),
+    NotAVariable(String),
+    WrongArgCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    BadOperands(Operation, Type, Type),
+    BadOperand(UnaryOp, Type),
+    ConditionNotBool(Type),
+    NoLength(Type),
+    NotIterable(Type),
+    MissingReturn(String),
+    UnknownStruct(String),
+    DuplicateStruct(String),
+    // the struct and the field
+    DuplicateField(String, String),
+    MissingField(String, String),
+    UnknownField(String, String),
+    NotAStruct(Type),
+    // a struct literal without a name where no struct type is expected
+    AnonymousStruct,
+    // a function used before a global it reads is defined, and the global
+    CalledBeforeGlobal(String, String),
+    BuiltinAsValue(String),
+}
+
+// span is the statement an error is in, or the variable or field it's about
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+    pub span: Span,
+}
+
+impl fmt::Display for TypeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeErrorKind::Mismatch { expected, found } => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            TypeErrorKind::UndefinedVariable(name) => write!(f, "undefined variable {}", name),
+            TypeErrorKind::NotCallable(name, found) => {
+                write!(f, "{} is a {}, not a function", name, found)
+            }
+            TypeErrorKind::NotAVariable(name) => {
+                write!(f, "{} is a function and can't be assigned to", name)
+            }
+            TypeErrorKind::WrongArgCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} takes {} argument(s) but was given {}",
+                name, expected, found
+            ),
+            TypeErrorKind::BadOperands(op, left, right) => {
+                write!(f, "can't apply {} to {} and {}", op, left, right)
+            }
+            TypeErrorKind::BadOperand(op, found) => write!(f, "can't apply {} to {}", op, found),
+            TypeErrorKind::ConditionNotBool(found) => {
+                write!(f, "condition must be a bool but found {}", found)
+            }
+            TypeErrorKind::NoLength(found) => {
+                write!(f, "len takes a list or str but found {}", found)
+            }
+            TypeErrorKind::NotIterable(found) => {
+                write!(f, "for loops need a list but found {}", found)
+            }
+            TypeErrorKind::MissingReturn(name) => {
+                write!(f, "{} doesn't return a value on every path", name)
+            }
+            TypeErrorKind::UnknownStruct(name) => write!(f, "no struct named {}", name),
+            TypeErrorKind::DuplicateStruct(name) => {
+                write!(f, "struct {} is declared more than once", name)
+            }
+            TypeErrorKind::DuplicateField(name, field) => {
+                write!(f, "field {} is given more than once in {}", field, name)
+            }
+            TypeErrorKind::MissingField(name, field) => {
+                write!(f, "{} is missing its {} field", name, field)
+            }
+            TypeErrorKind::UnknownField(name, field) => {
+                write!(f, "{} has no field named {}", name, field)
+            }
+            TypeErrorKind::NotAStruct(found) => {
+                write!(f, "can't access a field of a {}", found)
+            }
+            TypeErrorKind::AnonymousStruct => {
+                write!(f, "struct literal needs a name where no struct is expected")
+            }
+            TypeErrorKind::BuiltinAsValue(name) => {
+                write!(f, "{} is a builtin and can only be called", name)
+            }
+            TypeErrorKind::CalledBeforeGlobal(name, global) => {
+                write!(
+                    f,
+                    "{} can be called before global {} is defined",
+                    name, global
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.span.line, self.span.column, self.kind)
+    }
+}
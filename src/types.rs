@@ -1,13 +1,134 @@
-#[derive(Debug, Eq, PartialEq)]
+use std::fmt;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum Type {
     Void,
     Int,
+    BigInt,
+    Float,
     Str,
     Bool,
     Struct(String),
-    HigherOrderFunction(Box<Type>, Box<Type>),
-    ListInt,
-    ListStr,
-    ListBool,
-    ListStruct(String),
+    // the parameter types and the output type
+    HigherOrderFunction(Vec<Type>, Box<Type>),
+    List(Box<Type>),
+}
+
+impl Type {
+    // the type of the items in a list type, or None if this isn't a list
+    pub fn element_type(&self) -> Option<Type> {
+        match self {
+            Type::List(element) => Some((**element).clone()),
+            _ => None,
+        }
+    }
+}
+
+// every struct declared in a program, with its fields in declaration order
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct StructRegistry {
+    structs: Vec<(String, Vec<(String, Type)>)>,
+}
+
+impl StructRegistry {
+    pub fn new() -> StructRegistry {
+        StructRegistry::default()
+    }
+
+    // returns false, leaving the first declaration alone, if the name is taken
+    pub fn declare(&mut self, name: &str, fields: Vec<(String, Type)>) -> bool {
+        if self.fields(name).is_some() {
+            return false;
+        }
+        self.structs.push((String::from(name), fields));
+        true
+    }
+
+    pub fn fields(&self, name: &str) -> Option<&[(String, Type)]> {
+        self.structs
+            .iter()
+            .find(|(struct_name, _)| struct_name == name)
+            .map(|(_, fields)| &fields[..])
+    }
+
+    pub fn field_type(&self, name: &str, field: &str) -> Option<&Type> {
+        self.fields(name)?
+            .iter()
+            .find(|(field_name, _)| field_name == field)
+            .map(|(_, field_type)| field_type)
+    }
+
+    // structs in the order they were declared
+    pub fn iter(&self) -> impl Iterator<Item = &(String, Vec<(String, Type)>)> {
+        self.structs.iter()
+    }
+}
+
+// types print the way they are written in Pipes source
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Void => write!(f, "void"),
+            Type::Int => write!(f, "int"),
+            Type::BigInt => write!(f, "bigint"),
+            Type::Float => write!(f, "float"),
+            Type::Str => write!(f, "str"),
+            Type::Bool => write!(f, "bool"),
+            Type::Struct(name) => write!(f, "{}", name),
+            Type::HigherOrderFunction(params, output) => {
+                let params: Vec<String> = params.iter().map(Type::to_string).collect();
+                write!(f, "({}) -> {}", params.join(", "), output)
+            }
+            Type::List(element) => write!(f, "[{}]", element),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn list(element: Type) -> Type {
+        Type::List(Box::new(element))
+    }
+
+    #[test]
+    fn list_element_type() {
+        assert_eq!(list(Type::Str).element_type(), Some(Type::Str));
+        assert_eq!(list(list(Type::Int)).element_type(), Some(list(Type::Int)));
+        assert_eq!(Type::Int.element_type(), None)
+    }
+
+    #[test]
+    fn struct_registry() {
+        let mut registry = StructRegistry::new();
+        let point = vec![
+            (String::from("x"), Type::Int),
+            (String::from("y"), Type::Float),
+        ];
+        assert!(registry.declare("Point", point.clone()));
+        assert!(!registry.declare("Point", vec![]));
+
+        assert_eq!(registry.fields("Point"), Some(&point[..]));
+        assert_eq!(registry.field_type("Point", "y"), Some(&Type::Float));
+        assert_eq!(registry.field_type("Point", "z"), None);
+        assert_eq!(registry.fields("Line"), None)
+    }
+
+    #[test]
+    fn display_types() {
+        let hof = Type::HigherOrderFunction(vec![Type::Int], Box::new(list(Type::Float)));
+        assert_eq!(hof.to_string(), "(int) -> [float]");
+        assert_eq!(
+            list(list(Type::Struct(String::from("Point")))).to_string(),
+            "[[Point]]"
+        );
+        assert_eq!(list(hof.clone()).to_string(), "[(int) -> [float]]");
+        let adder = Type::HigherOrderFunction(vec![Type::Int, Type::Int], Box::new(Type::Int));
+        assert_eq!(adder.to_string(), "(int, int) -> int");
+        let apply = Type::HigherOrderFunction(vec![hof, Type::Int], Box::new(Type::Void));
+        assert_eq!(apply.to_string(), "((int) -> [float], int) -> void");
+        let thunk = Type::HigherOrderFunction(vec![], Box::new(Type::Bool));
+        assert_eq!(thunk.to_string(), "() -> bool")
+    }
 }